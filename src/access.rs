@@ -0,0 +1,131 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use windows::Win32::Foundation::{
+    GetHandleInformation, HANDLE_FLAG_INHERIT, HANDLE_FLAG_PROTECT_FROM_CLOSE,
+};
+
+use crate::handle::granted_access;
+use crate::{Job, JobError};
+
+/// A set of access rights to a job object. Rights can be combined using `|`, for example
+/// `JobAccess::ASSIGN_PROCESS | JobAccess::TERMINATE`.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/procthread/job-object-security-and-access-rights).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct JobAccess(u32);
+
+impl JobAccess {
+    /// No access rights.
+    pub const NONE: Self = Self(0);
+    /// Assign processes to the job (`JOB_OBJECT_ASSIGN_PROCESS`).
+    pub const ASSIGN_PROCESS: Self = Self(0x0001);
+    /// Set the limits and other information of the job (`JOB_OBJECT_SET_ATTRIBUTES`).
+    pub const SET_ATTRIBUTES: Self = Self(0x0002);
+    /// Query the information of the job, and whether processes are in it (`JOB_OBJECT_QUERY`).
+    pub const QUERY: Self = Self(0x0004);
+    /// Terminate all the processes of the job (`JOB_OBJECT_TERMINATE`).
+    pub const TERMINATE: Self = Self(0x0008);
+    /// Set the security limits of the job (`JOB_OBJECT_SET_SECURITY_ATTRIBUTES`, obsolete).
+    pub const SET_SECURITY_ATTRIBUTES: Self = Self(0x0010);
+    /// Impersonate the job (`JOB_OBJECT_IMPERSONATE`).
+    pub const IMPERSONATE: Self = Self(0x0020);
+    /// Delete the object (`DELETE`).
+    pub const DELETE: Self = Self(0x0001_0000);
+    /// Read the security descriptor of the object (`READ_CONTROL`).
+    pub const READ_CONTROL: Self = Self(0x0002_0000);
+    /// Modify the DACL of the object (`WRITE_DAC`).
+    pub const WRITE_DAC: Self = Self(0x0004_0000);
+    /// Change the owner of the object (`WRITE_OWNER`).
+    pub const WRITE_OWNER: Self = Self(0x0008_0000);
+    /// Wait on the job (`SYNCHRONIZE`).
+    pub const SYNCHRONIZE: Self = Self(0x0010_0000);
+    /// All the access rights to a job (`JOB_OBJECT_ALL_ACCESS`).
+    pub const ALL: Self = Self(0x001F_003F);
+
+    /// Return the set of the rights whose bits are set in `bits`.
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Return the raw access mask.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Return `true` if all the rights of `other` are in the set.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for JobAccess {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for JobAccess {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// The access rights and flags of a handle to a job (see `Job::granted_access`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JobHandleInfo {
+    /// The access rights granted to the handle.
+    pub access: JobAccess,
+    /// Whether the handle is inherited by child processes (`HANDLE_FLAG_INHERIT`).
+    pub inherit: bool,
+    /// Whether the handle is protected from being closed (`HANDLE_FLAG_PROTECT_FROM_CLOSE`).
+    pub protect_from_close: bool,
+}
+
+impl Job {
+    /// Return the access rights granted to the handle of the job, and its flags.
+    /// This is useful for verifying the capabilities of a job handle which was received
+    /// from elsewhere (for example, inherited or duplicated into the current process)
+    /// before attempting to terminate it or assign processes to it.
+    pub fn granted_access(&self) -> Result<JobHandleInfo, JobError> {
+        let access = granted_access(self.handle).map_err(|source| JobError::GetInfoFailed {
+            class: "ObjectBasicInformation",
+            source,
+        })?;
+
+        let mut flags = 0;
+        unsafe { GetHandleInformation(self.handle, &mut flags) }.map_err(|e| {
+            JobError::GetInfoFailed {
+                class: "HandleInformation",
+                source: e.into(),
+            }
+        })?;
+
+        Ok(JobHandleInfo {
+            access: JobAccess(access),
+            inherit: flags & HANDLE_FLAG_INHERIT.0 != 0,
+            protect_from_close: flags & HANDLE_FLAG_PROTECT_FROM_CLOSE.0 != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Job, JobAccess};
+
+    #[test]
+    fn granted_access() {
+        let job = Job::create().unwrap();
+
+        let info = job.granted_access().unwrap();
+        assert_eq!(info.access, JobAccess::ALL);
+        assert!(info
+            .access
+            .contains(JobAccess::ASSIGN_PROCESS | JobAccess::TERMINATE));
+        assert!(!info.inherit);
+        assert!(!info.protect_from_close);
+    }
+}
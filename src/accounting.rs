@@ -0,0 +1,200 @@
+use std::time::Duration;
+
+use windows::Win32::System::JobObjects::{
+    JOBOBJECT_BASIC_ACCOUNTING_INFORMATION, JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION,
+};
+
+use crate::utils::duration_from_100ns;
+use crate::{IoCounters, Job, JobError};
+
+/// Contains basic accounting information for a job object.
+#[derive(Debug, Clone, Copy)]
+pub struct BasicAccountingInfo(pub(crate) JOBOBJECT_BASIC_ACCOUNTING_INFORMATION);
+
+impl BasicAccountingInfo {
+    /// The total amount of user-mode execution time for all active processes associated with the job,
+    /// as well as all terminated processes no longer associated with the job.
+    pub fn total_user_time(&self) -> Duration {
+        duration_from_100ns(self.0.TotalUserTime)
+    }
+
+    /// The total amount of kernel-mode execution time for all active processes associated with the job,
+    /// as well as all terminated processes no longer associated with the job.
+    pub fn total_kernel_time(&self) -> Duration {
+        duration_from_100ns(self.0.TotalKernelTime)
+    }
+
+    /// The total amount of user-mode execution time for all active processes associated with the job
+    /// (as well as all terminated processes no longer associated with the job)
+    /// since the last call that set a per-job user-mode time limit.
+    pub fn this_period_total_user_time(&self) -> Duration {
+        duration_from_100ns(self.0.ThisPeriodTotalUserTime)
+    }
+
+    /// The total amount of kernel-mode execution time for all active processes associated with the job
+    /// (as well as all terminated processes no longer associated with the job)
+    /// since the last call that set a per-job user-mode time limit.
+    pub fn this_period_total_kernel_time(&self) -> Duration {
+        duration_from_100ns(self.0.ThisPeriodTotalKernelTime)
+    }
+
+    /// The total number of page faults encountered by all active processes associated with the job,
+    /// as well as all terminated processes no longer associated with the job.
+    pub fn total_page_fault_count(&self) -> u32 {
+        self.0.TotalPageFaultCount
+    }
+
+    /// The total number of processes associated with the job during its lifetime,
+    /// including those that are no longer associated with it.
+    pub fn total_processes(&self) -> u32 {
+        self.0.TotalProcesses
+    }
+
+    /// The total number of processes currently associated with the job.
+    pub fn active_processes(&self) -> u32 {
+        self.0.ActiveProcesses
+    }
+
+    /// The total number of processes terminated because of a limit violation.
+    pub fn total_terminated_processes(&self) -> u32 {
+        self.0.TotalTerminatedProcesses
+    }
+}
+
+/// Contains basic accounting and I/O accounting information for a job object.
+#[derive(Debug, Clone, Copy)]
+pub struct BasicAndIoAccountingInfo(pub(crate) JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION);
+
+impl BasicAndIoAccountingInfo {
+    /// The basic accounting information of the job.
+    pub fn basic(&self) -> BasicAccountingInfo {
+        BasicAccountingInfo(self.0.BasicInfo)
+    }
+
+    /// The I/O accounting information of the job.
+    pub fn io(&self) -> IoCounters {
+        self.0.IoInfo.into()
+    }
+}
+
+/// The cumulative CPU time used by the processes of a job.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CpuTime {
+    /// The total amount of user-mode execution time.
+    pub user: Duration,
+    /// The total amount of kernel-mode execution time.
+    pub kernel: Duration,
+}
+
+impl CpuTime {
+    /// The sum of the user-mode and kernel-mode execution time.
+    pub fn total(&self) -> Duration {
+        self.user + self.kernel
+    }
+}
+
+impl Job {
+    /// Return basic accounting information for a job object.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_basic_accounting_information).
+    pub fn query_basic_accounting_info(&self) -> Result<BasicAccountingInfo, JobError> {
+        self.query_info()
+    }
+
+    /// Return basic accounting and I/O accounting information for a job object.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_basic_and_io_accounting_information).
+    pub fn query_basic_and_io_accounting_info(&self) -> Result<BasicAndIoAccountingInfo, JobError> {
+        self.query_info()
+    }
+
+    /// Return `true` if there are no active processes in the job.
+    /// Unlike `query_process_id_list`, this does not allocate.
+    pub fn is_empty(&self) -> Result<bool, JobError> {
+        Ok(self.query_basic_accounting_info()?.active_processes() == 0)
+    }
+
+    /// Return the total number of processes associated with the job during its lifetime.
+    pub fn total_processes(&self) -> Result<u32, JobError> {
+        Ok(self.query_basic_accounting_info()?.total_processes())
+    }
+
+    /// Return the total number of processes of the job which were terminated because of a limit violation.
+    pub fn terminated_processes(&self) -> Result<u32, JobError> {
+        Ok(self
+            .query_basic_accounting_info()?
+            .total_terminated_processes())
+    }
+
+    /// Return the total number of page faults encountered by all the processes of the job,
+    /// including those that are no longer associated with it.
+    pub fn page_fault_count(&self) -> Result<u32, JobError> {
+        Ok(self.query_basic_accounting_info()?.total_page_fault_count())
+    }
+
+    /// Return the cumulative CPU time used by all the processes of the job,
+    /// including those that are no longer associated with it.
+    pub fn cpu_time(&self) -> Result<CpuTime, JobError> {
+        let info = self.query_basic_accounting_info()?;
+
+        Ok(CpuTime {
+            user: info.total_user_time(),
+            kernel: info.total_kernel_time(),
+        })
+    }
+
+    /// Return the total I/O operations and bytes of all the processes of the job,
+    /// including those that are no longer associated with it.
+    pub fn io_totals(&self) -> Result<IoCounters, JobError> {
+        Ok(self.query_basic_and_io_accounting_info()?.io())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Job;
+
+    #[test]
+    fn basic_accounting() {
+        let job = Job::create().unwrap();
+
+        let info = job.query_basic_accounting_info().unwrap();
+        assert_eq!(info.active_processes(), 0);
+        assert_eq!(info.total_processes(), 0);
+        assert!(job.is_empty().unwrap());
+
+        job.assign_current_process().unwrap();
+        assert!(!job.is_empty().unwrap());
+        assert!(job.total_processes().unwrap() >= 1);
+        assert_eq!(job.terminated_processes().unwrap(), 0);
+
+        assert!(job.page_fault_count().unwrap() >= info.total_page_fault_count());
+
+        let info = job.query_basic_and_io_accounting_info().unwrap();
+        assert!(info.basic().total_processes() >= 1);
+        assert!(job.io_totals().unwrap().read_operations >= info.io().read_operations);
+
+        let info = job.query_basic_accounting_info().unwrap();
+        assert!(info.active_processes() >= 1);
+        assert!(info.total_processes() >= 1);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn cpu_time() {
+        use std::{process::Command, time::Duration};
+
+        let job = Job::create().unwrap();
+        assert_eq!(job.cpu_time().unwrap().total(), Duration::ZERO);
+
+        // Busy-loop, so the child uses measurable CPU time.
+        let mut child = Command::new("cmd")
+            .args(["/C", "for /L %i in (1,1,300000) do @rem"])
+            .spawn()
+            .unwrap();
+        job.assign_child(&child).unwrap();
+        assert!(child.wait().unwrap().success());
+
+        assert!(job.total_processes().unwrap() >= 1);
+        assert!(job.cpu_time().unwrap().total() > Duration::ZERO);
+    }
+}
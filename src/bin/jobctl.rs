@@ -0,0 +1,220 @@
+//! A small command line tool for managing named job objects, built on the public `win32job` API.
+//!
+//! ```text
+//! jobctl create <name> [limits]               Create a named job, and keep it alive until Ctrl+C
+//! jobctl run <name> [limits] -- <cmd> [args]  Run a command inside a new named job, and wait for it
+//! jobctl limit <name> [limits]                Apply limits to an existing job
+//! jobctl assign <name> <pid>...               Assign processes to an existing job
+//! jobctl query <name>                         Print the accounting information and processes of a job
+//! jobctl terminate <name> [exit-code]         Terminate all the processes of a job
+//!
+//! Limits:
+//!     --working-set <min>:<max>   Working set limits, in bytes
+//!     --priority <class>          One of idle, below-normal, normal, above-normal, high, realtime
+//!     --scheduling-class <0-9>    Scheduling class
+//!     --affinity <mask>           Processor affinity mask (hex with a 0x prefix, or decimal)
+//!     --cpus <list>               Processor affinity, as a list of processors and ranges (like 0-3,8-11)
+//!     --cpu-rate <percent>        Hard cap on CPU usage, in percent of the whole machine
+//!     --kill-on-close             Kill all processes when the last handle to the job is closed
+//!     --breakaway-ok              Allow child processes to break away from the job
+//! ```
+use std::{env, process, thread};
+
+use win32job::{
+    CpuRateControlInfo, CpuSet, ExtendedLimitInfo, Job, JobCommand, PriorityClass, SchedulingClass,
+};
+
+type Error = Box<dyn std::error::Error>;
+
+const USAGE: &str = "usage: jobctl <create|run|limit|assign|query|terminate> <name> [args]";
+
+#[derive(Debug, Default)]
+struct Limits {
+    extended: Option<ExtendedLimitInfo>,
+    cpu_rate: Option<CpuRateControlInfo>,
+}
+
+impl Limits {
+    /// Parse the limit flags from `args`, and return the remaining arguments.
+    fn parse(args: Vec<String>) -> Result<(Self, Vec<String>), Error> {
+        let mut limits = Limits::default();
+        let mut rest = vec![];
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for `{}`", arg))
+            };
+
+            match arg.as_str() {
+                "--working-set" => {
+                    let value = value()?;
+                    let (min, max) = value
+                        .split_once(':')
+                        .ok_or("expected `--working-set <min>:<max>`")?;
+                    limits
+                        .extended()
+                        .limit_working_memory(min.parse()?, max.parse()?);
+                }
+                "--priority" => {
+                    let priority_class = parse_priority_class(&value()?)?;
+                    limits.extended().limit_priority_class(priority_class);
+                }
+                "--scheduling-class" => {
+                    let scheduling_class = SchedulingClass::new(value()?.parse()?)?;
+                    limits.extended().limit_scheduling_class(scheduling_class);
+                }
+                "--affinity" => {
+                    let value = value()?;
+                    let affinity = match value.strip_prefix("0x") {
+                        Some(hex) => usize::from_str_radix(hex, 16)?,
+                        None => value.parse()?,
+                    };
+                    limits.extended().limit_affinity(affinity);
+                }
+                "--cpus" => {
+                    let cpus: CpuSet = value()?.parse()?;
+                    limits.extended().limit_affinity(cpus.mask());
+                }
+                "--cpu-rate" => {
+                    let percent: f64 = value()?.parse()?;
+                    limits
+                        .cpu_rate
+                        .get_or_insert_with(CpuRateControlInfo::new)
+                        .limit_hard_cap_machine_percent(percent);
+                }
+                "--kill-on-close" => {
+                    limits.extended().limit_kill_on_job_close();
+                }
+                "--breakaway-ok" => {
+                    limits.extended().limit_breakaway_ok();
+                }
+                "--" => {
+                    rest.extend(args.by_ref());
+                }
+                _ if arg.starts_with("--") => return Err(format!("unknown flag `{}`", arg).into()),
+                _ => rest.push(arg),
+            }
+        }
+
+        Ok((limits, rest))
+    }
+
+    fn extended(&mut self) -> &mut ExtendedLimitInfo {
+        self.extended.get_or_insert_with(ExtendedLimitInfo::new)
+    }
+
+    fn apply(&self, job: &Job) -> Result<(), Error> {
+        if let Some(info) = &self.extended {
+            job.set_extended_limit_info(info)?;
+        }
+
+        if let Some(info) = &self.cpu_rate {
+            job.set_cpu_rate_control_info(info)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_priority_class(value: &str) -> Result<PriorityClass, Error> {
+    let priority_class = match value {
+        "idle" => PriorityClass::Idle,
+        "below-normal" => PriorityClass::BelowNormal,
+        "normal" => PriorityClass::Normal,
+        "above-normal" => PriorityClass::AboveNormal,
+        "high" => PriorityClass::High,
+        "realtime" => PriorityClass::Realtime,
+        _ => return Err(format!("unknown priority class `{}`", value).into()),
+    };
+
+    Ok(priority_class)
+}
+
+fn query(job: &Job) -> Result<(), Error> {
+    let info = job.query_basic_accounting_info()?;
+
+    println!("total user time:      {:?}", info.total_user_time());
+    println!("total kernel time:    {:?}", info.total_kernel_time());
+    println!("total page faults:    {}", info.total_page_fault_count());
+    println!("total processes:      {}", info.total_processes());
+    println!("active processes:     {}", info.active_processes());
+    println!(
+        "terminated processes: {}",
+        info.total_terminated_processes()
+    );
+
+    for pid in job.query_process_id_list()? {
+        println!("process:              {}", pid);
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<i32, Error> {
+    let mut args = env::args().skip(1);
+    let command = args.next().ok_or(USAGE)?;
+    let name = args.next().ok_or(USAGE)?;
+    let (limits, rest) = Limits::parse(args.collect())?;
+
+    match command.as_str() {
+        "create" => {
+            let job = Job::create_named(&name)?;
+            limits.apply(&job)?;
+
+            println!("created job `{}`, press Ctrl+C to release it", name);
+            loop {
+                thread::park();
+            }
+        }
+        "run" => {
+            let (program, program_args) = rest.split_first().ok_or(USAGE)?;
+
+            let job = Job::create_named(&name)?;
+            limits.apply(&job)?;
+
+            let mut child = JobCommand::new(&job, program).args(program_args).spawn()?;
+
+            let status = child.wait()?;
+            query(&job)?;
+
+            return Ok(status.code().unwrap_or(1));
+        }
+        "limit" => {
+            let job = Job::open(&name)?;
+            limits.apply(&job)?;
+        }
+        "assign" => {
+            let job = Job::open(&name)?;
+            for pid in rest {
+                job.assign_process_by_pid(pid.parse::<u32>()?)?;
+            }
+        }
+        "query" => {
+            let job = Job::open(&name)?;
+            query(&job)?;
+        }
+        "terminate" => {
+            let job = Job::open(&name)?;
+            let exit_code = match rest.first() {
+                Some(exit_code) => exit_code.parse()?,
+                None => 1,
+            };
+            job.terminate(exit_code)?;
+        }
+        _ => return Err(USAGE.into()),
+    }
+
+    Ok(0)
+}
+
+fn main() {
+    match run() {
+        Ok(code) => process::exit(code),
+        Err(err) => {
+            eprintln!("jobctl: {}", err);
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,14 @@
+//! A helper stub for `win32job::Janitor`, which can be shipped alongside an application
+//! and used with `Janitor::spawn_with`.
+//!
+//! It waits for the process which spawned it to exit, and then exits, closing its handle to the job.
+use std::process;
+
+use win32job::Janitor;
+
+fn main() {
+    Janitor::run_if_helper();
+
+    eprintln!("win32job-janitor should only be spawned by `win32job::Janitor`");
+    process::exit(1);
+}
@@ -0,0 +1,266 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        mpsc::{RecvError, RecvTimeoutError, TryRecvError},
+        Arc, Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::JobNotification;
+
+/// What a bounded subscription (see `monitor.subscribe_bounded`) does when it is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Block the monitor thread until the receiver makes room.
+    /// Note that this delays the notifications of all the other subscribers.
+    Block,
+    /// Drop the oldest queued notification to make room for the new one.
+    DropOldest,
+    /// Drop new `NewProcess` notifications, so bursts of process creations are only counted.
+    /// Other notifications are always queued, dropping the oldest queued `NewProcess`
+    /// notification to make room (or blocking, if there is none).
+    CoalesceNewProcess,
+}
+
+#[derive(Debug)]
+struct Queue {
+    state: Mutex<QueueState>,
+    changed: Condvar,
+    capacity: usize,
+    policy: OverflowPolicy,
+}
+
+#[derive(Debug)]
+struct QueueState {
+    notifications: VecDeque<JobNotification>,
+    dropped: u64,
+    sender_alive: bool,
+    receiver_alive: bool,
+}
+
+/// Create a bounded channel which can hold up to `capacity` notifications.
+pub(crate) fn channel(capacity: usize, policy: OverflowPolicy) -> (Sender, BoundedReceiver) {
+    let capacity = capacity.max(1);
+
+    let queue = Arc::new(Queue {
+        state: Mutex::new(QueueState {
+            notifications: VecDeque::with_capacity(capacity),
+            dropped: 0,
+            sender_alive: true,
+            receiver_alive: true,
+        }),
+        changed: Condvar::new(),
+        capacity,
+        policy,
+    });
+
+    (
+        Sender {
+            queue: queue.clone(),
+        },
+        BoundedReceiver { queue },
+    )
+}
+
+#[derive(Debug)]
+pub(crate) struct Sender {
+    queue: Arc<Queue>,
+}
+
+impl Sender {
+    /// Queue `notification` according to the overflow policy.
+    /// Return `false` if the receiver is gone.
+    pub(crate) fn send(&self, notification: JobNotification) -> bool {
+        let queue = &self.queue;
+        let mut state = queue.state.lock().unwrap();
+
+        loop {
+            if !state.receiver_alive {
+                return false;
+            }
+
+            if state.notifications.len() < queue.capacity {
+                break;
+            }
+
+            match queue.policy {
+                OverflowPolicy::Block => {}
+                OverflowPolicy::DropOldest => {
+                    state.notifications.pop_front();
+                    state.dropped += 1;
+                    break;
+                }
+                OverflowPolicy::CoalesceNewProcess => {
+                    if matches!(notification, JobNotification::NewProcess(_)) {
+                        state.dropped += 1;
+                        return true;
+                    }
+
+                    let oldest = state
+                        .notifications
+                        .iter()
+                        .position(|queued| matches!(queued, JobNotification::NewProcess(_)));
+
+                    if let Some(oldest) = oldest {
+                        state.notifications.remove(oldest);
+                        state.dropped += 1;
+                        break;
+                    }
+                }
+            }
+
+            state = queue.changed.wait(state).unwrap();
+        }
+
+        state.notifications.push_back(notification);
+        queue.changed.notify_all();
+
+        true
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().sender_alive = false;
+        self.queue.changed.notify_all();
+    }
+}
+
+/// The receiving side of a bounded subscription, returned by `monitor.subscribe_bounded`.
+///
+/// The methods mirror those of `std::sync::mpsc::Receiver`: receiving fails once the
+/// monitor is gone and all the queued notifications were received.
+#[derive(Debug)]
+pub struct BoundedReceiver {
+    queue: Arc<Queue>,
+}
+
+impl BoundedReceiver {
+    /// Block until a notification is available.
+    pub fn recv(&self) -> Result<JobNotification, RecvError> {
+        self.recv_deadline(None).map_err(|_| RecvError)
+    }
+
+    /// Block until a notification is available, or until `timeout` elapses.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<JobNotification, RecvTimeoutError> {
+        self.recv_deadline(Some(Instant::now() + timeout))
+    }
+
+    /// Return a queued notification, without blocking.
+    pub fn try_recv(&self) -> Result<JobNotification, TryRecvError> {
+        let mut state = self.queue.state.lock().unwrap();
+
+        match state.notifications.pop_front() {
+            Some(notification) => {
+                self.queue.changed.notify_all();
+                Ok(notification)
+            }
+            None if state.sender_alive => Err(TryRecvError::Empty),
+            None => Err(TryRecvError::Disconnected),
+        }
+    }
+
+    /// Return the number of notifications which were dropped because the channel was full.
+    pub fn dropped(&self) -> u64 {
+        self.queue.state.lock().unwrap().dropped
+    }
+
+    fn recv_deadline(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<JobNotification, RecvTimeoutError> {
+        let mut state = self.queue.state.lock().unwrap();
+
+        loop {
+            if let Some(notification) = state.notifications.pop_front() {
+                self.queue.changed.notify_all();
+                return Ok(notification);
+            }
+
+            if !state.sender_alive {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            state = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+
+                    if remaining.is_zero() {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+
+                    self.queue.changed.wait_timeout(state, remaining).unwrap().0
+                }
+                None => self.queue.changed.wait(state).unwrap(),
+            };
+        }
+    }
+}
+
+impl Drop for BoundedReceiver {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().receiver_alive = false;
+        self.queue.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::TryRecvError;
+
+    use super::channel;
+    use crate::{JobNotification, OverflowPolicy, Pid};
+
+    #[test]
+    fn drop_oldest() {
+        let (sender, receiver) = channel(2, OverflowPolicy::DropOldest);
+
+        for pid in 1..=3 {
+            assert!(sender.send(JobNotification::NewProcess(Pid::new(pid))));
+        }
+
+        assert_eq!(
+            receiver.try_recv(),
+            Ok(JobNotification::NewProcess(Pid::new(2)))
+        );
+        assert_eq!(
+            receiver.try_recv(),
+            Ok(JobNotification::NewProcess(Pid::new(3)))
+        );
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(receiver.dropped(), 1);
+
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn coalesce_new_process() {
+        let (sender, receiver) = channel(2, OverflowPolicy::CoalesceNewProcess);
+
+        assert!(sender.send(JobNotification::NewProcess(Pid::new(1))));
+        assert!(sender.send(JobNotification::NewProcess(Pid::new(2))));
+        assert!(sender.send(JobNotification::NewProcess(Pid::new(3))));
+        assert!(sender.send(JobNotification::ExitProcess(Pid::new(1))));
+
+        assert_eq!(
+            receiver.try_recv(),
+            Ok(JobNotification::NewProcess(Pid::new(2)))
+        );
+        assert_eq!(
+            receiver.try_recv(),
+            Ok(JobNotification::ExitProcess(Pid::new(1)))
+        );
+        assert_eq!(receiver.dropped(), 2);
+    }
+
+    #[test]
+    fn receiver_gone() {
+        let (sender, receiver) = channel(1, OverflowPolicy::Block);
+
+        assert!(sender.send(JobNotification::ActiveProcessZero));
+        drop(receiver);
+        assert!(!sender.send(JobNotification::ActiveProcessZero));
+    }
+}
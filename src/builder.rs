@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+use crate::{ExtendedLimitInfo, Job, JobError, PriorityClass, SchedulingClass};
+
+/// A builder of the extended limits of a job, which can only be consumed by applying it:
+/// either to an existing job using `apply_to`, or to a new one using `build_job`.
+///
+/// Unlike configuring an `ExtendedLimitInfo` and then calling `job.set_extended_limit_info`,
+/// the configured limits can only be inspected once they were applied (see `AppliedLimits`).
+/// Note that dropping a builder without applying it is only caught by the `unused_must_use` lint
+/// (the builder is `#[must_use]`), which warns by default but can be allowed: it is not a
+/// compile-time guarantee.
+///
+/// ```edition2021
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let job = LimitsBuilder::new()
+///     .job_memory(512 * 1024 * 1024)
+///     .active_processes(16)
+///     .priority_class(PriorityClass::BelowNormal)
+///     .build_job()?;
+///
+/// let applied = LimitsBuilder::new().kill_on_job_close().apply_to(&job)?;
+/// assert!(applied.info().kill_on_job_close());
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+#[must_use = "the limits are not applied until `apply_to` or `build_job` is called"]
+pub struct LimitsBuilder {
+    info: ExtendedLimitInfo,
+}
+
+impl LimitsBuilder {
+    /// Return a builder without any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from the limits of `info` (for example, as queried from another job).
+    pub fn from_info(info: ExtendedLimitInfo) -> Self {
+        LimitsBuilder { info }
+    }
+
+    /// See `ExtendedLimitInfo::limit_working_memory`.
+    pub fn working_memory(mut self, min: usize, max: usize) -> Self {
+        self.info.limit_working_memory(min, max);
+        self
+    }
+
+    /// See `ExtendedLimitInfo::limit_kill_on_job_close`.
+    pub fn kill_on_job_close(mut self) -> Self {
+        self.info.limit_kill_on_job_close();
+        self
+    }
+
+    /// See `ExtendedLimitInfo::limit_breakaway_ok`.
+    pub fn breakaway_ok(mut self) -> Self {
+        self.info.limit_breakaway_ok();
+        self
+    }
+
+    /// See `ExtendedLimitInfo::limit_silent_breakaway_ok`.
+    pub fn silent_breakaway_ok(mut self) -> Self {
+        self.info.limit_silent_breakaway_ok();
+        self
+    }
+
+    /// See `ExtendedLimitInfo::limit_priority_class`.
+    pub fn priority_class(mut self, priority_class: PriorityClass) -> Self {
+        self.info.limit_priority_class(priority_class);
+        self
+    }
+
+    /// See `ExtendedLimitInfo::limit_scheduling_class`.
+    pub fn scheduling_class(mut self, scheduling_class: SchedulingClass) -> Self {
+        self.info.limit_scheduling_class(scheduling_class);
+        self
+    }
+
+    /// See `ExtendedLimitInfo::limit_affinity`.
+    pub fn affinity(mut self, affinity: usize) -> Self {
+        self.info.limit_affinity(affinity);
+        self
+    }
+
+    /// See `ExtendedLimitInfo::limit_job_memory`.
+    pub fn job_memory(mut self, limit: usize) -> Self {
+        self.info.limit_job_memory(limit);
+        self
+    }
+
+    /// See `ExtendedLimitInfo::limit_process_memory`.
+    pub fn process_memory(mut self, limit: usize) -> Self {
+        self.info.limit_process_memory(limit);
+        self
+    }
+
+    /// See `ExtendedLimitInfo::limit_process_time`.
+    pub fn process_time(mut self, time: Duration) -> Self {
+        self.info.limit_process_time(time);
+        self
+    }
+
+    /// See `ExtendedLimitInfo::limit_job_time`.
+    pub fn job_time(mut self, time: Duration) -> Self {
+        self.info.limit_job_time(time);
+        self
+    }
+
+    /// See `ExtendedLimitInfo::limit_active_processes`.
+    pub fn active_processes(mut self, limit: u32) -> Self {
+        self.info.limit_active_processes(limit);
+        self
+    }
+
+    /// Set the limits to `job`, replacing its current extended limits.
+    pub fn apply_to(self, job: &Job) -> Result<AppliedLimits, JobError> {
+        job.set_extended_limit_info(&self.info)?;
+
+        Ok(AppliedLimits { info: self.info })
+    }
+
+    /// Create a new (anonymous) job with the limits.
+    pub fn build_job(self) -> Result<Job, JobError> {
+        Job::create_with_limit_info(&self.info)
+    }
+}
+
+/// Limits which were applied to a job using `LimitsBuilder::apply_to`.
+#[derive(Debug, Clone)]
+pub struct AppliedLimits {
+    info: ExtendedLimitInfo,
+}
+
+impl AppliedLimits {
+    /// Return the limits which were applied.
+    pub fn info(&self) -> &ExtendedLimitInfo {
+        &self.info
+    }
+
+    /// Also set the limits to `job`.
+    pub fn apply_to(&self, job: &Job) -> Result<(), JobError> {
+        job.set_extended_limit_info(&self.info)
+    }
+
+    /// Return the limits which were applied, for further changes.
+    pub fn into_info(self) -> ExtendedLimitInfo {
+        self.info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Job, LimitsBuilder};
+
+    #[test]
+    fn build_job() {
+        let job = LimitsBuilder::new()
+            .job_memory(64 * 1024 * 1024)
+            .active_processes(4)
+            .build_job()
+            .unwrap();
+
+        let info = job.query_extended_limit_info().unwrap();
+        assert_eq!(info.job_memory_limit(), Some(64 * 1024 * 1024));
+        assert_eq!(info.active_processes_limit(), Some(4));
+    }
+
+    #[test]
+    fn apply_to() {
+        let job = Job::create().unwrap();
+        let other = Job::create().unwrap();
+
+        let applied = LimitsBuilder::new()
+            .affinity(1)
+            .active_processes(2)
+            .apply_to(&job)
+            .unwrap();
+        applied.apply_to(&other).unwrap();
+
+        for job in [&job, &other] {
+            assert_eq!(&job.query_extended_limit_info().unwrap(), applied.info());
+        }
+    }
+}
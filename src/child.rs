@@ -0,0 +1,163 @@
+use std::os::windows::io::AsRawHandle;
+
+#[cfg(feature = "duct")]
+use windows::Win32::System::Threading::{
+    OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_QUOTA, PROCESS_TERMINATE,
+};
+use windows::Win32::{
+    Foundation::{HANDLE, STILL_ACTIVE},
+    System::Threading::GetExitCodeProcess,
+};
+
+#[cfg(feature = "duct")]
+use crate::handle::OwnedHandle;
+use crate::{Job, JobError};
+
+impl Job {
+    /// Assigns a child process (like a `std::process::Child`) to the job object.
+    /// Returns `JobError::ProcessExited` if the child has already exited.
+    pub fn assign_child<C: AsRawHandle>(&self, child: &C) -> Result<(), JobError> {
+        self.assign_process_checked(HANDLE(child.as_raw_handle() as isize))
+    }
+
+    /// Assigns many processes to the job object, without stopping at the first failure.
+    /// Return the result of assigning each process, in the same order as `processes`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job = self.handle.0, count = processes.len()))
+    )]
+    pub fn assign_processes<C: AsRawHandle>(&self, processes: &[C]) -> Vec<Result<(), JobError>> {
+        processes
+            .iter()
+            .map(|process| self.assign_child(process))
+            .collect()
+    }
+
+    /// Assigns a `tokio::process::Child` to the job object.
+    /// Returns `JobError::ProcessExited` if the child has already exited
+    /// (or was already awaited, in which case tokio has closed its handle).
+    #[cfg(feature = "tokio")]
+    pub fn assign_tokio_child(&self, child: &tokio::process::Child) -> Result<(), JobError> {
+        let proc_handle = child.raw_handle().ok_or(JobError::ProcessExited)?;
+
+        self.assign_process_checked(HANDLE(proc_handle as isize))
+    }
+
+    /// Assigns an `async_process::Child` (as spawned by `smol`) to the job object.
+    /// Returns `JobError::ProcessExited` if the child has already exited.
+    #[cfg(feature = "async-process")]
+    pub fn assign_async_process_child(&self, child: &async_process::Child) -> Result<(), JobError> {
+        self.assign_child(child)
+    }
+
+    /// Assigns the processes of a running `duct::Handle` (all the processes of the pipeline,
+    /// but not their own children) to the job object, without stopping at the first failure.
+    /// Return the result of assigning each process, in pipeline order (like `handle.pids()`).
+    /// Processes which have already exited are reported as `JobError::ProcessExited`.
+    #[cfg(feature = "duct")]
+    pub fn assign_duct_handle(&self, handle: &duct::Handle) -> Vec<Result<(), JobError>> {
+        handle
+            .pids()
+            .into_iter()
+            .map(|pid| {
+                // The handle keeps the (unwaited) processes from being reaped, so their pids
+                // can't be reused.
+                let process = unsafe {
+                    OpenProcess(
+                        PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_SET_QUOTA | PROCESS_TERMINATE,
+                        false,
+                        pid,
+                    )
+                }
+                .map(OwnedHandle)
+                .map_err(|e| JobError::OpenProcessFailed(e.into()))?;
+
+                self.assign_process_checked(process.0)
+            })
+            .collect()
+    }
+
+    /// Assigns the process to the job, and reports a failure to assign a process
+    /// which has already exited as `JobError::ProcessExited`.
+    pub(crate) fn assign_process_checked(&self, proc_handle: HANDLE) -> Result<(), JobError> {
+        self.assign_process(proc_handle.0).map_err(|err| {
+            let mut exit_code = 0;
+
+            match unsafe { GetExitCodeProcess(proc_handle, &mut exit_code) } {
+                Ok(()) if exit_code != STILL_ACTIVE.0 as u32 => JobError::ProcessExited,
+                _ => err,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "full")]
+    #[test]
+    fn assign_std_child() {
+        use std::time::Duration;
+
+        use crate::test_util::DisposableChild;
+        use crate::{Job, JobError, Pid};
+
+        let job = Job::create().unwrap();
+
+        let mut child = DisposableChild::spawn_exiting(0, Duration::from_secs(1)).unwrap();
+        job.assign_child(&child).unwrap();
+
+        let pids = job.query_process_id_list().unwrap();
+        assert!(pids.contains(&Pid::new(child.id())));
+
+        child.inner_mut().wait().unwrap();
+        assert!(matches!(
+            job.assign_child(&child),
+            Err(JobError::ProcessExited)
+        ));
+    }
+
+    #[cfg(feature = "full")]
+    #[test]
+    fn assign_processes() {
+        use std::time::Duration;
+
+        use crate::test_util::DisposableChild;
+        use crate::{Job, JobError};
+
+        let job = Job::create().unwrap();
+
+        let mut exited = DisposableChild::spawn_exiting(0, Duration::ZERO).unwrap();
+        exited.inner_mut().wait().unwrap();
+
+        let running = DisposableChild::spawn().unwrap();
+
+        let results = job.assign_processes(&[exited, running]);
+        assert!(matches!(results[0], Err(JobError::ProcessExited)));
+        assert!(results[1].is_ok());
+    }
+
+    #[cfg(feature = "duct")]
+    #[test]
+    fn assign_duct_handle() {
+        use crate::{Job, Pid};
+
+        let job = Job::create().unwrap();
+
+        let handle = duct::cmd!("cmd", "/C", "ping -n 2 127.0.0.1")
+            .pipe(duct::cmd!("findstr", "Reply"))
+            .stdout_null()
+            .start()
+            .unwrap();
+
+        let results = job.assign_duct_handle(&handle);
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|res| res.is_ok()));
+
+        let pids = job.query_process_id_list().unwrap();
+        for pid in handle.pids() {
+            assert!(pids.contains(&Pid::new(pid)));
+        }
+
+        handle.wait().unwrap();
+    }
+}
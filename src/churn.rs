@@ -0,0 +1,80 @@
+use std::time::Instant;
+
+use crate::{BasicAccountingInfo, Job, JobError};
+
+/// Counters of the processes created, exited, killed and crashed within a job, at a point in time.
+///
+/// Compare two snapshots with `churn_rate` to detect runaway respawning inside a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessChurn {
+    /// The number of processes associated with the job during its lifetime.
+    pub created: u64,
+    /// The number of processes which exited (including killed processes).
+    pub exited: u64,
+    /// The number of processes which were terminated because of a limit violation.
+    pub killed: u64,
+    /// The number of processes which exited abnormally (for example, because of an unhandled exception).
+    /// Only a `JobMonitor` can count these: when queried from the job's accounting information,
+    /// this is always 0.
+    pub abnormal: u64,
+    /// When the snapshot was taken.
+    pub at: Instant,
+}
+
+impl ProcessChurn {
+    pub(crate) fn from_accounting(info: &BasicAccountingInfo) -> Self {
+        let created = info.total_processes() as u64;
+
+        ProcessChurn {
+            created,
+            exited: created.saturating_sub(info.active_processes() as u64),
+            killed: info.total_terminated_processes() as u64,
+            abnormal: 0,
+            at: Instant::now(),
+        }
+    }
+
+    /// Return the number of processes created per second between `earlier` and this snapshot.
+    pub fn churn_rate(&self, earlier: &ProcessChurn) -> f64 {
+        let elapsed = self.at.saturating_duration_since(earlier.at).as_secs_f64();
+
+        if elapsed == 0.0 {
+            return 0.0;
+        }
+
+        self.created.saturating_sub(earlier.created) as f64 / elapsed
+    }
+}
+
+impl Job {
+    /// Return the process churn counters of the job, based on its accounting information.
+    /// A `JobMonitor` provides more accurate counters with `monitor.process_churn`.
+    pub fn query_process_churn(&self) -> Result<ProcessChurn, JobError> {
+        let info = self.query_basic_accounting_info()?;
+
+        Ok(ProcessChurn::from_accounting(&info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{Job, ProcessChurn};
+
+    #[test]
+    fn churn_rate() {
+        let job = Job::create().unwrap();
+
+        let earlier = job.query_process_churn().unwrap();
+        assert_eq!(earlier.created, 0);
+
+        let later = ProcessChurn {
+            created: 10,
+            at: earlier.at + Duration::from_secs(2),
+            ..earlier
+        };
+        assert_eq!(later.churn_rate(&earlier), 5.0);
+        assert_eq!(earlier.churn_rate(&later), 0.0);
+    }
+}
@@ -0,0 +1,364 @@
+use std::{
+    ffi::{c_void, OsStr},
+    io, mem,
+    os::windows::{io::AsRawHandle, process::CommandExt},
+    path::Path,
+    process::{Child, Command, ExitStatus, Output, Stdio},
+};
+
+use windows::Win32::{
+    Foundation::{BOOL, HANDLE, NTSTATUS},
+    System::{
+        JobObjects::{
+            IsProcessInJob, JobObjectExtendedLimitInformation, QueryInformationJobObject,
+            JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_BREAKAWAY_OK,
+            JOB_OBJECT_LIMIT_SILENT_BREAKAWAY_OK,
+        },
+        Threading::{
+            GetCurrentProcess, ResumeThread, CREATE_BREAKAWAY_FROM_JOB, CREATE_NEW_PROCESS_GROUP,
+            CREATE_SUSPENDED, THREAD_SUSPEND_RESUME,
+        },
+    },
+};
+
+use crate::handle::OwnedHandle;
+use crate::{Job, JobError};
+
+/// A process builder, mirroring `std::process::Command`, which always spawns
+/// the child process inside a job.
+///
+/// The child is created suspended, assigned to the job, and only then resumed,
+/// so it (and any process it creates) can never run outside the job.
+///
+/// ```edition2021
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let job = Job::create()?;
+///
+/// let status = JobCommand::new(&job, "cmd")
+///     .args(["/C", "exit 3"])
+///     .status()?;
+///
+/// assert_eq!(status.code(), Some(3));
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct JobCommand<'a> {
+    job: &'a Job,
+    command: Command,
+    creation_flags: u32,
+    new_process_group: bool,
+}
+
+impl<'a> JobCommand<'a> {
+    /// Create a new builder for launching `program` inside `job`.
+    pub fn new<S: AsRef<OsStr>>(job: &'a Job, program: S) -> Self {
+        JobCommand {
+            job,
+            command: Command::new(program),
+            creation_flags: 0,
+            new_process_group: false,
+        }
+    }
+
+    /// Add an argument to pass to the program.
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.command.arg(arg);
+
+        self
+    }
+
+    /// Add multiple arguments to pass to the program.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.command.args(args);
+
+        self
+    }
+
+    /// Insert or update an environment variable of the child process.
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.command.env(key, val);
+
+        self
+    }
+
+    /// Insert or update multiple environment variables of the child process.
+    pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.command.envs(vars);
+
+        self
+    }
+
+    /// Remove an environment variable from the child process.
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Self {
+        self.command.env_remove(key);
+
+        self
+    }
+
+    /// Clear all the environment variables of the child process.
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.command.env_clear();
+
+        self
+    }
+
+    /// Set the working directory of the child process.
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.command.current_dir(dir);
+
+        self
+    }
+
+    /// Set the standard input of the child process.
+    pub fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.command.stdin(cfg);
+
+        self
+    }
+
+    /// Set the standard output of the child process.
+    pub fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.command.stdout(cfg);
+
+        self
+    }
+
+    /// Set the standard error of the child process.
+    pub fn stderr<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.command.stderr(cfg);
+
+        self
+    }
+
+    /// Set the process creation flags of the child process.
+    /// `CREATE_SUSPENDED` is always added, and removed once the child is assigned to the job.
+    pub fn creation_flags(&mut self, flags: u32) -> &mut Self {
+        self.creation_flags = flags;
+
+        self
+    }
+
+    /// Create the child as the root of a new console process group (using `CREATE_NEW_PROCESS_GROUP`),
+    /// so it can be asked to exit with `job.send_ctrl_break`.
+    pub fn new_process_group(&mut self) -> &mut Self {
+        self.new_process_group = true;
+
+        self
+    }
+
+    /// Spawn the child process inside the job.
+    /// If the child cannot be assigned to the job, it is killed and an error is returned.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job = self.job.handle.0), err)
+    )]
+    pub fn spawn(&mut self) -> Result<Child, JobError> {
+        let mut creation_flags = self.creation_flags;
+        if self.new_process_group {
+            creation_flags |= CREATE_NEW_PROCESS_GROUP.0;
+        }
+
+        spawn_in_job(self.job, &mut self.command, creation_flags)
+    }
+
+    /// Spawn the child process inside the job, and wait for it to exit.
+    pub fn status(&mut self) -> Result<ExitStatus, JobError> {
+        self.spawn()?.wait().map_err(JobError::SpawnFailed)
+    }
+
+    /// Spawn the child process inside the job, wait for it to exit and collect its output.
+    /// The standard output and error of the child are always captured.
+    pub fn output(&mut self) -> Result<Output, JobError> {
+        self.command.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        self.spawn()?
+            .wait_with_output()
+            .map_err(JobError::SpawnFailed)
+    }
+}
+
+impl Job {
+    /// Spawn `command` outside of the job of the current process, using `CREATE_BREAKAWAY_FROM_JOB`.
+    /// Returns `JobError::BreakawayNotAllowed` if the current process is in a job
+    /// which was not set with `limit_breakaway_ok` (or `limit_silent_breakaway_ok`). If the current process is not in a job,
+    /// the command is spawned normally.
+    /// Note that `command`'s creation flags are replaced.
+    pub fn spawn_breakaway(command: &mut Command) -> Result<Child, JobError> {
+        let mut in_job = BOOL::default();
+
+        unsafe { IsProcessInJob(GetCurrentProcess(), HANDLE::default(), &mut in_job) }.map_err(
+            |e| JobError::GetInfoFailed {
+                class: "IsProcessInJob",
+                source: e.into(),
+            },
+        )?;
+
+        if !in_job.as_bool() {
+            return command.spawn().map_err(JobError::SpawnFailed);
+        }
+
+        let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+
+        // A null handle queries the (immediate) job of the current process.
+        unsafe {
+            QueryInformationJobObject(
+                HANDLE::default(),
+                JobObjectExtendedLimitInformation,
+                &mut info as *mut _ as *mut c_void,
+                mem::size_of_val(&info) as u32,
+                None,
+            )
+        }
+        .map_err(|e| JobError::GetInfoFailed {
+            class: "JobObjectExtendedLimitInformation",
+            source: e.into(),
+        })?;
+
+        let flags = info.BasicLimitInformation.LimitFlags;
+
+        if !flags.contains(JOB_OBJECT_LIMIT_BREAKAWAY_OK)
+            && !flags.contains(JOB_OBJECT_LIMIT_SILENT_BREAKAWAY_OK)
+        {
+            return Err(JobError::BreakawayNotAllowed);
+        }
+
+        command
+            .creation_flags(CREATE_BREAKAWAY_FROM_JOB.0)
+            .spawn()
+            .map_err(JobError::SpawnFailed)
+    }
+}
+
+/// Spawn `command` suspended, assign it to `job` and resume it.
+/// If the child cannot be assigned to the job, it is killed and an error is returned.
+pub(crate) fn spawn_in_job(
+    job: &Job,
+    command: &mut Command,
+    creation_flags: u32,
+) -> Result<Child, JobError> {
+    let mut child = command
+        .creation_flags(creation_flags | CREATE_SUSPENDED.0)
+        .spawn()
+        .map_err(JobError::SpawnFailed)?;
+
+    let res = job
+        .assign_child(&child)
+        .and_then(|_| resume_primary_thread(&child));
+
+    if let Err(err) = res {
+        let _ = child.kill();
+        let _ = child.wait();
+
+        return Err(err);
+    }
+
+    Ok(child)
+}
+
+#[cfg_attr(windows, link(name = "ntdll"))]
+extern "system" {
+    fn NtGetNextThread(
+        process_handle: HANDLE,
+        thread_handle: HANDLE,
+        desired_access: u32,
+        handle_attributes: u32,
+        flags: u32,
+        new_thread_handle: *mut HANDLE,
+    ) -> NTSTATUS;
+}
+
+/// Resume the primary thread of the (suspended) child.
+///
+/// `std::process::Child` doesn't expose the thread handle returned by `CreateProcessW`, so it
+/// is reopened from the process handle. A suspended process has only its primary thread,
+/// so this is the first (and only) thread returned by `NtGetNextThread`.
+fn resume_primary_thread(child: &Child) -> Result<(), JobError> {
+    let process = HANDLE(child.as_raw_handle() as isize);
+    let mut thread = HANDLE::default();
+
+    unsafe {
+        NtGetNextThread(
+            process,
+            HANDLE::default(),
+            THREAD_SUSPEND_RESUME.0,
+            0,
+            0,
+            &mut thread,
+        )
+    }
+    .ok()
+    .map_err(|e| JobError::ResumeFailed(e.into()))?;
+    let thread = OwnedHandle(thread);
+
+    if unsafe { ResumeThread(thread.0) } == u32::MAX {
+        return Err(JobError::ResumeFailed(io::Error::last_os_error()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use rusty_fork::rusty_fork_test;
+
+    use crate::{ExtendedLimitInfo, Job, JobCommand, JobError, Pid};
+
+    #[test]
+    fn spawns_inside_job() {
+        let job = Job::create().unwrap();
+
+        let output = JobCommand::new(&job, "cmd")
+            .args(["/C", "echo hello"])
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
+
+        let info = job.query_basic_accounting_info().unwrap();
+        assert_eq!(info.total_processes(), 1);
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn spawn_breakaway() {
+            let job = Job::create().unwrap();
+            job.assign_current_process().unwrap();
+
+            let mut command = Command::new("cmd");
+            command.args(["/C", "exit 0"]);
+
+            assert!(matches!(
+                Job::spawn_breakaway(&mut command),
+                Err(JobError::BreakawayNotAllowed)
+            ));
+
+            let mut info = ExtendedLimitInfo::new();
+            info.limit_breakaway_ok();
+            job.set_extended_limit_info(&info).unwrap();
+
+            let mut child = Job::spawn_breakaway(&mut command).unwrap();
+            let pids = job.query_process_id_list().unwrap();
+            assert!(!pids.contains(&Pid::new(child.id())));
+            child.wait().unwrap();
+        }
+    }
+}
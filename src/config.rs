@@ -0,0 +1,602 @@
+use crate::{
+    CpuRateControlInfo, ExtendedLimitInfo, IoRateControlInfo, Job, JobError, NetRateControlInfo,
+    NotificationLimitInfo, UiRestrictions,
+};
+
+/// An aggregate of all the configurable information classes of a job object.
+///
+/// Use `job.export_config()` to capture the configuration of an existing job,
+/// and `job.apply_config(&config)` to apply it to another job (or re-apply it later).
+///
+/// ```edition2021
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let mut config = JobConfig::new();
+/// config.extended_limits.limit_priority_class(PriorityClass::BelowNormal);
+/// config.cpu_rate_control.limit_hard_cap(5000);
+///
+/// let job = Job::create()?;
+/// job.apply_config(&config)?;
+///
+/// let exported = job.export_config()?;
+/// assert_eq!(exported.cpu_rate_control.hard_cap(), Some(5000));
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct JobConfig {
+    /// Basic and extended limits.
+    pub extended_limits: ExtendedLimitInfo,
+    /// Basic user-interface restrictions.
+    pub ui_restrictions: UiRestrictions,
+    /// CPU rate control.
+    pub cpu_rate_control: CpuRateControlInfo,
+    /// Network rate control.
+    pub net_rate_control: NetRateControlInfo,
+    /// I/O rate control, one entry per volume.
+    pub io_rate_control: Vec<IoRateControlInfo>,
+    /// Notification limits.
+    pub notification_limits: NotificationLimitInfo,
+}
+
+/// A single difference between two `JobConfig`s, as returned by `JobConfig::diff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDifference {
+    /// The information class which differs, for example `extended_limits`.
+    pub class: &'static str,
+    /// The field which differs, for example `MaximumWorkingSetSize`.
+    pub field: String,
+    /// The value of the field in `self`, or `None` if it is missing (for per-volume I/O settings).
+    pub this: Option<String>,
+    /// The value of the field in `other`, or `None` if it is missing (for per-volume I/O settings).
+    pub other: Option<String>,
+}
+
+/// The result of copying a single information class, as returned by `job.clone_config_from`.
+#[derive(Debug)]
+pub struct ClassCopyResult {
+    /// The information class which was copied, for example `extended_limits`.
+    pub class: &'static str,
+    /// Whether the class was queried from the source job and set to the target job.
+    pub result: Result<(), JobError>,
+}
+
+impl JobConfig {
+    /// Return an empty configuration, without any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare this configuration to `other`, returning the list of fields which differ.
+    /// An empty list means both configurations are equivalent.
+    pub fn diff(&self, other: &JobConfig) -> Vec<ConfigDifference> {
+        let mut diffs = vec![];
+
+        let classes = [
+            (
+                InfoClass::ExtendedLimits.as_str(),
+                extended_limits_fields(&self.extended_limits),
+                extended_limits_fields(&other.extended_limits),
+            ),
+            (
+                InfoClass::UiRestrictions.as_str(),
+                ui_restrictions_fields(&self.ui_restrictions),
+                ui_restrictions_fields(&other.ui_restrictions),
+            ),
+            (
+                InfoClass::CpuRateControl.as_str(),
+                cpu_rate_control_fields(&self.cpu_rate_control),
+                cpu_rate_control_fields(&other.cpu_rate_control),
+            ),
+            (
+                InfoClass::NetRateControl.as_str(),
+                net_rate_control_fields(&self.net_rate_control),
+                net_rate_control_fields(&other.net_rate_control),
+            ),
+            (
+                InfoClass::IoRateControl.as_str(),
+                io_rate_control_fields(&self.io_rate_control),
+                io_rate_control_fields(&other.io_rate_control),
+            ),
+            (
+                InfoClass::NotificationLimits.as_str(),
+                notification_limits_fields(&self.notification_limits),
+                notification_limits_fields(&other.notification_limits),
+            ),
+        ];
+
+        for (class, this, other) in classes {
+            for (field, value) in &this {
+                let other_value = other.iter().find(|(f, _)| f == field).map(|(_, v)| v);
+
+                if other_value != Some(value) {
+                    diffs.push(ConfigDifference {
+                        class,
+                        field: field.clone(),
+                        this: Some(value.clone()),
+                        other: other_value.cloned(),
+                    });
+                }
+            }
+
+            for (field, value) in &other {
+                if !this.iter().any(|(f, _)| f == field) {
+                    diffs.push(ConfigDifference {
+                        class,
+                        field: field.clone(),
+                        this: None,
+                        other: Some(value.clone()),
+                    });
+                }
+            }
+        }
+
+        diffs
+    }
+}
+
+type Fields = Vec<(String, String)>;
+
+/// The information classes of a `JobConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InfoClass {
+    ExtendedLimits,
+    UiRestrictions,
+    CpuRateControl,
+    NetRateControl,
+    IoRateControl,
+    NotificationLimits,
+}
+
+impl InfoClass {
+    /// All the information classes, in the order they are applied.
+    const ALL: [InfoClass; 6] = [
+        InfoClass::ExtendedLimits,
+        InfoClass::UiRestrictions,
+        InfoClass::CpuRateControl,
+        InfoClass::NetRateControl,
+        InfoClass::IoRateControl,
+        InfoClass::NotificationLimits,
+    ];
+
+    /// Return the name of the class, as used in `ConfigDifference` and `JobError::ApplyConfigFailed`.
+    fn as_str(self) -> &'static str {
+        match self {
+            InfoClass::ExtendedLimits => "extended_limits",
+            InfoClass::UiRestrictions => "ui_restrictions",
+            InfoClass::CpuRateControl => "cpu_rate_control",
+            InfoClass::NetRateControl => "net_rate_control",
+            InfoClass::IoRateControl => "io_rate_control",
+            InfoClass::NotificationLimits => "notification_limits",
+        }
+    }
+}
+
+/// Only the values of the limits which are set are compared: a new job reports
+/// a `SchedulingClass` of 5 even though the limit is not set.
+fn extended_limits_fields(info: &ExtendedLimitInfo) -> Fields {
+    let info = info.set_limits();
+    let basic = &info.BasicLimitInformation;
+
+    vec![
+        ("LimitFlags".into(), format!("{:#x}", basic.LimitFlags.0)),
+        (
+            "MinimumWorkingSetSize".into(),
+            basic.MinimumWorkingSetSize.to_string(),
+        ),
+        (
+            "MaximumWorkingSetSize".into(),
+            basic.MaximumWorkingSetSize.to_string(),
+        ),
+        (
+            "PerProcessUserTimeLimit".into(),
+            basic.PerProcessUserTimeLimit.to_string(),
+        ),
+        (
+            "PerJobUserTimeLimit".into(),
+            basic.PerJobUserTimeLimit.to_string(),
+        ),
+        (
+            "ActiveProcessLimit".into(),
+            basic.ActiveProcessLimit.to_string(),
+        ),
+        ("Affinity".into(), format!("{:#x}", basic.Affinity)),
+        ("PriorityClass".into(), basic.PriorityClass.to_string()),
+        ("SchedulingClass".into(), basic.SchedulingClass.to_string()),
+        (
+            "ProcessMemoryLimit".into(),
+            info.ProcessMemoryLimit.to_string(),
+        ),
+        ("JobMemoryLimit".into(), info.JobMemoryLimit.to_string()),
+    ]
+}
+
+fn ui_restrictions_fields(info: &UiRestrictions) -> Fields {
+    vec![(
+        "UIRestrictionsClass".into(),
+        format!("{:#x}", info.0.UIRestrictionsClass.0),
+    )]
+}
+
+fn cpu_rate_control_fields(info: &CpuRateControlInfo) -> Fields {
+    vec![
+        (
+            "ControlFlags".into(),
+            format!("{:#x}", info.0.ControlFlags.0),
+        ),
+        // All the variants of the union share the same 4 bytes.
+        (
+            "CpuRate".into(),
+            unsafe { info.0.Anonymous.CpuRate }.to_string(),
+        ),
+    ]
+}
+
+fn net_rate_control_fields(info: &NetRateControlInfo) -> Fields {
+    vec![
+        (
+            "ControlFlags".into(),
+            format!("{:#x}", info.0.ControlFlags.0),
+        ),
+        ("MaxBandwidth".into(), info.0.MaxBandwidth.to_string()),
+        ("DscpTag".into(), info.0.DscpTag.to_string()),
+    ]
+}
+
+fn io_rate_control_fields(infos: &[IoRateControlInfo]) -> Fields {
+    infos
+        .iter()
+        .flat_map(|info| {
+            let volume = info.volume_name().unwrap_or("*");
+
+            [
+                (format!("{volume}.MaxIops"), info.max_iops().to_string()),
+                (
+                    format!("{volume}.MaxBandwidth"),
+                    info.max_bandwidth().to_string(),
+                ),
+                (
+                    format!("{volume}.ReservationIops"),
+                    info.reservation_iops().to_string(),
+                ),
+                (format!("{volume}.Enabled"), info.is_enabled().to_string()),
+            ]
+        })
+        .collect()
+}
+
+fn notification_limits_fields(info: &NotificationLimitInfo) -> Fields {
+    vec![
+        ("LimitFlags".into(), format!("{:#x}", info.0.LimitFlags.0)),
+        (
+            "IoReadBytesLimit".into(),
+            info.0.IoReadBytesLimit.to_string(),
+        ),
+        (
+            "IoWriteBytesLimit".into(),
+            info.0.IoWriteBytesLimit.to_string(),
+        ),
+        (
+            "PerJobUserTimeLimit".into(),
+            info.0.PerJobUserTimeLimit.to_string(),
+        ),
+        ("JobMemoryLimit".into(), info.0.JobMemoryLimit.to_string()),
+    ]
+}
+
+impl Job {
+    /// Query all the configurable information classes of the job.
+    pub fn export_config(&self) -> Result<JobConfig, JobError> {
+        Ok(JobConfig {
+            extended_limits: self.query_extended_limit_info()?,
+            ui_restrictions: self.query_ui_restrictions()?,
+            cpu_rate_control: self.query_cpu_rate_control_info()?,
+            net_rate_control: self.query_net_rate_control_info()?,
+            io_rate_control: self.query_io_rate_control_info(None)?,
+            notification_limits: self.query_notification_limit_info()?,
+        })
+    }
+
+    /// Set all the information classes in `config` to the job.
+    /// If setting one of the classes fails, the job is left with the classes which were already set.
+    /// See `apply_config_atomic` for a version which rolls back on failure.
+    pub fn apply_config(&self, config: &JobConfig) -> Result<(), JobError> {
+        for class in InfoClass::ALL {
+            self.apply_class(config, class)?;
+        }
+
+        Ok(())
+    }
+
+    /// Like `apply_config`, but if setting one of the classes fails, restore the classes
+    /// which were already set to their previous values, and return
+    /// `JobError::ApplyConfigFailed` with the class which failed.
+    ///
+    /// Note that rolling back is best-effort: failures to restore a class are logged, and reported
+    /// by the `restored` field of the error.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job = self.handle.0), err)
+    )]
+    pub fn apply_config_atomic(&self, config: &JobConfig) -> Result<(), JobError> {
+        let previous = self.export_config()?;
+
+        for (index, class) in InfoClass::ALL.into_iter().enumerate() {
+            if let Err(err) = self.apply_class(config, class) {
+                let mut restored = true;
+
+                for &applied in InfoClass::ALL[..=index].iter().rev() {
+                    if let Err(err) = self.restore_class(&previous, config, applied) {
+                        log_warn!("Failed to restore {}: {}", applied.as_str(), err);
+                        restored = false;
+                    }
+                }
+
+                return Err(JobError::ApplyConfigFailed {
+                    class: class.as_str(),
+                    restored,
+                    source: Box::new(err),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copy all the configurable information classes of `other` to the job, for example
+    /// to stamp out worker jobs from a "golden" template job.
+    ///
+    /// Unlike `apply_config`, every class is copied even if copying another one fails
+    /// (for example, because it is not supported by the OS), and the result of each class is returned.
+    ///
+    /// ```edition2021
+    /// use win32job::*;
+    /// # fn main() -> Result<(), JobError> {
+    ///
+    /// let template = Job::create()?;
+    /// let mut ui_restrictions = UiRestrictions::new();
+    /// ui_restrictions.limit_read_clipboard();
+    /// template.set_ui_restrictions(&ui_restrictions)?;
+    ///
+    /// let worker = Job::create()?;
+    /// for copied in worker.clone_config_from(&template) {
+    ///     if let Err(err) = copied.result {
+    ///         eprintln!("Failed to copy {}: {}", copied.class, err);
+    ///     }
+    /// }
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn clone_config_from(&self, other: &Job) -> Vec<ClassCopyResult> {
+        InfoClass::ALL
+            .into_iter()
+            .map(|class| {
+                let mut config = JobConfig::new();
+
+                let result = other
+                    .query_class(&mut config, class)
+                    .and_then(|_| self.apply_class(&config, class));
+
+                ClassCopyResult {
+                    class: class.as_str(),
+                    result,
+                }
+            })
+            .collect()
+    }
+
+    fn query_class(&self, config: &mut JobConfig, class: InfoClass) -> Result<(), JobError> {
+        match class {
+            InfoClass::ExtendedLimits => {
+                config.extended_limits = self.query_extended_limit_info()?
+            }
+            InfoClass::UiRestrictions => config.ui_restrictions = self.query_ui_restrictions()?,
+            InfoClass::CpuRateControl => {
+                config.cpu_rate_control = self.query_cpu_rate_control_info()?
+            }
+            InfoClass::NetRateControl => {
+                config.net_rate_control = self.query_net_rate_control_info()?
+            }
+            InfoClass::IoRateControl => {
+                config.io_rate_control = self.query_io_rate_control_info(None)?
+            }
+            InfoClass::NotificationLimits => {
+                config.notification_limits = self.query_notification_limit_info()?
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_class(&self, config: &JobConfig, class: InfoClass) -> Result<(), JobError> {
+        match class {
+            InfoClass::ExtendedLimits => self.set_extended_limit_info(&config.extended_limits),
+            InfoClass::UiRestrictions => self.set_ui_restrictions(&config.ui_restrictions),
+            InfoClass::CpuRateControl => self.set_cpu_rate_control_info(&config.cpu_rate_control),
+            InfoClass::NetRateControl => self.set_net_rate_control_info(&config.net_rate_control),
+            InfoClass::IoRateControl => config
+                .io_rate_control
+                .iter()
+                .try_for_each(|info| self.set_io_rate_control_info(info)),
+            InfoClass::NotificationLimits => {
+                self.set_notification_limit_info(&config.notification_limits)
+            }
+        }
+    }
+
+    /// Restore `class` to its value in `previous`, after (partially) applying `config`.
+    fn restore_class(
+        &self,
+        previous: &JobConfig,
+        config: &JobConfig,
+        class: InfoClass,
+    ) -> Result<(), JobError> {
+        if class != InfoClass::IoRateControl {
+            return self.apply_class(previous, class);
+        }
+
+        // Volumes which did not have I/O rate control before are cleared.
+        for info in &config.io_rate_control {
+            let existed = previous
+                .io_rate_control
+                .iter()
+                .any(|prev| prev.volume_name() == info.volume_name());
+
+            if !existed {
+                self.set_io_rate_control_info(info.clone().clear_limits())?;
+            }
+        }
+
+        self.apply_class(previous, class)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{Job, JobConfig, JobError, PriorityClass, SchedulingClass};
+
+    #[test]
+    fn dedupe_configs() {
+        let mut config = JobConfig::new();
+        config.extended_limits.limit_kill_on_job_close();
+        config.cpu_rate_control.limit_hard_cap(2500);
+
+        let configs: HashSet<_> = [config.clone(), config.clone(), JobConfig::new()]
+            .into_iter()
+            .collect();
+        assert_eq!(configs.len(), 2);
+
+        // Accounting fields do not affect equality.
+        let mut queried = config.clone();
+        queried.extended_limits.0.PeakJobMemoryUsed = 4096;
+        assert_eq!(queried, config);
+    }
+
+    #[test]
+    fn diff_configs() {
+        let mut config = JobConfig::new();
+        assert_eq!(config.diff(&JobConfig::new()), []);
+
+        config
+            .extended_limits
+            .limit_priority_class(PriorityClass::BelowNormal);
+
+        let diffs = config.diff(&JobConfig::new());
+        let fields: Vec<_> = diffs
+            .iter()
+            .map(|diff| (diff.class, diff.field.as_str()))
+            .collect();
+
+        assert_eq!(
+            fields,
+            [
+                ("extended_limits", "LimitFlags"),
+                ("extended_limits", "PriorityClass")
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_against_new_job() {
+        let job = Job::create().unwrap();
+        let exported = job.export_config().unwrap();
+
+        // The values of limits which are not set are ignored.
+        assert_eq!(JobConfig::new().diff(&exported), []);
+
+        let mut config = JobConfig::new();
+        config
+            .extended_limits
+            .limit_scheduling_class(SchedulingClass::new(7).unwrap());
+        job.apply_config(&config).unwrap();
+
+        let exported = job.export_config().unwrap();
+        assert_eq!(config.diff(&exported), []);
+
+        let fields: Vec<_> = JobConfig::new()
+            .diff(&exported)
+            .into_iter()
+            .map(|diff| diff.field)
+            .collect();
+        assert_eq!(fields, ["LimitFlags", "SchedulingClass"]);
+    }
+
+    #[test]
+    fn apply_config_atomic() {
+        let job = Job::create().unwrap();
+
+        let mut config = JobConfig::new();
+        config.ui_restrictions.limit_read_clipboard();
+        // An invalid rate, which fails to be set after the extended limits and UI restrictions.
+        config.cpu_rate_control.limit_hard_cap(20000);
+
+        let err = job.apply_config_atomic(&config).unwrap_err();
+        assert!(matches!(
+            err,
+            JobError::ApplyConfigFailed {
+                class: "cpu_rate_control",
+                restored: true,
+                ..
+            }
+        ));
+
+        let restored = job.export_config().unwrap();
+        assert_eq!(
+            restored.ui_restrictions.0,
+            JobConfig::new().ui_restrictions.0
+        );
+    }
+
+    #[test]
+    fn export_and_apply_config() {
+        let source = Job::create().unwrap();
+
+        let mut config = JobConfig::new();
+        config.extended_limits.limit_breakaway_ok();
+        config.ui_restrictions.limit_read_clipboard();
+        config.cpu_rate_control.limit_hard_cap(4000);
+
+        source.apply_config(&config).unwrap();
+
+        let exported = source.export_config().unwrap();
+
+        let target = Job::create().unwrap();
+        target.apply_config(&exported).unwrap();
+
+        let config = target.export_config().unwrap();
+
+        assert_eq!(config.extended_limits.0, exported.extended_limits.0);
+        assert_eq!(config.ui_restrictions.0, exported.ui_restrictions.0);
+        assert_eq!(config.cpu_rate_control.hard_cap(), Some(4000));
+    }
+
+    #[test]
+    fn clone_config_from() {
+        let template = Job::create().unwrap();
+
+        let mut config = JobConfig::new();
+        config.ui_restrictions.limit_read_clipboard();
+        config.cpu_rate_control.limit_hard_cap(3000);
+        template.apply_config(&config).unwrap();
+
+        let worker = Job::create().unwrap();
+        let results = worker.clone_config_from(&template);
+
+        assert_eq!(results.len(), 6);
+        for copied in &results {
+            assert!(
+                copied.result.is_ok(),
+                "{}: {:?}",
+                copied.class,
+                copied.result
+            );
+        }
+
+        let cloned = worker.export_config().unwrap();
+        assert_eq!(cloned.ui_restrictions.0, config.ui_restrictions.0);
+        assert_eq!(cloned.cpu_rate_control.hard_cap(), Some(3000));
+    }
+}
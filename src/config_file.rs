@@ -0,0 +1,528 @@
+//! Loading a `JobConfig` from a TOML or JSON policy file.
+//!
+//! All the sections and keys are optional. Sizes are either a number of bytes or a string
+//! with a unit (`B`, `KB`, `MB`, `GB` or `TB`, which are powers of 1024, or `KiB`, `MiB`, ...),
+//! durations are strings with a unit (`ms`, `s`, `m` or `h`), and CPU rates are percentages
+//! of the whole machine (either a number or a string such as `"25%"`). Like the keys, names
+//! (such as priority classes and UI restrictions) are case-sensitive.
+//!
+//! ```toml
+//! [limits]
+//! kill_on_job_close = true
+//! breakaway_ok = false
+//! silent_breakaway_ok = false
+//! active_processes = 8
+//! process_memory = "512 MiB"
+//! job_memory = "2 GiB"
+//! working_set = { min = "1 MiB", max = "256 MiB" }
+//! # User-mode execution time limits.
+//! process_time = "30s"
+//! job_time = "10m"
+//! # One of `idle`, `below_normal`, `normal`, `above_normal`, `high` or `realtime`.
+//! priority_class = "below_normal"
+//! scheduling_class = 3
+//! # Either an affinity mask, or a list of processors and ranges (like `"0-3,8-11"`).
+//! affinity = 3
+//!
+//! [cpu]
+//! # Either `hard_cap` or `weight` (from 1 to 9).
+//! hard_cap = "25%"
+//!
+//! [ui]
+//! # Any of `desktop`, `display_settings`, `exit_windows`, `global_atoms`, `handles`,
+//! # `read_clipboard`, `write_clipboard` and `system_parameters`.
+//! restrictions = ["read_clipboard", "write_clipboard"]
+//!
+//! [network]
+//! # Per second.
+//! max_bandwidth = "10 MiB"
+//! dscp_tag = 8
+//!
+//! [notifications]
+//! io_read_bytes = "1 GiB"
+//! io_write_bytes = "512 MiB"
+//! job_time = "10m"
+//! job_memory = "1 GiB"
+//! ```
+//!
+//! The same schema is used for JSON, for example `{"limits": {"job_memory": "2 GiB"}}`.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{CpuSet, JobConfig, JobError, PriorityClass, SchedulingClass};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    limits: LimitsSection,
+    cpu: CpuSection,
+    ui: UiSection,
+    network: NetworkSection,
+    notifications: NotificationsSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LimitsSection {
+    kill_on_job_close: bool,
+    breakaway_ok: bool,
+    silent_breakaway_ok: bool,
+    active_processes: Option<u32>,
+    process_memory: Option<Size>,
+    job_memory: Option<Size>,
+    working_set: Option<WorkingSet>,
+    process_time: Option<String>,
+    job_time: Option<String>,
+    priority_class: Option<String>,
+    scheduling_class: Option<u8>,
+    affinity: Option<Affinity>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkingSet {
+    min: Size,
+    max: Size,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CpuSection {
+    hard_cap: Option<Percent>,
+    weight: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct UiSection {
+    restrictions: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct NetworkSection {
+    max_bandwidth: Option<Size>,
+    dscp_tag: Option<u8>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct NotificationsSection {
+    io_read_bytes: Option<Size>,
+    io_write_bytes: Option<Size>,
+    job_time: Option<String>,
+    job_memory: Option<Size>,
+}
+
+/// A size, either in bytes or as a string with a unit.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Size {
+    Bytes(u64),
+    Text(String),
+}
+
+/// An affinity, either as a mask or as a list of processors (see `CpuSet`).
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Affinity {
+    Mask(usize),
+    Cpus(String),
+}
+
+/// A percentage, either as a number or as a string ending with `%`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Percent {
+    Number(f64),
+    Text(String),
+}
+
+fn invalid(key: &str, message: impl std::fmt::Display) -> JobError {
+    JobError::InvalidConfigFile(format!("{}: {}", key, message))
+}
+
+impl Size {
+    fn bytes(&self, key: &str) -> Result<u64, JobError> {
+        let text = match self {
+            Size::Bytes(bytes) => return Ok(*bytes),
+            Size::Text(text) => text.trim(),
+        };
+
+        let split = text
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+
+        let number: f64 = number
+            .parse()
+            .map_err(|_| invalid(key, format!("invalid size `{}`", text)))?;
+
+        let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+            "" | "b" => 1,
+            "k" | "kb" | "kib" => 1 << 10,
+            "m" | "mb" | "mib" => 1 << 20,
+            "g" | "gb" | "gib" => 1 << 30,
+            "t" | "tb" | "tib" => 1 << 40,
+            _ => return Err(invalid(key, format!("unknown size unit `{}`", unit.trim()))),
+        };
+
+        let bytes = number * multiplier as f64;
+
+        // `u64::MAX as f64` rounds up to 2^64, which is out of range.
+        if !bytes.is_finite() || bytes >= u64::MAX as f64 {
+            return Err(invalid(key, format!("size `{}` is too large", text)));
+        }
+
+        Ok(bytes as u64)
+    }
+
+    fn usize(&self, key: &str) -> Result<usize, JobError> {
+        usize::try_from(self.bytes(key)?).map_err(|_| invalid(key, "size is too large"))
+    }
+}
+
+impl Percent {
+    fn value(&self, key: &str) -> Result<f64, JobError> {
+        let percent = match self {
+            Percent::Number(percent) => *percent,
+            Percent::Text(text) => text
+                .trim()
+                .trim_end_matches('%')
+                .trim()
+                .parse()
+                .map_err(|_| invalid(key, format!("invalid percentage `{}`", text)))?,
+        };
+
+        // A rate of 0 is rejected by the OS, and NaN is never in range.
+        if !(percent > 0.0 && percent <= 100.0) {
+            return Err(invalid(
+                key,
+                "percentage must be greater than 0 and at most 100",
+            ));
+        }
+
+        Ok(percent)
+    }
+}
+
+fn parse_duration(key: &str, text: &str) -> Result<Duration, JobError> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| invalid(key, format!("invalid duration `{}`", text)))?;
+
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 60.0 * 60.0,
+        "" => return Err(invalid(key, "missing duration unit (ms, s, m or h)")),
+        unit => return Err(invalid(key, format!("unknown duration unit `{}`", unit))),
+    };
+
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| invalid(key, format!("duration `{}` is too large", text)))
+}
+
+fn parse_priority_class(name: &str) -> Result<PriorityClass, JobError> {
+    Ok(match name {
+        "idle" => PriorityClass::Idle,
+        "below_normal" => PriorityClass::BelowNormal,
+        "normal" => PriorityClass::Normal,
+        "above_normal" => PriorityClass::AboveNormal,
+        "high" => PriorityClass::High,
+        "realtime" => PriorityClass::Realtime,
+        _ => {
+            return Err(invalid(
+                "limits.priority_class",
+                format!("unknown priority class `{}`", name),
+            ))
+        }
+    })
+}
+
+impl ConfigFile {
+    fn into_config(self) -> Result<JobConfig, JobError> {
+        let mut config = JobConfig::new();
+
+        let limits = &self.limits;
+        let extended = &mut config.extended_limits;
+
+        if limits.kill_on_job_close {
+            extended.limit_kill_on_job_close();
+        }
+        if limits.breakaway_ok {
+            extended.limit_breakaway_ok();
+        }
+        if limits.silent_breakaway_ok {
+            extended.limit_silent_breakaway_ok();
+        }
+        if let Some(active) = limits.active_processes {
+            extended.limit_active_processes(active);
+        }
+        if let Some(size) = &limits.process_memory {
+            extended.limit_process_memory(size.usize("limits.process_memory")?);
+        }
+        if let Some(size) = &limits.job_memory {
+            extended.limit_job_memory(size.usize("limits.job_memory")?);
+        }
+        if let Some(working_set) = &limits.working_set {
+            extended.limit_working_memory(
+                working_set.min.usize("limits.working_set.min")?,
+                working_set.max.usize("limits.working_set.max")?,
+            );
+        }
+        if let Some(time) = &limits.process_time {
+            extended.limit_process_time(parse_duration("limits.process_time", time)?);
+        }
+        if let Some(time) = &limits.job_time {
+            extended.limit_job_time(parse_duration("limits.job_time", time)?);
+        }
+        if let Some(name) = &limits.priority_class {
+            extended.limit_priority_class(parse_priority_class(name)?);
+        }
+        if let Some(class) = limits.scheduling_class {
+            extended.limit_scheduling_class(SchedulingClass::new(class)?);
+        }
+        match &limits.affinity {
+            Some(Affinity::Mask(mask)) => {
+                extended.limit_affinity(*mask);
+            }
+            Some(Affinity::Cpus(cpus)) => {
+                let cpus: CpuSet = cpus
+                    .parse()
+                    .map_err(|err| invalid("limits.affinity", err))?;
+                extended.limit_affinity(cpus.mask());
+            }
+            None => {}
+        }
+
+        match (&self.cpu.hard_cap, self.cpu.weight) {
+            (Some(_), Some(_)) => {
+                return Err(invalid("cpu", "`hard_cap` and `weight` are exclusive"));
+            }
+            (Some(percent), None) => {
+                config
+                    .cpu_rate_control
+                    .limit_hard_cap_machine_percent(percent.value("cpu.hard_cap")?);
+            }
+            (None, Some(weight)) => {
+                if !(1..=9).contains(&weight) {
+                    return Err(invalid("cpu.weight", "weight must be between 1 and 9"));
+                }
+                config.cpu_rate_control.limit_weight(weight);
+            }
+            (None, None) => {}
+        }
+
+        for name in &self.ui.restrictions {
+            let ui = &mut config.ui_restrictions;
+
+            match name.as_str() {
+                "desktop" => ui.limit_desktop(),
+                "display_settings" => ui.limit_display_settings(),
+                "exit_windows" => ui.limit_exit_windows(),
+                "global_atoms" => ui.limit_global_atoms(),
+                "handles" => ui.limit_handles(),
+                "read_clipboard" => ui.limit_read_clipboard(),
+                "write_clipboard" => ui.limit_write_clipboard(),
+                "system_parameters" => ui.limit_system_parameters(),
+                _ => {
+                    return Err(invalid(
+                        "ui.restrictions",
+                        format!("unknown restriction `{}`", name),
+                    ))
+                }
+            };
+        }
+
+        if let Some(size) = &self.network.max_bandwidth {
+            config
+                .net_rate_control
+                .limit_max_bandwidth(size.bytes("network.max_bandwidth")?);
+        }
+        if let Some(tag) = self.network.dscp_tag {
+            config.net_rate_control.limit_dscp_tag(tag);
+        }
+
+        let notifications = &self.notifications;
+        let limits = &mut config.notification_limits;
+
+        if let Some(size) = &notifications.io_read_bytes {
+            limits.limit_io_read_bytes(size.bytes("notifications.io_read_bytes")?);
+        }
+        if let Some(size) = &notifications.io_write_bytes {
+            limits.limit_io_write_bytes(size.bytes("notifications.io_write_bytes")?);
+        }
+        if let Some(time) = &notifications.job_time {
+            limits.limit_job_time(parse_duration("notifications.job_time", time)?);
+        }
+        if let Some(size) = &notifications.job_memory {
+            limits.limit_job_memory(size.bytes("notifications.job_memory")?);
+        }
+
+        Ok(config)
+    }
+}
+
+impl JobConfig {
+    /// Parse a configuration from a TOML policy file.
+    /// See the `config_file` module for the schema.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(text: &str) -> Result<Self, JobError> {
+        let file: ConfigFile =
+            toml::from_str(text).map_err(|e| JobError::InvalidConfigFile(e.to_string()))?;
+
+        file.into_config()
+    }
+
+    /// Parse a configuration from a JSON policy file.
+    /// See the `config_file` module for the schema.
+    #[cfg(feature = "json")]
+    pub fn from_json(text: &str) -> Result<Self, JobError> {
+        let file: ConfigFile =
+            serde_json::from_str(text).map_err(|e| JobError::InvalidConfigFile(e.to_string()))?;
+
+        file.into_config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{parse_duration, parse_priority_class, Percent, Size};
+    #[cfg(any(feature = "toml", feature = "json"))]
+    use crate::JobConfig;
+    use crate::JobError;
+    #[cfg(feature = "toml")]
+    use crate::{NotificationLimitInfo, PriorityClass};
+
+    #[test]
+    fn sizes_and_durations() {
+        assert_eq!(Size::Bytes(42).bytes("size").unwrap(), 42);
+        assert_eq!(
+            Size::Text("512 MiB".into()).bytes("size").unwrap(),
+            512 << 20
+        );
+        assert_eq!(Size::Text("1.5GB".into()).bytes("size").unwrap(), 3 << 29);
+        assert!(matches!(
+            Size::Text("3 parsecs".into()).bytes("size"),
+            Err(JobError::InvalidConfigFile(_))
+        ));
+
+        assert_eq!(
+            parse_duration("time", "250ms").unwrap(),
+            Duration::from_millis(250)
+        );
+        assert_eq!(
+            parse_duration("time", "10m").unwrap(),
+            Duration::from_secs(600)
+        );
+        assert!(parse_duration("time", "10").is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_values() {
+        assert!(matches!(
+            Size::Text("99999999999 TB".into()).bytes("size"),
+            Err(JobError::InvalidConfigFile(_))
+        ));
+        assert!(matches!(
+            Size::Text(format!("{}9 TB", "9".repeat(400))).bytes("size"),
+            Err(JobError::InvalidConfigFile(_))
+        ));
+        assert!(matches!(
+            parse_duration("time", &format!("{}9h", "9".repeat(400))),
+            Err(JobError::InvalidConfigFile(_))
+        ));
+
+        for percent in [
+            Percent::Text("0%".into()),
+            Percent::Number(0.0),
+            Percent::Number(f64::NAN),
+            Percent::Text("101%".into()),
+        ] {
+            assert!(matches!(
+                percent.value("cpu.hard_cap"),
+                Err(JobError::InvalidConfigFile(_))
+            ));
+        }
+        assert_eq!(
+            Percent::Text("25%".into()).value("cpu.hard_cap").unwrap(),
+            25.0
+        );
+
+        assert!(parse_priority_class("below_normal").is_ok());
+        assert!(parse_priority_class("Below_Normal").is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn from_toml() {
+        let config = JobConfig::from_toml(
+            r#"
+            [limits]
+            kill_on_job_close = true
+            job_memory = "2 GiB"
+            priority_class = "below_normal"
+            affinity = "0-1,4"
+
+            [cpu]
+            hard_cap = "25%"
+
+            [ui]
+            restrictions = ["read_clipboard"]
+
+            [notifications]
+            job_time = "10m"
+            "#,
+        )
+        .unwrap();
+
+        let mut expected = JobConfig::new();
+        expected
+            .extended_limits
+            .limit_kill_on_job_close()
+            .limit_job_memory(2 << 30)
+            .limit_priority_class(PriorityClass::BelowNormal)
+            .limit_affinity(0b10011);
+        expected
+            .cpu_rate_control
+            .limit_hard_cap_machine_percent(25.0);
+        expected.ui_restrictions.limit_read_clipboard();
+        expected.notification_limits = {
+            let mut limits = NotificationLimitInfo::new();
+            limits.limit_job_time(Duration::from_secs(600));
+            limits
+        };
+
+        assert_eq!(config, expected);
+
+        assert!(JobConfig::from_toml("[limits]\nno_such_limit = 1").is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn from_json() {
+        let config =
+            JobConfig::from_json(r#"{"limits": {"active_processes": 4}, "cpu": {"weight": 3}}"#)
+                .unwrap();
+
+        let mut expected = JobConfig::new();
+        expected.extended_limits.limit_active_processes(4);
+        expected.cpu_rate_control.limit_weight(3);
+
+        assert_eq!(config, expected);
+
+        assert!(JobConfig::from_json(r#"{"cpu": {"weight": 3, "hard_cap": 50}}"#).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    process,
+    sync::{Mutex, PoisonError},
+};
+
+use windows::Win32::System::Console::{
+    AttachConsole, FreeConsole, GenerateConsoleCtrlEvent, GetConsoleProcessList, CTRL_BREAK_EVENT,
+};
+
+use crate::{Job, JobError, Pid};
+
+/// Serializes switching the console of the current process, which is shared by all of its threads.
+static CONSOLE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Return the pids of the processes attached to the console of the current process
+/// (which is empty if the current process has no console).
+fn console_pids() -> Vec<u32> {
+    let mut pids = vec![0; 16];
+
+    loop {
+        let count = unsafe { GetConsoleProcessList(&mut pids) } as usize;
+
+        if count <= pids.len() {
+            pids.truncate(count);
+            return pids;
+        }
+
+        pids.resize(count, 0);
+    }
+}
+
+/// Send a `CTRL_BREAK` event to the process group of `pid`, which is attached to another console,
+/// by temporarily attaching the current process to that console.
+fn send_ctrl_break_on_other_console(pid: u32, console: &[u32]) -> bool {
+    // Detaching from a console destroys it if no other process is attached to it,
+    // so another process is needed to re-attach to the current console.
+    let current_pid = process::id();
+    let reattach_to = match console.iter().find(|pid| **pid != current_pid) {
+        Some(other) => Some(*other),
+        None if console.is_empty() => None,
+        None => {
+            log_debug!(
+                "Cannot signal {}: the current console would be destroyed",
+                pid
+            );
+            return false;
+        }
+    };
+
+    if reattach_to.is_some() {
+        if let Err(err) = unsafe { FreeConsole() } {
+            log_debug!("Failed to detach from the console: {}", err);
+            return false;
+        }
+    }
+
+    let sent = unsafe { AttachConsole(pid) }
+        .and_then(|_| {
+            let sent = unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid) };
+            let _ = unsafe { FreeConsole() };
+
+            sent
+        })
+        .map_err(|err| {
+            log_debug!("Failed to send CTRL_BREAK to {}: {}", pid, err);
+        })
+        .is_ok();
+
+    if let Some(other) = reattach_to {
+        if let Err(err) = unsafe { AttachConsole(other) } {
+            log_warn!("Failed to re-attach to the console of {}: {}", other, err);
+        }
+    }
+
+    sent
+}
+
+/// Send a `CTRL_BREAK` event to the console process groups led by `groups`,
+/// skipping the pids which are not in `members` (or are the current process).
+/// If `attach` is `true`, groups on other consoles are signaled by temporarily attaching
+/// the current process to their console, otherwise they are skipped.
+/// Return the number of process groups which were signaled.
+pub(crate) fn send_ctrl_break(groups: &[u32], members: &[u32], attach: bool) -> usize {
+    let _lock = CONSOLE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let console = console_pids();
+    let current_pid = process::id();
+
+    groups
+        .iter()
+        // Group 0 is every process on the console, including the current process.
+        .filter(|pid| **pid != 0 && **pid != current_pid)
+        .filter(|pid| members.contains(pid))
+        .filter(|pid| {
+            if !console.contains(pid) {
+                if !attach {
+                    log_debug!("Not signaling {}: it is attached to another console", pid);
+                    return false;
+                }
+
+                return send_ctrl_break_on_other_console(**pid, &console);
+            }
+
+            unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, **pid) }
+                .map_err(|err| {
+                    log_debug!("Failed to send CTRL_BREAK to {}: {}", pid, err);
+                })
+                .is_ok()
+        })
+        .count()
+}
+
+impl Job {
+    /// Ask console processes of the job to exit, by sending a `CTRL_BREAK` event
+    /// to the console process groups led by `groups`.
+    ///
+    /// Each process in `groups` must have been created with `CREATE_NEW_PROCESS_GROUP`
+    /// (for example, using `JobCommand::new_process_group`). This can't be checked: Windows sends
+    /// the event for a process which does not lead a group to every process on the console,
+    /// including the current process. Pids which are not in the job are skipped.
+    ///
+    /// Only groups on the console of the current process are signaled
+    /// (see `send_ctrl_break_attaching`).
+    ///
+    /// Return the number of process groups which were signaled.
+    ///
+    /// ```edition2021
+    /// use win32job::*;
+    /// # fn main() -> Result<(), JobError> {
+    ///
+    /// let job = Job::create()?;
+    /// let mut child = JobCommand::new(&job, "cmd")
+    ///     .args(["/C", "ping -n 30 127.0.0.1 > NUL"])
+    ///     .new_process_group()
+    ///     .spawn()?;
+    ///
+    /// job.send_ctrl_break(&[Pid::new(child.id())])?;
+    /// # job.terminate(1)?;
+    /// # child.wait().unwrap();
+    /// #   Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), ret, err)
+    )]
+    pub fn send_ctrl_break(&self, groups: &[Pid]) -> Result<usize, JobError> {
+        self.send_ctrl_break_to(groups, false)
+    }
+
+    /// Like `send_ctrl_break`, but also signal the groups which are attached to other consoles,
+    /// by temporarily detaching the current process from its console and attaching it to theirs.
+    ///
+    /// This changes the console of the whole current process: other threads may not be able to use
+    /// the console (or the standard handles) while it is detached. Groups on other consoles are
+    /// skipped if the current console would be destroyed by detaching from it.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), ret, err)
+    )]
+    pub fn send_ctrl_break_attaching(&self, groups: &[Pid]) -> Result<usize, JobError> {
+        self.send_ctrl_break_to(groups, true)
+    }
+
+    fn send_ctrl_break_to(&self, groups: &[Pid], attach: bool) -> Result<usize, JobError> {
+        let members: Vec<u32> = self
+            .query_process_id_list()?
+            .into_iter()
+            .map(Pid::as_u32)
+            .collect();
+        let groups: Vec<u32> = groups.iter().copied().map(Pid::as_u32).collect();
+
+        Ok(send_ctrl_break(&groups, &members, attach))
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use crate::test_util::DisposableChild;
+    use crate::{Job, Pid};
+
+    #[test]
+    fn send_ctrl_break() {
+        let job = Job::create().unwrap();
+
+        let child = DisposableChild::spawn_process_group_in(&job).unwrap();
+        let pid = Pid::new(child.id());
+
+        // Processes which are not in the job (like the current process) are never signaled.
+        assert_eq!(job.send_ctrl_break(&[Pid::current()]).unwrap(), 0);
+        assert_eq!(job.send_ctrl_break(&[]).unwrap(), 0);
+
+        assert_eq!(job.send_ctrl_break(&[pid]).unwrap(), 1);
+
+        job.terminate(1).unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+
+use windows::Win32::System::JobObjects::{
+    JOBOBJECT_CPU_RATE_CONTROL_INFORMATION, JOB_OBJECT_CPU_RATE_CONTROL,
+    JOB_OBJECT_CPU_RATE_CONTROL_ENABLE, JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP,
+    JOB_OBJECT_CPU_RATE_CONTROL_MIN_MAX_RATE, JOB_OBJECT_CPU_RATE_CONTROL_WEIGHT_BASED,
+};
+
+use crate::utils::get_active_processor_count;
+use crate::{Job, JobError};
+
+/// The rate of all the processor cycles of the machine.
+const MAX_RATE: u32 = 10000;
+
+/// Convert a number of (logical) cores to a rate, given the number of processors of the machine.
+fn rate_from_cores(cores: f64, processor_count: u32) -> u32 {
+    rate_from_percent(cores / processor_count.max(1) as f64 * 100.0)
+}
+
+/// Convert a percentage of the whole machine to a rate.
+fn rate_from_percent(percent: f64) -> u32 {
+    ((percent * 100.0).round() as u32).clamp(1, MAX_RATE)
+}
+
+/// Contains CPU rate control information for a job object, with helper methods for
+/// easy limit manipulation. To apply limits, pass the instance of this struct to
+/// `job.set_cpu_rate_control_info`.
+///
+/// Rates are specified as a percentage of the processor cycles of the whole machine,
+/// times 100 (so `2000` is 20% of all processors). Valid rates are 1 to 10000.
+#[derive(Clone, Copy)]
+pub struct CpuRateControlInfo(pub(crate) JOBOBJECT_CPU_RATE_CONTROL_INFORMATION);
+
+// All the variants of the union share the same 4 bytes.
+impl_eq_hash_by_key!(CpuRateControlInfo, |info| (info.ControlFlags.0, unsafe {
+    info.Anonymous.CpuRate
+}));
+
+impl Default for CpuRateControlInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CpuRateControlInfo {
+    /// Return an empty CPU rate control info object, without any limits.
+    pub fn new() -> Self {
+        let inner = Default::default();
+        CpuRateControlInfo(inner)
+    }
+
+    /// Limit the job's CPU usage to `rate`, and do not let it use more cycles
+    /// even if the system is otherwise idle.
+    pub fn limit_hard_cap(&mut self, rate: u32) -> &mut Self {
+        self.0.ControlFlags =
+            JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+        self.0.Anonymous.CpuRate = rate;
+
+        self
+    }
+
+    /// Limit the job's CPU usage to the equivalent of `cores` logical processors
+    /// (for example, `1.5` is one and a half cores), regardless of the number of processors of the machine.
+    /// The rate is clamped to the valid range, so asking for more cores than the machine has removes the cap.
+    pub fn limit_hard_cap_cores(&mut self, cores: f64) -> &mut Self {
+        self.limit_hard_cap(rate_from_cores(cores, get_active_processor_count()))
+    }
+
+    /// Limit the job's CPU usage to `percent` of the processor cycles of the whole machine
+    /// (for example, `25.0` is a quarter of all processors).
+    pub fn limit_hard_cap_machine_percent(&mut self, percent: f64) -> &mut Self {
+        self.limit_hard_cap(rate_from_percent(percent))
+    }
+
+    /// Schedule the job's processes relative to other jobs according to `weight`,
+    /// which is a value from 1 to 9 (where 5 is the default weight of unlimited jobs).
+    pub fn limit_weight(&mut self, weight: u32) -> &mut Self {
+        self.0.ControlFlags =
+            JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_WEIGHT_BASED;
+        self.0.Anonymous.Weight = weight;
+
+        self
+    }
+
+    /// Reserve at least `min` and limit the job to at most `max` of the processor cycles.
+    pub fn limit_min_max_rate(&mut self, min: u16, max: u16) -> &mut Self {
+        self.0.ControlFlags =
+            JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_MIN_MAX_RATE;
+        self.0.Anonymous.Anonymous.MinRate = min;
+        self.0.Anonymous.Anonymous.MaxRate = max;
+
+        self
+    }
+
+    /// Return `true` if CPU rate control is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.0
+            .ControlFlags
+            .contains(JOB_OBJECT_CPU_RATE_CONTROL_ENABLE)
+    }
+
+    /// Return the hard cap rate, if a hard cap is set.
+    pub fn hard_cap(&self) -> Option<u32> {
+        let hard_cap = JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+
+        if self.0.ControlFlags.contains(hard_cap) {
+            Some(unsafe { self.0.Anonymous.CpuRate })
+        } else {
+            None
+        }
+    }
+
+    /// Clear all limits.
+    pub fn clear_limits(&mut self) -> &mut Self {
+        self.0.ControlFlags = JOB_OBJECT_CPU_RATE_CONTROL(0);
+        self.0.Anonymous.CpuRate = 0;
+
+        self
+    }
+}
+
+impl fmt::Debug for CpuRateControlInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = self.0.ControlFlags;
+        let mut s = f.debug_struct("CpuRateControlInfo");
+        s.field("ControlFlags", &flags);
+
+        if flags.contains(JOB_OBJECT_CPU_RATE_CONTROL_WEIGHT_BASED) {
+            s.field("Weight", &unsafe { self.0.Anonymous.Weight });
+        } else if flags.contains(JOB_OBJECT_CPU_RATE_CONTROL_MIN_MAX_RATE) {
+            s.field("MinRate", &unsafe { self.0.Anonymous.Anonymous.MinRate });
+            s.field("MaxRate", &unsafe { self.0.Anonymous.Anonymous.MaxRate });
+        } else {
+            s.field("CpuRate", &unsafe { self.0.Anonymous.CpuRate });
+        }
+
+        s.finish()
+    }
+}
+
+impl Job {
+    /// Return the CPU rate control information for a job object.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_cpu_rate_control_information).
+    pub fn query_cpu_rate_control_info(&self) -> Result<CpuRateControlInfo, JobError> {
+        self.query_info()
+    }
+
+    /// Set the CPU rate control information for a job object.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(job = self.handle.0, flags = info.0.ControlFlags.0),
+            err
+        )
+    )]
+    pub fn set_cpu_rate_control_info(&self, info: &CpuRateControlInfo) -> Result<(), JobError> {
+        self.set_info(info)
+    }
+
+    /// Query the current CPU rate control information, let `f` modify it,
+    /// and set the result back to the job.
+    pub fn update_cpu_rate_control_info<F>(&self, f: F) -> Result<CpuRateControlInfo, JobError>
+    where
+        F: FnOnce(&mut CpuRateControlInfo),
+    {
+        let mut info = self.query_cpu_rate_control_info()?;
+        f(&mut info);
+        self.set_cpu_rate_control_info(&info)?;
+
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rate_from_cores, rate_from_percent};
+    use crate::{CpuRateControlInfo, Job};
+
+    #[test]
+    fn rate_normalization() {
+        assert_eq!(rate_from_percent(25.0), 2500);
+        assert_eq!(rate_from_percent(0.0), 1);
+        assert_eq!(rate_from_percent(150.0), 10000);
+
+        assert_eq!(rate_from_cores(1.5, 8), 1875);
+        assert_eq!(rate_from_cores(1.0, 1), 10000);
+        assert_eq!(rate_from_cores(16.0, 4), 10000);
+    }
+
+    #[test]
+    fn hard_cap_limits() {
+        let job = Job::create().unwrap();
+
+        let info = job.query_cpu_rate_control_info().unwrap();
+        assert!(!info.is_enabled());
+
+        let mut info = CpuRateControlInfo::new();
+        info.limit_hard_cap(2000);
+
+        job.set_cpu_rate_control_info(&info).unwrap();
+
+        let info = job.query_cpu_rate_control_info().unwrap();
+        assert_eq!(info.hard_cap(), Some(2000));
+
+        let info = job
+            .update_cpu_rate_control_info(|info| {
+                info.clear_limits();
+            })
+            .unwrap();
+        assert!(!info.is_enabled());
+    }
+}
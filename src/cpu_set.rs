@@ -0,0 +1,391 @@
+use std::{fmt, io, mem, str::FromStr};
+
+use windows::Win32::{
+    Foundation::ERROR_INSUFFICIENT_BUFFER,
+    System::SystemInformation::{
+        GetLogicalProcessorInformationEx, RelationProcessorCore,
+        SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX,
+    },
+};
+
+use crate::JobError;
+
+/// A set of logical processors (of a single processor group), which can be used as an
+/// affinity mask (see `ExtendedLimitInfo::limit_affinity`).
+///
+/// A `CpuSet` can be parsed from, and is displayed as, a list of processor numbers and
+/// (inclusive) ranges, like the lists `taskset --cpu-list` accepts:
+///
+/// ```edition2021
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let cpus: CpuSet = "0-3,8-11".parse()?;
+/// assert_eq!(cpus.mask(), 0xf0f);
+/// assert_eq!(cpus.to_string(), "0-3,8-11");
+///
+/// let mut info = ExtendedLimitInfo::new();
+/// info.limit_affinity(cpus.mask());
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CpuSet(usize);
+
+impl CpuSet {
+    /// The number of processors a set can contain (the number of bits in an affinity mask).
+    pub const CAPACITY: u32 = usize::BITS;
+
+    /// Return an empty set.
+    pub fn new() -> Self {
+        CpuSet(0)
+    }
+
+    /// Return the set of the processors whose bits are set in `mask`.
+    pub fn from_mask(mask: usize) -> Self {
+        CpuSet(mask)
+    }
+
+    /// Return the set as an affinity mask.
+    pub fn mask(&self) -> usize {
+        self.0
+    }
+
+    /// Add the processor `cpu` to the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cpu` is not less than `CpuSet::CAPACITY`.
+    pub fn insert(&mut self, cpu: u32) -> &mut Self {
+        assert!(cpu < Self::CAPACITY, "processor {} is out of range", cpu);
+        self.0 |= 1 << cpu;
+
+        self
+    }
+
+    /// Remove the processor `cpu` from the set.
+    pub fn remove(&mut self, cpu: u32) -> &mut Self {
+        if cpu < Self::CAPACITY {
+            self.0 &= !(1 << cpu);
+        }
+
+        self
+    }
+
+    /// Return `true` if the set contains the processor `cpu`.
+    pub fn contains(&self, cpu: u32) -> bool {
+        cpu < Self::CAPACITY && self.0 & (1 << cpu) != 0
+    }
+
+    /// Return the number of processors in the set.
+    pub fn len(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// Return `true` if the set has no processors.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterate over the processors of the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> {
+        let mask = self.0;
+
+        (0..Self::CAPACITY).filter(move |cpu| mask & (1 << cpu) != 0)
+    }
+
+    /// Return a set with one logical processor (the first hardware thread) of each physical
+    /// core of the first processor group, excluding the SMT siblings, so a job can be pinned
+    /// to one hardware thread per core.
+    ///
+    /// The first processor group is the only one on machines with up to 64 logical
+    /// processors. See `CpuSet::physical_cores_in_group` for the other groups.
+    pub fn physical_cores() -> Result<Self, JobError> {
+        Self::physical_cores_in_group(0)
+    }
+
+    /// Return a set with one logical processor of each physical core of the processor group `group`.
+    pub fn physical_cores_in_group(group: u16) -> Result<Self, JobError> {
+        let cores = processor_cores().map_err(JobError::ProcessorInformationFailed)?;
+
+        let mask = cores
+            .iter()
+            .filter(|core| core.group == group)
+            // Keep only the lowest bit of each core.
+            .fold(0, |mask, core| {
+                mask | (core.mask & core.mask.wrapping_neg())
+            });
+
+        Ok(CpuSet(mask))
+    }
+
+    /// Return the logical processors of the efficiency cores (E-cores) of the first processor
+    /// group, such as for confining background jobs on hybrid CPUs.
+    ///
+    /// These are the cores with the lowest efficiency class. On CPUs which are not hybrid,
+    /// all the cores have the same efficiency class, so all the processors are returned.
+    pub fn efficiency_cores() -> Result<Self, JobError> {
+        Self::cores_by_efficiency_class(false)
+    }
+
+    /// Return the logical processors of the performance cores (P-cores) of the first processor group.
+    ///
+    /// These are the cores with the highest efficiency class. On CPUs which are not hybrid,
+    /// all the cores have the same efficiency class, so all the processors are returned.
+    pub fn performance_cores() -> Result<Self, JobError> {
+        Self::cores_by_efficiency_class(true)
+    }
+
+    /// Return the processors of the cores of the first group with the highest efficiency class
+    /// (if `performance` is `true`), or with the lowest one.
+    fn cores_by_efficiency_class(performance: bool) -> Result<Self, JobError> {
+        let cores = processor_cores().map_err(JobError::ProcessorInformationFailed)?;
+
+        Ok(CpuSet(first_group_cores_by_class(&cores, performance)))
+    }
+}
+
+/// Return the mask of the cores of the first group with the highest efficiency class
+/// (if `performance` is `true`), or with the lowest one, among the cores of the first group.
+fn first_group_cores_by_class(cores: &[ProcessorCore], performance: bool) -> usize {
+    // Only the cores of the first group are masked, so the other groups
+    // must not affect which class is selected.
+    let cores = || cores.iter().filter(|core| core.group == 0);
+
+    let classes = cores().map(|core| core.efficiency_class);
+    let class = match performance {
+        true => classes.max(),
+        false => classes.min(),
+    };
+
+    let Some(class) = class else {
+        return 0;
+    };
+
+    cores()
+        .filter(|core| core.efficiency_class == class)
+        .fold(0, |mask, core| mask | core.mask)
+}
+
+/// A physical processor core, with the mask of its logical processors (hardware threads).
+#[derive(Debug, Clone, Copy)]
+struct ProcessorCore {
+    group: u16,
+    mask: usize,
+    /// Higher classes are more performant (and less efficient). Cores of CPUs which
+    /// are not hybrid all have the same class.
+    efficiency_class: u8,
+}
+
+/// Return the physical processor cores of the system, using `GetLogicalProcessorInformationEx`.
+fn processor_cores() -> Result<Vec<ProcessorCore>, io::Error> {
+    let mut size = 0;
+
+    // The first call only returns the required size.
+    match unsafe { GetLogicalProcessorInformationEx(RelationProcessorCore, None, &mut size) } {
+        Err(err) if err.code() == ERROR_INSUFFICIENT_BUFFER.to_hresult() => {}
+        Err(err) => return Err(err.into()),
+        Ok(()) => return Ok(vec![]),
+    }
+
+    // Use `u64`s, so the buffer is suitably aligned.
+    let mut buf = vec![0u64; (size as usize).div_ceil(mem::size_of::<u64>())];
+
+    unsafe {
+        GetLogicalProcessorInformationEx(
+            RelationProcessorCore,
+            Some(buf.as_mut_ptr() as *mut SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX),
+            &mut size,
+        )
+    }?;
+
+    let mut cores = vec![];
+    let mut offset = 0;
+
+    // Each entry has a variable size, so they are walked using their `Size` field.
+    while offset < size as usize {
+        let info = unsafe {
+            &*((buf.as_ptr() as *const u8).add(offset)
+                as *const SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX)
+        };
+
+        if info.Relationship == RelationProcessorCore {
+            let processor = unsafe { &info.Anonymous.Processor };
+
+            // A core always belongs to a single group.
+            cores.push(ProcessorCore {
+                group: processor.GroupMask[0].Group,
+                mask: processor.GroupMask[0].Mask,
+                efficiency_class: processor.EfficiencyClass,
+            });
+        }
+
+        offset += info.Size as usize;
+    }
+
+    Ok(cores)
+}
+
+impl From<usize> for CpuSet {
+    fn from(mask: usize) -> Self {
+        CpuSet(mask)
+    }
+}
+
+impl From<CpuSet> for usize {
+    fn from(cpus: CpuSet) -> Self {
+        cpus.0
+    }
+}
+
+impl FromIterator<u32> for CpuSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        let mut cpus = CpuSet::new();
+
+        for cpu in iter {
+            cpus.insert(cpu);
+        }
+
+        cpus
+    }
+}
+
+impl FromStr for CpuSet {
+    type Err = JobError;
+
+    /// Parse a comma separated list of processor numbers and ranges, like `0-3,8-11`.
+    /// Whitespace around the items is ignored, and an empty string is an empty set.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || JobError::InvalidCpuSet(s.to_string());
+        let parse_cpu = |cpu: &str| match cpu.trim().parse::<u32>() {
+            Ok(cpu) if cpu < Self::CAPACITY => Ok(cpu),
+            _ => Err(invalid()),
+        };
+
+        let mut cpus = CpuSet::new();
+
+        if s.trim().is_empty() {
+            return Ok(cpus);
+        }
+
+        for item in s.split(',') {
+            let (first, last) = match item.split_once('-') {
+                Some((first, last)) => (parse_cpu(first)?, parse_cpu(last)?),
+                None => {
+                    let cpu = parse_cpu(item)?;
+                    (cpu, cpu)
+                }
+            };
+
+            if first > last {
+                return Err(invalid());
+            }
+
+            for cpu in first..=last {
+                cpus.insert(cpu);
+            }
+        }
+
+        Ok(cpus)
+    }
+}
+
+impl fmt::Display for CpuSet {
+    /// Format the set as a list of processor numbers and ranges, like `0-3,8-11`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cpus = self.iter().peekable();
+        let mut first_item = true;
+
+        while let Some(first) = cpus.next() {
+            let mut last = first;
+            while cpus.next_if_eq(&(last + 1)).is_some() {
+                last += 1;
+            }
+
+            if !first_item {
+                f.write_str(",")?;
+            }
+            first_item = false;
+
+            match last - first {
+                0 => write!(f, "{}", first)?,
+                _ => write!(f, "{}-{}", first, last)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{first_group_cores_by_class, ProcessorCore};
+    use crate::{CpuSet, JobError};
+
+    #[test]
+    fn physical_cores() {
+        let cores = CpuSet::physical_cores().unwrap();
+
+        assert!(!cores.is_empty());
+        assert!(cores.len() <= crate::utils::get_group_active_processor_count(0));
+    }
+
+    #[test]
+    fn hybrid_cores() {
+        let efficiency = CpuSet::efficiency_cores().unwrap();
+        let performance = CpuSet::performance_cores().unwrap();
+
+        assert!(!efficiency.is_empty());
+        assert!(!performance.is_empty());
+
+        // Either the CPU is hybrid and the sets are disjoint, or they are the same set.
+        assert!(efficiency.mask() & performance.mask() == 0 || efficiency == performance);
+    }
+
+    #[test]
+    fn parse_and_format() {
+        let cpus: CpuSet = "0-3,8-11".parse().unwrap();
+        assert_eq!(cpus.mask(), 0xf0f);
+        assert_eq!(cpus.len(), 8);
+        assert_eq!(cpus.to_string(), "0-3,8-11");
+
+        let cpus: CpuSet = " 5, 1 ,2-3".parse().unwrap();
+        assert_eq!(cpus.iter().collect::<Vec<_>>(), [1, 2, 3, 5]);
+        assert_eq!(cpus.to_string(), "1-3,5");
+
+        assert_eq!("".parse::<CpuSet>().unwrap(), CpuSet::new());
+        assert_eq!(CpuSet::new().to_string(), "");
+        assert_eq!(
+            CpuSet::from_mask(usize::MAX).to_string(),
+            format!("0-{}", usize::BITS - 1)
+        );
+
+        for invalid in ["3-1", "a", "1,,2", "0-", &usize::BITS.to_string()] {
+            assert!(matches!(
+                invalid.parse::<CpuSet>(),
+                Err(JobError::InvalidCpuSet(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn efficiency_class_of_first_group() {
+        let core = |group, mask, efficiency_class| ProcessorCore {
+            group,
+            mask,
+            efficiency_class,
+        };
+
+        // The second group has both a lower and a higher class than the first one.
+        let cores = [
+            core(0, 0b0011, 1),
+            core(0, 0b1100, 2),
+            core(1, 0b0001, 0),
+            core(1, 0b0010, 3),
+        ];
+
+        assert_eq!(first_group_cores_by_class(&cores, false), 0b0011);
+        assert_eq!(first_group_cores_by_class(&cores, true), 0b1100);
+        assert_eq!(first_group_cores_by_class(&cores[2..], true), 0);
+    }
+}
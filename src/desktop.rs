@@ -0,0 +1,182 @@
+use std::{ffi::OsStr, iter, os::windows::ffi::OsStrExt};
+
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::GENERIC_ALL,
+        System::StationsAndDesktops::{
+            CloseDesktop, CloseWindowStation, CreateDesktopW, CreateWindowStationW,
+            GetProcessWindowStation, SetProcessWindowStation, DESKTOP_CONTROL_FLAGS, HDESK,
+            HWINSTA,
+        },
+    },
+};
+
+use crate::JobError;
+
+/// A separate desktop (and optionally, a separate window station), which isolates the
+/// windows of the processes spawned onto it from the rest of the user's desktop.
+///
+/// Pass it to `SandboxCommand::desktop` to spawn a process onto it, and pair it with
+/// `UiRestrictions::limit_desktop` and `UiRestrictions::limit_handles`, so the job's
+/// processes cannot switch back to the default desktop or use the windows of other processes.
+///
+/// The desktop and window station are closed when this object is dropped, and are destroyed
+/// by the system once the last process using them exits.
+///
+/// ```edition2021,no_run
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let job = Job::create()?;
+/// let desktop = IsolatedDesktop::create_with_window_station("sandbox-winsta", "sandbox")?;
+///
+/// let mut ui_restrictions = UiRestrictions::new();
+/// ui_restrictions.limit_desktop().limit_handles();
+///
+/// let exit_code = SandboxCommand::new(&job, "cmd")
+///     .args(["/C", "exit 0"])
+///     .desktop(&desktop)
+///     .ui_restrictions(ui_restrictions)
+///     .status()?;
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct IsolatedDesktop {
+    window_station: Option<HWINSTA>,
+    desktop: HDESK,
+    name: String,
+}
+
+// The handles are only closed on drop, and can be used from any thread.
+unsafe impl Send for IsolatedDesktop {}
+unsafe impl Sync for IsolatedDesktop {}
+
+impl IsolatedDesktop {
+    /// Create a new desktop named `desktop` in the window station of the current process.
+    ///
+    /// Processes on this desktop still share the clipboard and global atoms of the window
+    /// station with the current process (which can be limited using `UiRestrictions`).
+    pub fn create(desktop: &str) -> Result<Self, JobError> {
+        let handle = create_desktop(desktop)?;
+
+        Ok(IsolatedDesktop {
+            window_station: None,
+            desktop: handle,
+            name: desktop.to_owned(),
+        })
+    }
+
+    /// Create a new window station named `window_station`, and a new desktop named
+    /// `desktop` in it.
+    ///
+    /// Note that this temporarily switches the window station of the current process,
+    /// so it should not run concurrently with code that creates windows.
+    pub fn create_with_window_station(
+        window_station: &str,
+        desktop: &str,
+    ) -> Result<Self, JobError> {
+        let name = to_wide(window_station);
+
+        let station =
+            unsafe { CreateWindowStationW(PCWSTR(name.as_ptr()), 0, GENERIC_ALL.0, None) }
+                .map_err(|e| JobError::DesktopFailed(e.into()))?;
+
+        // `CreateDesktopW` always creates the desktop in the window station of the current process.
+        let res = unsafe { GetProcessWindowStation() }.and_then(|previous| {
+            unsafe { SetProcessWindowStation(station) }?;
+
+            let handle = create_desktop(desktop);
+
+            unsafe { SetProcessWindowStation(previous) }?;
+
+            Ok(handle)
+        });
+
+        let handle = match res.map_err(|e| JobError::DesktopFailed(e.into())) {
+            Ok(Ok(handle)) => handle,
+            Ok(Err(err)) | Err(err) => {
+                let _ = unsafe { CloseWindowStation(station) };
+
+                return Err(err);
+            }
+        };
+
+        Ok(IsolatedDesktop {
+            window_station: Some(station),
+            desktop: handle,
+            name: format!("{window_station}\\{desktop}"),
+        })
+    }
+
+    /// Return the name of the desktop, as used for `STARTUPINFO::lpDesktop`: either
+    /// `desktop`, or `window_station\desktop` if it was created in a separate window station.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the underlying handle to the desktop.
+    /// Note that this handle will be closed once the `IsolatedDesktop` object is dropped.
+    pub fn handle(&self) -> isize {
+        self.desktop.0
+    }
+}
+
+impl Drop for IsolatedDesktop {
+    fn drop(&mut self) {
+        // The desktop must be closed before its window station.
+        let _ = unsafe { CloseDesktop(self.desktop) };
+
+        if let Some(station) = self.window_station {
+            let _ = unsafe { CloseWindowStation(station) };
+        }
+    }
+}
+
+fn create_desktop(name: &str) -> Result<HDESK, JobError> {
+    let name = to_wide(name);
+
+    unsafe {
+        CreateDesktopW(
+            PCWSTR(name.as_ptr()),
+            PCWSTR::null(),
+            None,
+            DESKTOP_CONTROL_FLAGS(0),
+            GENERIC_ALL.0,
+            None,
+        )
+    }
+    .map_err(|e| JobError::DesktopFailed(e.into()))
+}
+
+fn to_wide(s: &str) -> Vec<u16> {
+    OsStr::new(s).encode_wide().chain(iter::once(0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{IsolatedDesktop, Job, SandboxCommand, UiRestrictions};
+
+    #[test]
+    fn spawns_onto_isolated_desktop() {
+        let desktop =
+            IsolatedDesktop::create_with_window_station("win32job-test-winsta", "win32job-test")
+                .unwrap();
+        assert_eq!(desktop.name(), r"win32job-test-winsta\win32job-test");
+
+        let job = Job::create().unwrap();
+
+        let mut ui_restrictions = UiRestrictions::new();
+        ui_restrictions.limit_desktop().limit_handles();
+
+        let exit_code = SandboxCommand::new(&job, "cmd")
+            .args(["/C", "exit 4"])
+            .desktop(&desktop)
+            .ui_restrictions(ui_restrictions)
+            .status()
+            .unwrap();
+
+        assert_eq!(exit_code, 4);
+    }
+}
@@ -1,15 +1,212 @@
 use std::io;
 use thiserror::Error;
 
+use crate::Pid;
+
+/// Errors which wrap an OS error include its message and code in their `Display`,
+/// for example "Failed to set JobObjectExtendedLimitInformation for job: Access is denied. (os error 5)".
 #[derive(Error, Debug)]
 #[non_exhaustive]
 pub enum JobError {
-    #[error("Failed to create job")]
+    #[error("Failed to create job: {0}")]
     CreateFailed(io::Error),
-    #[error("Failed to assign job")]
+    #[error("Failed to assign job: {0}")]
     AssignFailed(io::Error),
-    #[error("Failed to set info for job")]
-    SetInfoFailed(io::Error),
-    #[error("Failed to get info for job")]
-    GetInfoFailed(io::Error),
+    /// Failed to assign a process to the job. Unlike `AssignFailed`, the process could be
+    /// identified, so the error includes its pid and (when obtainable) its image name.
+    #[error(
+        "Failed to assign process {pid}{} to job{}: {source}",
+        image_name_suffix(.image_name),
+        exited_suffix(*.exited)
+    )]
+    AssignProcessFailed {
+        pid: Pid,
+        /// The file name of the executable of the process, if it could be queried.
+        image_name: Option<String>,
+        /// Whether the process had already exited.
+        exited: bool,
+        source: io::Error,
+    },
+    #[error("Failed to duplicate job handle: {0}")]
+    DuplicateFailed(io::Error),
+    #[error("Failed to open job: {0}")]
+    OpenFailed(io::Error),
+    #[error("Failed to open process: {0}")]
+    OpenProcessFailed(io::Error),
+    #[error("Failed to terminate job: {0}")]
+    TerminateFailed(io::Error),
+    #[error("Failed to terminate process: {0}")]
+    TerminateProcessFailed(io::Error),
+    #[error("The process handle is missing the {0} access right, which is required to assign it to a job")]
+    MissingProcessAccess(&'static str),
+    #[error("Process {0} is not in the job")]
+    ProcessNotInJob(Pid),
+    #[error("The process has already exited")]
+    ProcessExited,
+    #[error("Failed to spawn process: {0}")]
+    SpawnFailed(io::Error),
+    #[error("Failed to resume process: {0}")]
+    ResumeFailed(io::Error),
+    #[error("Failed to create restricted token: {0}")]
+    RestrictedTokenFailed(io::Error),
+    #[error("Failed to create desktop: {0}")]
+    DesktopFailed(io::Error),
+    #[error("The job of the current process does not allow breakaway")]
+    BreakawayNotAllowed,
+    #[error("Invalid scheduling class {0}: valid classes are 0 to 9, and classes greater than 5 require the `SE_INC_BASE_PRIORITY_NAME` privilege")]
+    InvalidSchedulingClass(u8),
+    #[error("Invalid priority class {0:#x}")]
+    InvalidPriorityClass(u32),
+    #[error("Invalid limit: {0}")]
+    InvalidLimit(#[from] LimitValidationError),
+    #[error("Invalid CPU set `{0}`: expected a list of processors and ranges, like `0-3,8-11`")]
+    InvalidCpuSet(String),
+    #[error("Failed to get processor information: {0}")]
+    ProcessorInformationFailed(io::Error),
+    #[error("Failed to list the processes of the system: {0}")]
+    ProcessSnapshotFailed(io::Error),
+    #[error("Failed to trim the working set of process {pid}: {source}")]
+    TrimFailed { pid: Pid, source: io::Error },
+    /// Failed to apply an information class, after which the classes which were already applied
+    /// were rolled back. Rolling back is best-effort: `restored` is `false` if restoring
+    /// one of them failed, in which case the job is left partially configured.
+    #[error("Failed to apply {class} to job{}", restored_suffix(*.restored))]
+    ApplyConfigFailed {
+        class: &'static str,
+        restored: bool,
+        source: Box<JobError>,
+    },
+    #[error("Failed to get {class} for job: {source}")]
+    GetInfoFailed {
+        class: &'static str,
+        source: io::Error,
+    },
+    #[error("Failed to set {class} for job: {source}")]
+    SetInfoFailed {
+        class: &'static str,
+        source: io::Error,
+    },
+    /// Failed to query an information class which is not wrapped by this crate
+    /// (see `job.query_information_raw`), identified by its `JOBOBJECTINFOCLASS` value.
+    #[error("Failed to get information class {class_id} for job: {source}")]
+    GetRawInfoFailed { class_id: i32, source: io::Error },
+    /// Failed to set an information class which is not wrapped by this crate
+    /// (see `job.set_information_raw`), identified by its `JOBOBJECTINFOCLASS` value.
+    #[error("Failed to set information class {class_id} for job: {source}")]
+    SetRawInfoFailed { class_id: i32, source: io::Error },
+    /// A caller-provided buffer was too small for a variable-length query: the process id list
+    /// (`required` process ids) or the processor groups (`required` groups) of the job.
+    /// The I/O rate control query never returns this error, since the system allocates its buffer.
+    #[error("The buffer for {class} is too small: room for {required} entries is required")]
+    BufferTooSmall {
+        class: &'static str,
+        required: usize,
+    },
+    #[error("Failed to spawn monitor thread: {0}")]
+    MonitorThreadFailed(io::Error),
+    #[error("A job monitor callback (or a trimmer or governor policy) panicked: {0}")]
+    MonitorPanicked(String),
+    #[error("The monitor thread did not stop within the timeout")]
+    MonitorShutdownTimedOut,
+    #[error("Invalid job configuration file: {0}")]
+    InvalidConfigFile(String),
+    #[error("ETW operation failed: {0}")]
+    EtwFailed(io::Error),
+    #[error("Completion port operation failed: {0}")]
+    CompletionPortFailed(io::Error),
+}
+
+/// An invalid limit, as returned by the checked setters of `ExtendedLimitInfo`
+/// (like `try_limit_working_memory`).
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LimitValidationError {
+    #[error("The minimum working set size ({min}) is greater than the maximum ({max})")]
+    WorkingSetMinExceedsMax { min: usize, max: usize },
+    #[error("Invalid scheduling class {0}: valid classes are 0 to 9")]
+    SchedulingClassOutOfRange(u8),
+    #[error("The affinity mask is empty")]
+    EmptyAffinity,
+    #[error(
+        "The affinity mask {affinity:#x} is not a subset of the system affinity mask {system:#x}"
+    )]
+    AffinityNotInSystemMask { affinity: usize, system: usize },
+    #[error("The active process limit must be at least 1")]
+    ZeroActiveProcesses,
+    #[error("The memory limit must be greater than 0")]
+    ZeroMemoryLimit,
+    #[error("Invalid CPU rate {0}: valid rates are 1 to 10000 (0.01% to 100%)")]
+    CpuRateOutOfRange(u32),
+}
+
+fn image_name_suffix(image_name: &Option<String>) -> String {
+    image_name
+        .as_ref()
+        .map(|image_name| format!(" ({})", image_name))
+        .unwrap_or_default()
+}
+
+fn restored_suffix(restored: bool) -> &'static str {
+    if restored {
+        " (the previous configuration was restored)"
+    } else {
+        " (the previous configuration could not be fully restored)"
+    }
+}
+
+fn exited_suffix(exited: bool) -> &'static str {
+    if exited {
+        " (the process has already exited)"
+    } else {
+        ""
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::{JobError, Pid};
+
+    #[test]
+    fn display_includes_os_error() {
+        let err = JobError::SetInfoFailed {
+            class: "JobObjectExtendedLimitInformation",
+            source: io::Error::from_raw_os_error(5),
+        };
+
+        let message = err.to_string();
+        assert!(message.starts_with("Failed to set JobObjectExtendedLimitInformation for job: "));
+        assert!(message.ends_with("(os error 5)"));
+    }
+
+    #[test]
+    fn display_includes_process() {
+        let err = JobError::AssignProcessFailed {
+            pid: Pid::new(42),
+            image_name: Some("cmd.exe".to_string()),
+            exited: true,
+            source: io::Error::from_raw_os_error(5),
+        };
+
+        let message = err.to_string();
+        assert!(message.starts_with(
+            "Failed to assign process 42 (cmd.exe) to job (the process has already exited): "
+        ));
+    }
+
+    #[test]
+    fn display_reports_restore() {
+        let err = |restored| JobError::ApplyConfigFailed {
+            class: "cpu_rate_control",
+            restored,
+            source: Box::new(JobError::ProcessExited),
+        };
+
+        assert_eq!(
+            err(true).to_string(),
+            "Failed to apply cpu_rate_control to job (the previous configuration was restored)"
+        );
+        assert!(!err(false).to_string().contains("was restored"));
+    }
 }
@@ -0,0 +1,252 @@
+use std::{io, mem, sync::Arc};
+
+use windows::{
+    core::GUID,
+    Win32::System::Diagnostics::Etw::{
+        EventProviderSetTraits, EventRegister, EventSetInformation, EventUnregister, EventWrite,
+        EVENT_DATA_DESCRIPTOR, EVENT_DATA_DESCRIPTOR_0, EVENT_DATA_DESCRIPTOR_TYPE_EVENT_METADATA,
+        EVENT_DATA_DESCRIPTOR_TYPE_PROVIDER_METADATA, EVENT_DESCRIPTOR,
+    },
+};
+
+use crate::{JobError, JobMonitor, JobNotification, NotificationFilter};
+
+/// The level of errors, in the ETW event descriptor.
+const LEVEL_ERROR: u8 = 2;
+/// The level of warnings (limit violations), in the ETW event descriptor.
+const LEVEL_WARNING: u8 = 3;
+/// The level of informational events (process lifecycle), in the ETW event descriptor.
+const LEVEL_INFORMATION: u8 = 4;
+
+/// The channel which marks an event as a TraceLogging (self-describing) event.
+const CHANNEL_TRACELOGGING: u8 = 11;
+
+/// The TraceLogging type of `UInt32` fields.
+const IN_TYPE_UINT32: u8 = 8;
+/// The TraceLogging type of `UInt64` fields.
+const IN_TYPE_UINT64: u8 = 10;
+
+/// A registered ETW provider, which emits a TraceLogging (self-describing) event
+/// for each job notification, so it can be decoded without a manifest
+/// (for example, by WPA, `tracelog` or `Microsoft.Diagnostics.Tracing.TraceEvent`).
+///
+/// Each event is named after the notification (like `NewProcess` or `JobMemoryLimit`), with the
+/// `JOB_OBJECT_MSG_*` message as its id and a `Message` field. Notifications about a process
+/// also have a `Pid` field, and unknown notifications a `Value` field with the raw value.
+/// Limit violations are logged as warnings, abnormal exits as errors, and other
+/// notifications as informational events.
+///
+/// ```edition2021,no_run
+/// use std::sync::Arc;
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let job = Job::create()?;
+/// let monitor = JobMonitor::spawn(&job)?;
+///
+/// let provider = EtwProvider::register("MyCompany.MyService.Jobs", 0x3d1f5b7e_2a4c_4e68_9b0d_6c8e2f4a1b3d)?;
+/// monitor.emit_etw_events(Arc::new(provider));
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct EtwProvider {
+    handle: u64,
+    /// The TraceLogging provider traits, which hold the name of the provider.
+    traits: Vec<u8>,
+}
+
+impl EtwProvider {
+    /// Register a provider named `name`, with the provider id `guid`.
+    ///
+    /// Every application should use its own provider id, so its events can be enabled
+    /// separately. By convention, TraceLogging providers derive it from their name
+    /// (like `[System.Diagnostics.Tracing.EventSource]::new("MyCompany.MyService.Jobs").Guid`).
+    pub fn register(name: &str, guid: u128) -> Result<Self, JobError> {
+        let guid = GUID::from_u128(guid);
+        let mut handle = 0;
+
+        let res = unsafe { EventRegister(&guid, None, None, &mut handle) };
+        if res != 0 {
+            return Err(JobError::EtwFailed(io::Error::from_raw_os_error(
+                res as i32,
+            )));
+        }
+
+        let mut traits = vec![0, 0];
+        traits.extend_from_slice(name.as_bytes());
+        traits.push(0);
+        let len = traits.len() as u16;
+        traits[..2].copy_from_slice(&len.to_le_bytes());
+
+        let provider = Self { handle, traits };
+
+        // Only used by some consumers (events always carry the traits), so this may fail
+        // before Windows 10.
+        let res = unsafe {
+            EventSetInformation(
+                provider.handle,
+                EventProviderSetTraits,
+                provider.traits.as_ptr() as *const _,
+                provider.traits.len() as u32,
+            )
+        };
+        if res != 0 {
+            log_debug!("Failed to set the ETW provider traits: {}", res);
+        }
+
+        Ok(provider)
+    }
+
+    /// Emit an event for `notification`. Nothing is written if no trace session
+    /// has enabled the provider.
+    pub fn write_notification(&self, notification: &JobNotification) -> Result<(), JobError> {
+        let level = match notification {
+            JobNotification::NewProcess(_)
+            | JobNotification::ExitProcess(_)
+            | JobNotification::ActiveProcessZero
+            | JobNotification::Unknown { .. } => LEVEL_INFORMATION,
+            JobNotification::AbnormalExitProcess(_) => LEVEL_ERROR,
+            _ => LEVEL_WARNING,
+        };
+
+        let message = notification.message();
+
+        let descriptor = EVENT_DESCRIPTOR {
+            Id: message as u16,
+            Channel: CHANNEL_TRACELOGGING,
+            Level: level,
+            ..Default::default()
+        };
+
+        let pid = notification.pid().map(|pid| pid.as_u32());
+        let value = match notification {
+            JobNotification::Unknown { value, .. } => Some(*value as u64),
+            _ => None,
+        };
+
+        let mut metadata = EventMetadata::new(notification.name());
+        metadata.field("Message", IN_TYPE_UINT32);
+        if pid.is_some() {
+            metadata.field("Pid", IN_TYPE_UINT32);
+        }
+        if value.is_some() {
+            metadata.field("Value", IN_TYPE_UINT64);
+        }
+        let metadata = metadata.finish();
+
+        let mut data = vec![
+            data_descriptor(&self.traits, EVENT_DATA_DESCRIPTOR_TYPE_PROVIDER_METADATA),
+            data_descriptor(&metadata, EVENT_DATA_DESCRIPTOR_TYPE_EVENT_METADATA),
+            field_descriptor(&message),
+        ];
+        data.extend(pid.as_ref().map(field_descriptor));
+        data.extend(value.as_ref().map(field_descriptor));
+
+        let res = unsafe { EventWrite(self.handle, &descriptor, Some(&data)) };
+        if res != 0 {
+            return Err(JobError::EtwFailed(io::Error::from_raw_os_error(
+                res as i32,
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// The TraceLogging metadata of an event: its name, and the name and type of each field.
+struct EventMetadata(Vec<u8>);
+
+impl EventMetadata {
+    fn new(name: &str) -> Self {
+        // The size (filled in by `finish`), and no event tags.
+        let mut buf = vec![0, 0, 0];
+        buf.extend_from_slice(name.as_bytes());
+        buf.push(0);
+
+        Self(buf)
+    }
+
+    fn field(&mut self, name: &str, in_type: u8) {
+        self.0.extend_from_slice(name.as_bytes());
+        self.0.push(0);
+        self.0.push(in_type);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let len = self.0.len() as u16;
+        self.0[..2].copy_from_slice(&len.to_le_bytes());
+
+        self.0
+    }
+}
+
+fn data_descriptor(buf: &[u8], ty: u32) -> EVENT_DATA_DESCRIPTOR {
+    EVENT_DATA_DESCRIPTOR {
+        Ptr: buf.as_ptr() as u64,
+        Size: buf.len() as u32,
+        Anonymous: EVENT_DATA_DESCRIPTOR_0 { Reserved: ty },
+    }
+}
+
+fn field_descriptor<T>(value: &T) -> EVENT_DATA_DESCRIPTOR {
+    EVENT_DATA_DESCRIPTOR {
+        Ptr: value as *const T as u64,
+        Size: mem::size_of::<T>() as u32,
+        ..Default::default()
+    }
+}
+
+impl Drop for EtwProvider {
+    fn drop(&mut self) {
+        let res = unsafe { EventUnregister(self.handle) };
+        if res != 0 {
+            log_warn!("Failed to unregister ETW provider: {}", res);
+        }
+    }
+}
+
+impl JobMonitor {
+    /// Emit an ETW event with `provider` for each notification of the job.
+    pub fn emit_etw_events(&self, provider: Arc<EtwProvider>) {
+        self.on_notification(NotificationFilter::ALL, move |notification| {
+            if let Err(err) = provider.write_notification(&notification) {
+                log_debug!("Failed to write ETW event: {}", err);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventMetadata, IN_TYPE_UINT32};
+    use crate::{EtwProvider, JobNotification, Pid};
+
+    #[test]
+    fn event_metadata() {
+        let mut metadata = EventMetadata::new(JobNotification::NewProcess(Pid::new(1)).name());
+        metadata.field("Pid", IN_TYPE_UINT32);
+
+        assert_eq!(metadata.finish(), b"\x13\0\0NewProcess\0Pid\0\x08");
+    }
+
+    #[test]
+    fn write_notification() {
+        let provider =
+            EtwProvider::register("Win32Job.Tests", 0x8e4b2c6a_1f3d_4a5b_9c7e_0d2f4b6a8c1e)
+                .unwrap();
+
+        provider
+            .write_notification(&JobNotification::NewProcess(Pid::new(42)))
+            .unwrap();
+        provider
+            .write_notification(&JobNotification::JobMemoryLimit(Pid::new(42)))
+            .unwrap();
+        provider
+            .write_notification(&JobNotification::Unknown {
+                message: 99,
+                value: 7,
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use crate::monitor::panic_message;
+use crate::{Job, JobError, LimitValidationError};
+
+/// Adjusts a job's CPU hard cap at runtime according to a user-provided policy.
+///
+/// Every `interval`, the policy is called with the currently applied hard cap
+/// (`None` if the job is uncapped) and returns the desired one.
+/// When the desired cap differs from the current one, it is applied to the job.
+/// Caps outside of 1 to 10000 are not applied, and are reported as `JobError::InvalidLimit`.
+///
+/// The governor stops when `stop` is called, or when it is dropped. If the policy panics,
+/// the governor stops, and `stop` returns the panic message as `JobError::MonitorPanicked`.
+///
+/// ```edition2021
+/// use std::time::Duration;
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let job = Job::create()?;
+///
+/// let governor = CpuGovernor::spawn(&job, Duration::from_millis(500), |_current| {
+///     // For example, allow more CPU when the user is idle.
+///     Some(2500)
+/// })?;
+///
+/// governor.stop()?;
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CpuGovernor {
+    shared: Arc<Shared>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    stopped: bool,
+    last_error: Option<JobError>,
+    panicked: Option<String>,
+}
+
+impl CpuGovernor {
+    /// Start governing the CPU rate of `job`, calling `policy` every `interval`.
+    pub fn spawn<F>(job: &Job, interval: Duration, mut policy: F) -> Result<Self, JobError>
+    where
+        F: FnMut(Option<u32>) -> Option<u32> + Send + 'static,
+    {
+        let job = job.try_clone()?;
+        let mut current = job.query_cpu_rate_control_info()?.hard_cap();
+
+        let shared = Arc::new(Shared::default());
+        let thread_shared = shared.clone();
+
+        let thread = thread::Builder::new()
+            .name("win32job-governor".to_owned())
+            .spawn(move || loop {
+                let desired = match panic::catch_unwind(AssertUnwindSafe(|| policy(current))) {
+                    Ok(desired) => desired,
+                    Err(payload) => {
+                        let message = panic_message(payload.as_ref());
+                        log_warn!("CPU governor policy panicked: {}", message);
+
+                        let mut state = thread_shared.state.lock().unwrap();
+                        state.panicked = Some(message);
+                        state.stopped = true;
+                        break;
+                    }
+                };
+
+                let result = if let Some(rate @ (0 | 10001..)) = desired {
+                    Err(LimitValidationError::CpuRateOutOfRange(rate).into())
+                } else if desired != current {
+                    job.update_cpu_rate_control_info(|info| match desired {
+                        Some(rate) => {
+                            info.limit_hard_cap(rate);
+                        }
+                        None => {
+                            info.clear_limits();
+                        }
+                    })
+                    .map(|info| {
+                        log_debug!("Changed CPU hard cap from {:?} to {:?}", current, desired);
+                        current = info.hard_cap();
+                    })
+                } else {
+                    Ok(())
+                };
+
+                let state = thread_shared.state.lock().unwrap();
+                let (mut state, _) = thread_shared
+                    .wake
+                    .wait_timeout_while(state, interval, |state| !state.stopped)
+                    .unwrap();
+
+                if let Err(err) = result {
+                    log_warn!("Failed to change CPU hard cap to {:?}: {}", desired, err);
+                    state.last_error = Some(err);
+                }
+
+                if state.stopped {
+                    break;
+                }
+            })
+            .map_err(JobError::MonitorThreadFailed)?;
+
+        Ok(Self {
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// Stop the governor, leaving the last applied cap in place.
+    /// Return `JobError::MonitorPanicked` if the policy panicked, or else the last error
+    /// which occurred while applying a cap, if any.
+    pub fn stop(mut self) -> Result<(), JobError> {
+        self.join()?;
+
+        let mut state = self.shared.state.lock().unwrap();
+
+        if let Some(message) = state.panicked.take() {
+            return Err(JobError::MonitorPanicked(message));
+        }
+
+        match state.last_error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn join(&mut self) -> Result<(), JobError> {
+        // The lock is poisoned only if the thread panicked outside of the policy,
+        // which is reported by `join`.
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .stopped = true;
+        self.shared.wake.notify_all();
+
+        match self.thread.take().map(thread::JoinHandle::join) {
+            Some(Err(payload)) => Err(JobError::MonitorPanicked(panic_message(payload.as_ref()))),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Drop for CpuGovernor {
+    fn drop(&mut self) {
+        let _ = self.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{CpuGovernor, Job, JobError, LimitValidationError};
+
+    #[test]
+    fn governor_applies_policy() {
+        let job = Job::create().unwrap();
+
+        let governor =
+            CpuGovernor::spawn(&job, Duration::from_millis(10), |_current| Some(3000)).unwrap();
+
+        std::thread::sleep(Duration::from_millis(100));
+        governor.stop().unwrap();
+
+        let info = job.query_cpu_rate_control_info().unwrap();
+        assert_eq!(info.hard_cap(), Some(3000));
+    }
+
+    #[test]
+    fn rejects_invalid_caps() {
+        let job = Job::create().unwrap();
+
+        let governor =
+            CpuGovernor::spawn(&job, Duration::from_millis(10), |_current| Some(20000)).unwrap();
+
+        std::thread::sleep(Duration::from_millis(100));
+        let err = governor.stop().unwrap_err();
+        assert!(matches!(
+            err,
+            JobError::InvalidLimit(LimitValidationError::CpuRateOutOfRange(20000))
+        ));
+
+        let info = job.query_cpu_rate_control_info().unwrap();
+        assert_eq!(info.hard_cap(), None);
+    }
+
+    #[test]
+    fn policy_panic() {
+        let job = Job::create().unwrap();
+
+        let governor = CpuGovernor::spawn(&job, Duration::from_millis(10), |_current| {
+            panic!("policy failed");
+        })
+        .unwrap();
+
+        let err = governor.stop().unwrap_err();
+        assert!(matches!(err, JobError::MonitorPanicked(message) if message == "policy failed"));
+    }
+}
@@ -0,0 +1,181 @@
+use std::{
+    os::windows::io::{AsRawHandle, RawHandle},
+    process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, ExitStatus, Output, Stdio},
+};
+
+use crate::command::spawn_in_job;
+use crate::{Job, JobError};
+
+/// Spawn a `std::process::Command` as a group: the process and all of its descendants,
+/// which are contained in a new job, so the whole tree can be killed and waited for.
+///
+/// This mirrors the `CommandGroup` trait of the `command-group` crate, so code which uses
+/// its `group_spawn` can switch to (or combine it with) `win32job` jobs.
+/// Note that the creation flags of the command are replaced.
+///
+/// ```edition2021
+/// use std::process::Command;
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let mut child = Command::new("cmd")
+///     .args(["/C", "ping -n 30 127.0.0.1 > NUL"])
+///     .group_spawn()?;
+///
+/// // Kills both `cmd` and `ping`.
+/// child.kill()?;
+/// child.wait()?;
+/// #   Ok(())
+/// # }
+/// ```
+pub trait CommandGroupExt {
+    /// Spawn the command inside a new job, and return a handle to the group.
+    fn group_spawn(&mut self) -> Result<GroupChild, JobError>;
+
+    /// Spawn the command as a group, and wait for all the processes of the group to exit.
+    /// Return the exit status of the group leader.
+    fn group_status(&mut self) -> Result<ExitStatus, JobError> {
+        self.group_spawn()?.wait()
+    }
+
+    /// Spawn the command as a group, wait for all the processes of the group to exit, and
+    /// collect the output of the group leader. Its standard output and error are always captured.
+    fn group_output(&mut self) -> Result<Output, JobError>;
+}
+
+impl CommandGroupExt for Command {
+    fn group_spawn(&mut self) -> Result<GroupChild, JobError> {
+        let job = Job::create()?;
+        let child = spawn_in_job(&job, self, 0)?;
+
+        Ok(GroupChild { job, child })
+    }
+
+    fn group_output(&mut self) -> Result<Output, JobError> {
+        self.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        self.group_spawn()?.wait_with_output()
+    }
+}
+
+/// A process group spawned by `CommandGroupExt::group_spawn`: the group leader (the spawned
+/// process) and the job which contains it and all of its descendants.
+///
+/// Dropping a `GroupChild` does not kill the group (like dropping a `std::process::Child`).
+#[derive(Debug)]
+pub struct GroupChild {
+    job: Job,
+    child: Child,
+}
+
+impl GroupChild {
+    /// Return the pid of the group leader.
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Return the job which contains the group.
+    pub fn job(&self) -> &Job {
+        &self.job
+    }
+
+    /// Return the group leader.
+    pub fn inner(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// Return the job and the group leader.
+    pub fn into_parts(self) -> (Job, Child) {
+        (self.job, self.child)
+    }
+
+    /// Return the standard input of the group leader, if it was piped.
+    pub fn stdin(&mut self) -> &mut Option<ChildStdin> {
+        &mut self.child.stdin
+    }
+
+    /// Return the standard output of the group leader, if it was piped.
+    pub fn stdout(&mut self) -> &mut Option<ChildStdout> {
+        &mut self.child.stdout
+    }
+
+    /// Return the standard error of the group leader, if it was piped.
+    pub fn stderr(&mut self) -> &mut Option<ChildStderr> {
+        &mut self.child.stderr
+    }
+
+    /// Kill all the processes of the group, with exit code 1.
+    pub fn kill(&mut self) -> Result<(), JobError> {
+        self.job.terminate(1)
+    }
+
+    /// Wait for all the processes of the group to exit, and return the exit status
+    /// of the group leader.
+    pub fn wait(&mut self) -> Result<ExitStatus, JobError> {
+        let status = self.child.wait().map_err(JobError::SpawnFailed)?;
+        self.job.wait_until_empty(None)?;
+
+        Ok(status)
+    }
+
+    /// Return the exit status of the group leader if all the processes of the group
+    /// have exited, without blocking.
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>, JobError> {
+        if !self.job.is_empty()? {
+            return Ok(None);
+        }
+
+        self.child.try_wait().map_err(JobError::SpawnFailed)
+    }
+
+    /// Wait for all the processes of the group to exit, and collect the output of the group leader.
+    pub fn wait_with_output(self) -> Result<Output, JobError> {
+        let output = self
+            .child
+            .wait_with_output()
+            .map_err(JobError::SpawnFailed)?;
+        self.job.wait_until_empty(None)?;
+
+        Ok(output)
+    }
+}
+
+impl AsRawHandle for GroupChild {
+    fn as_raw_handle(&self) -> RawHandle {
+        self.child.as_raw_handle()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use crate::CommandGroupExt;
+
+    #[test]
+    fn kills_whole_group() {
+        let mut child = Command::new("cmd")
+            .args(["/C", "ping -n 30 127.0.0.1 > NUL"])
+            .group_spawn()
+            .unwrap();
+
+        assert!(child.try_wait().unwrap().is_none());
+
+        child.kill().unwrap();
+        let status = child.wait().unwrap();
+
+        assert_eq!(status.code(), Some(1));
+        assert!(child.job().is_empty().unwrap());
+    }
+
+    #[test]
+    fn group_output() {
+        let output = Command::new("cmd")
+            .args(["/C", "echo hello"])
+            .group_output()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
+    }
+}
@@ -0,0 +1,82 @@
+use std::{ffi::c_void, io, mem};
+
+use windows::{
+    Wdk::Foundation::{NtQueryObject, ObjectBasicInformation, OBJECT_INFORMATION_CLASS},
+    Win32::{
+        Foundation::{
+            CloseHandle, HANDLE, STATUS_BUFFER_OVERFLOW, STATUS_INFO_LENGTH_MISMATCH,
+            UNICODE_STRING,
+        },
+        System::WindowsProgramming::PUBLIC_OBJECT_BASIC_INFORMATION,
+    },
+};
+
+use crate::utils::unicode_string_to_string;
+
+/// `ObjectNameInformation`, which is missing from the bindings.
+const OBJECT_NAME_INFORMATION_CLASS: OBJECT_INFORMATION_CLASS = OBJECT_INFORMATION_CLASS(1);
+
+/// An owned handle, which is closed when dropped.
+#[derive(Debug)]
+pub(crate) struct OwnedHandle(pub(crate) HANDLE);
+
+unsafe impl Send for OwnedHandle {}
+unsafe impl Sync for OwnedHandle {}
+
+impl Drop for OwnedHandle {
+    fn drop(&mut self) {
+        if let Err(err) = unsafe { CloseHandle(self.0) } {
+            log_warn!("Failed to close handle {:?}: {}", self.0, err);
+        }
+    }
+}
+
+/// Return the access rights granted to `handle`.
+pub(crate) fn granted_access(handle: HANDLE) -> Result<u32, io::Error> {
+    let mut info = PUBLIC_OBJECT_BASIC_INFORMATION::default();
+
+    unsafe {
+        NtQueryObject(
+            handle,
+            ObjectBasicInformation,
+            Some(&mut info as *mut _ as *mut c_void),
+            mem::size_of_val(&info) as u32,
+            None,
+        )
+    }
+    .ok()?;
+
+    Ok(info.GrantedAccess)
+}
+
+/// Return the kernel name of the object `handle` refers to (empty for anonymous objects).
+pub(crate) fn object_name(handle: HANDLE) -> Result<String, io::Error> {
+    // Use `u64`s, so the buffer is aligned for the `UNICODE_STRING`.
+    let mut buf = vec![0u64; 128];
+
+    loop {
+        let mut len = 0;
+
+        let status = unsafe {
+            NtQueryObject(
+                handle,
+                OBJECT_NAME_INFORMATION_CLASS,
+                Some(buf.as_mut_ptr() as *mut c_void),
+                mem::size_of_val(buf.as_slice()) as u32,
+                Some(&mut len),
+            )
+        };
+
+        if status == STATUS_INFO_LENGTH_MISMATCH || status == STATUS_BUFFER_OVERFLOW {
+            buf.resize((len as usize).div_ceil(mem::size_of::<u64>()), 0);
+            continue;
+        }
+
+        status.ok()?;
+
+        // The name is a `UNICODE_STRING` which points into `buf`.
+        let name = unsafe { &*(buf.as_ptr() as *const UNICODE_STRING) };
+
+        return Ok(unicode_string_to_string(name));
+    }
+}
@@ -0,0 +1,141 @@
+use std::{io, time::SystemTime};
+
+use windows::Win32::{
+    Foundation::{ERROR_INVALID_PARAMETER, HANDLE},
+    System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE},
+};
+
+use crate::handle::OwnedHandle;
+use crate::utils;
+use crate::{Job, JobError, Pid};
+
+/// A process, identified by its pid and creation time.
+///
+/// A pid can be reused by a new process once the process exits, so long-lived supervisors
+/// should compare identities (rather than pids) before acting on a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessIdentity {
+    /// The pid of the process.
+    pub pid: Pid,
+    /// When the process was created.
+    pub creation_time: SystemTime,
+}
+
+impl ProcessIdentity {
+    /// Return the identity of the process which currently has the given `pid`.
+    pub fn of_pid(pid: impl Into<Pid>) -> Result<Self, JobError> {
+        let pid = pid.into().as_u32();
+        let handle = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }
+            .map_err(|e| JobError::OpenProcessFailed(e.into()))?;
+        let handle = OwnedHandle(handle);
+
+        Self::of_handle(handle.0, pid).map_err(JobError::OpenProcessFailed)
+    }
+
+    /// Return the identity of the process `pid`, opened as `handle`
+    /// (with `PROCESS_QUERY_LIMITED_INFORMATION`).
+    pub(crate) fn of_handle(handle: HANDLE, pid: u32) -> Result<Self, io::Error> {
+        Ok(Self {
+            pid: Pid::new(pid),
+            creation_time: utils::get_process_times(handle.0)?.creation_time,
+        })
+    }
+
+    /// Return `true` if the pid still refers to this process
+    /// (which might have exited, if other handles to it are still open).
+    pub fn is_current(&self) -> bool {
+        Self::of_pid(self.pid).is_ok_and(|identity| identity == *self)
+    }
+}
+
+impl Job {
+    /// Like `query_process_id_list`, but return the identity of each process.
+    /// Processes which exit before they can be opened are skipped.
+    pub fn query_process_identities(&self) -> Result<Vec<ProcessIdentity>, JobError> {
+        Ok(self
+            .processes()?
+            .iter()
+            .filter_map(|process| process.identity().ok())
+            .collect())
+    }
+
+    /// Terminate the process `identity` of the job, using `exit_code` as its exit code.
+    ///
+    /// Unlike `kill_process`, the creation time of the process is checked as well,
+    /// so a stale identity never kills an unrelated process which reused the pid.
+    /// Returns `JobError::ProcessExited` if the process is gone, and `JobError::ProcessNotInJob`
+    /// if it is not associated with the job (the process is not terminated in these cases).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), err)
+    )]
+    pub fn kill(&self, identity: ProcessIdentity, exit_code: u32) -> Result<(), JobError> {
+        let handle = match unsafe {
+            OpenProcess(
+                PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_TERMINATE,
+                false,
+                identity.pid.as_u32(),
+            )
+        } {
+            Ok(handle) => OwnedHandle(handle),
+            Err(e) if e.code() == ERROR_INVALID_PARAMETER.to_hresult() => {
+                return Err(JobError::ProcessExited)
+            }
+            Err(e) => return Err(JobError::OpenProcessFailed(e.into())),
+        };
+
+        // The identity is checked on the opened handle, so the pid cannot be reused
+        // between the check and the termination.
+        let current = ProcessIdentity::of_handle(handle.0, identity.pid.as_u32())
+            .map_err(JobError::OpenProcessFailed)?;
+
+        if current != identity {
+            return Err(JobError::ProcessExited);
+        }
+
+        self.kill_process_handle(handle.0, identity.pid.as_u32(), exit_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Job, ProcessIdentity};
+
+    #[test]
+    fn identities() {
+        let job = Job::create().unwrap();
+        job.assign_current_process().unwrap();
+
+        let current = ProcessIdentity::of_pid(std::process::id()).unwrap();
+        assert!(current.is_current());
+
+        let identities = job.query_process_identities().unwrap();
+        assert!(identities.contains(&current));
+    }
+
+    #[cfg(all(windows, feature = "full"))]
+    #[test]
+    fn kill_by_identity() {
+        use std::time::Duration;
+
+        use crate::test_util::DisposableChild;
+        use crate::JobError;
+
+        let job = Job::create().unwrap();
+
+        let mut child = DisposableChild::spawn_in(&job).unwrap();
+
+        let identity = ProcessIdentity::of_pid(child.id()).unwrap();
+
+        let stale = ProcessIdentity {
+            creation_time: identity.creation_time - Duration::from_secs(1),
+            ..identity
+        };
+        assert!(matches!(job.kill(stale, 3), Err(JobError::ProcessExited)));
+        assert!(child.try_wait().unwrap().is_none());
+
+        job.kill(identity, 3).unwrap();
+        assert_eq!(child.wait().unwrap().code(), Some(3));
+    }
+}
@@ -0,0 +1,365 @@
+use std::{ffi::c_void, mem, sync::PoisonError};
+
+use windows::Win32::System::JobObjects::{
+    JobObjectBasicAccountingInformation, JobObjectBasicAndIoAccountingInformation,
+    JobObjectExtendedLimitInformation, QueryInformationJobObject, SetInformationJobObject,
+    JOBOBJECTINFOCLASS, JOBOBJECT_BASIC_ACCOUNTING_INFORMATION,
+    JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+};
+#[cfg(feature = "full")]
+use windows::Win32::System::JobObjects::{
+    JobObjectBasicUIRestrictions, JobObjectCpuRateControlInformation,
+    JobObjectNetRateControlInformation, JobObjectNotificationLimitInformation,
+    JobObjectNotificationLimitInformation2, JOBOBJECT_BASIC_UI_RESTRICTIONS,
+    JOBOBJECT_CPU_RATE_CONTROL_INFORMATION, JOBOBJECT_NET_RATE_CONTROL_INFORMATION,
+    JOBOBJECT_NOTIFICATION_LIMIT_INFORMATION, JOBOBJECT_NOTIFICATION_LIMIT_INFORMATION_2,
+};
+
+use crate::{BasicAccountingInfo, BasicAndIoAccountingInfo, ExtendedLimitInfo, Job, JobError};
+#[cfg(feature = "full")]
+use crate::{
+    CpuRateControlInfo, NetRateControlInfo, NotificationLimitInfo, NotificationLimitInfo2,
+    UiRestrictions,
+};
+
+pub(crate) mod sealed {
+    use windows::Win32::System::JobObjects::JOBOBJECTINFOCLASS;
+
+    pub trait Sealed: Sized {
+        type Raw: Copy + Default;
+
+        const CLASS: JOBOBJECTINFOCLASS;
+
+        /// The name of the class, used in errors.
+        const NAME: &'static str;
+
+        fn from_raw(raw: Self::Raw) -> Self;
+
+        fn as_raw(&self) -> &Self::Raw;
+
+        fn as_raw_mut(&mut self) -> &mut Self::Raw;
+    }
+}
+
+/// An information class of a job object, which can be queried using `job.query_info`.
+///
+/// This trait is sealed, and is implemented by the information types of this crate
+/// (`ExtendedLimitInfo`, `CpuRateControlInfo`, `UiRestrictions`, ...).
+pub trait JobInformation: sealed::Sealed {}
+
+/// An information class of a job object, which can also be set using `job.set_info`.
+pub trait SettableJobInformation: JobInformation {}
+
+macro_rules! impl_job_information {
+    ($ty:ty, $raw:ty, $class:expr) => {
+        impl sealed::Sealed for $ty {
+            type Raw = $raw;
+
+            const CLASS: JOBOBJECTINFOCLASS = $class;
+
+            const NAME: &'static str = stringify!($class);
+
+            fn from_raw(raw: Self::Raw) -> Self {
+                Self(raw)
+            }
+
+            fn as_raw(&self) -> &Self::Raw {
+                &self.0
+            }
+
+            fn as_raw_mut(&mut self) -> &mut Self::Raw {
+                &mut self.0
+            }
+        }
+
+        impl JobInformation for $ty {}
+
+        impl $ty {
+            #[doc = concat!("Return a reference to the underlying `", stringify!($raw), "`.")]
+            pub fn raw(&self) -> &$raw {
+                &self.0
+            }
+        }
+    };
+    ($ty:ty, $raw:ty, $class:expr, settable) => {
+        impl_job_information!($ty, $raw, $class);
+
+        impl SettableJobInformation for $ty {}
+
+        impl $ty {
+            #[doc = concat!("Return a mutable reference to the underlying `", stringify!($raw), "`,")]
+            /// for setting fields which have no typed accessor.
+            pub fn raw_mut(&mut self) -> &mut $raw {
+                &mut self.0
+            }
+        }
+    };
+}
+
+impl_job_information!(
+    ExtendedLimitInfo,
+    JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JobObjectExtendedLimitInformation,
+    settable
+);
+#[cfg(feature = "full")]
+impl_job_information!(
+    CpuRateControlInfo,
+    JOBOBJECT_CPU_RATE_CONTROL_INFORMATION,
+    JobObjectCpuRateControlInformation,
+    settable
+);
+#[cfg(feature = "full")]
+impl_job_information!(
+    UiRestrictions,
+    JOBOBJECT_BASIC_UI_RESTRICTIONS,
+    JobObjectBasicUIRestrictions,
+    settable
+);
+#[cfg(feature = "full")]
+impl_job_information!(
+    NetRateControlInfo,
+    JOBOBJECT_NET_RATE_CONTROL_INFORMATION,
+    JobObjectNetRateControlInformation,
+    settable
+);
+#[cfg(feature = "full")]
+impl_job_information!(
+    NotificationLimitInfo,
+    JOBOBJECT_NOTIFICATION_LIMIT_INFORMATION,
+    JobObjectNotificationLimitInformation,
+    settable
+);
+#[cfg(feature = "full")]
+impl_job_information!(
+    NotificationLimitInfo2,
+    JOBOBJECT_NOTIFICATION_LIMIT_INFORMATION_2,
+    JobObjectNotificationLimitInformation2,
+    settable
+);
+impl_job_information!(
+    BasicAccountingInfo,
+    JOBOBJECT_BASIC_ACCOUNTING_INFORMATION,
+    JobObjectBasicAccountingInformation
+);
+impl_job_information!(
+    BasicAndIoAccountingInfo,
+    JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION,
+    JobObjectBasicAndIoAccountingInformation
+);
+
+impl Job {
+    /// Query the information class `T` of the job.
+    ///
+    /// ```edition2021
+    /// use win32job::*;
+    /// # fn main() -> Result<(), JobError> {
+    ///
+    /// let job = Job::create()?;
+    /// let info: CpuRateControlInfo = job.query_info()?;
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn query_info<T: JobInformation>(&self) -> Result<T, JobError> {
+        let mut info = T::from_raw(Default::default());
+        self.query_info_into(&mut info)?;
+
+        Ok(info)
+    }
+
+    /// Like `query_info`, but fill a caller owned `info` instead of returning a new one.
+    pub fn query_info_into<T: JobInformation>(&self, info: &mut T) -> Result<(), JobError> {
+        let raw = info.as_raw_mut();
+
+        unsafe {
+            QueryInformationJobObject(
+                self.handle,
+                T::CLASS,
+                raw as *mut _ as *mut c_void,
+                mem::size_of_val(raw) as u32,
+                None,
+            )
+        }
+        .map_err(|e| JobError::GetInfoFailed {
+            class: T::NAME,
+            source: e.into(),
+        })
+    }
+
+    /// Set the information class `T` of the job.
+    pub fn set_info<T: SettableJobInformation>(&self, info: &T) -> Result<(), JobError> {
+        let _lock = self
+            .set_info_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        self.set_info_unlocked(info)
+    }
+
+    /// Set the information class `T` of the job, and return its previous value.
+    ///
+    /// Setting an information class through this `Job` (or one of its `try_clone`s) from other
+    /// threads waits for the swap to complete, so the returned value is exactly the one which
+    /// was replaced. This is an in-process lock: it does not apply to other handles to the job
+    /// (such as one from `Job::open`), nor to other processes which share the job.
+    pub fn swap_info<T: SettableJobInformation>(&self, info: &T) -> Result<T, JobError> {
+        let _lock = self
+            .set_info_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let previous = self.query_info()?;
+        self.set_info_unlocked(info)?;
+
+        Ok(previous)
+    }
+
+    /// Query an information class which is not wrapped by this crate into `buf`,
+    /// and return the number of bytes which were written.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must be suitably aligned for the structure of `class`
+    /// (for example, by allocating it as a slice of `u64`s).
+    pub unsafe fn query_information_raw(
+        &self,
+        class: JOBOBJECTINFOCLASS,
+        buf: &mut [u8],
+    ) -> Result<usize, JobError> {
+        let mut len = 0;
+
+        QueryInformationJobObject(
+            self.handle,
+            class,
+            buf.as_mut_ptr() as *mut c_void,
+            buf.len() as u32,
+            Some(&mut len),
+        )
+        .map_err(|e| JobError::GetRawInfoFailed {
+            class_id: class.0,
+            source: e.into(),
+        })?;
+
+        Ok(len as usize)
+    }
+
+    /// Set an information class which is not wrapped by this crate from `buf`.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must hold a valid, suitably aligned structure of `class`. In particular,
+    /// any pointers or handles it contains must be valid for the duration of the call.
+    pub unsafe fn set_information_raw(
+        &self,
+        class: JOBOBJECTINFOCLASS,
+        buf: &[u8],
+    ) -> Result<(), JobError> {
+        let _lock = self
+            .set_info_lock
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        SetInformationJobObject(
+            self.handle,
+            class,
+            buf.as_ptr() as *const c_void,
+            buf.len() as u32,
+        )
+        .map_err(|e| JobError::SetRawInfoFailed {
+            class_id: class.0,
+            source: e.into(),
+        })
+    }
+
+    fn set_info_unlocked<T: SettableJobInformation>(&self, info: &T) -> Result<(), JobError> {
+        let raw = info.as_raw();
+
+        unsafe {
+            SetInformationJobObject(
+                self.handle,
+                T::CLASS,
+                raw as *const _ as *const c_void,
+                mem::size_of_val(raw) as u32,
+            )
+        }
+        .map_err(|e| JobError::SetInfoFailed {
+            class: T::NAME,
+            source: e.into(),
+        })
+    }
+}
+
+#[cfg(all(test, feature = "full"))]
+mod tests {
+    use std::mem;
+
+    use windows::Win32::System::JobObjects::{
+        JobObjectBasicUIRestrictions, JOBOBJECT_BASIC_UI_RESTRICTIONS,
+    };
+
+    use crate::{CpuRateControlInfo, ExtendedLimitInfo, Job, JobError, UiRestrictions};
+
+    #[test]
+    fn generic_query_and_set() {
+        let job = Job::create().unwrap();
+
+        let mut info = UiRestrictions::new();
+        info.limit_global_atoms();
+        job.set_info(&info).unwrap();
+
+        let queried: UiRestrictions = job.query_info().unwrap();
+        assert_eq!(queried.0, info.0);
+
+        let info: ExtendedLimitInfo = job.query_info().unwrap();
+        assert_eq!(info.0.BasicLimitInformation.LimitFlags.0, 0);
+    }
+
+    #[test]
+    fn error_names_class() {
+        let job = Job::create().unwrap();
+
+        let mut info = CpuRateControlInfo::new();
+        info.limit_hard_cap(20000);
+
+        let err = job.set_info(&info).unwrap_err();
+        assert!(matches!(
+            err,
+            JobError::SetInfoFailed {
+                class: "JobObjectCpuRateControlInformation",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn raw_query_and_set() {
+        let job = Job::create().unwrap();
+
+        let mut info = UiRestrictions::new();
+        info.limit_global_atoms();
+        let raw = unsafe {
+            std::slice::from_raw_parts(&info.0 as *const _ as *const u8, mem::size_of_val(&info.0))
+        };
+        unsafe { job.set_information_raw(JobObjectBasicUIRestrictions, raw) }.unwrap();
+
+        let mut queried = JOBOBJECT_BASIC_UI_RESTRICTIONS::default();
+        let buf = unsafe {
+            std::slice::from_raw_parts_mut(
+                &mut queried as *mut _ as *mut u8,
+                mem::size_of_val(&queried),
+            )
+        };
+        let len = unsafe { job.query_information_raw(JobObjectBasicUIRestrictions, buf) }.unwrap();
+
+        assert_eq!(len, mem::size_of_val(&queried));
+        assert_eq!(queried, info.0);
+
+        // Too small for the structure of the class.
+        let err = unsafe { job.query_information_raw(JobObjectBasicUIRestrictions, &mut buf[..1]) }
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            JobError::GetRawInfoFailed { class_id, .. } if class_id == JobObjectBasicUIRestrictions.0
+        ));
+    }
+}
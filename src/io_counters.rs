@@ -0,0 +1,70 @@
+use std::fmt;
+
+use windows::Win32::System::Threading::IO_COUNTERS;
+
+/// I/O accounting information of a job (or a process).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IoCounters {
+    /// The number of read operations performed.
+    pub read_operations: u64,
+    /// The number of write operations performed.
+    pub write_operations: u64,
+    /// The number of I/O operations performed, other than read and write operations.
+    pub other_operations: u64,
+    /// The number of bytes read.
+    pub read_bytes: u64,
+    /// The number of bytes written.
+    pub write_bytes: u64,
+    /// The number of bytes transferred during operations other than read and write operations.
+    pub other_bytes: u64,
+}
+
+impl From<IO_COUNTERS> for IoCounters {
+    fn from(counters: IO_COUNTERS) -> Self {
+        IoCounters {
+            read_operations: counters.ReadOperationCount,
+            write_operations: counters.WriteOperationCount,
+            other_operations: counters.OtherOperationCount,
+            read_bytes: counters.ReadTransferCount,
+            write_bytes: counters.WriteTransferCount,
+            other_bytes: counters.OtherTransferCount,
+        }
+    }
+}
+
+impl fmt::Display for IoCounters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "read {} bytes ({} ops), wrote {} bytes ({} ops), other {} bytes ({} ops)",
+            self.read_bytes,
+            self.read_operations,
+            self.write_bytes,
+            self.write_operations,
+            self.other_bytes,
+            self.other_operations
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::IoCounters;
+
+    #[test]
+    fn display() {
+        let counters = IoCounters {
+            read_operations: 2,
+            write_operations: 1,
+            read_bytes: 4096,
+            write_bytes: 512,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            counters.to_string(),
+            "read 4096 bytes (2 ops), wrote 512 bytes (1 ops), other 0 bytes (0 ops)"
+        );
+    }
+}
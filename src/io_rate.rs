@@ -0,0 +1,199 @@
+use std::{io, slice};
+
+use windows::{
+    core::{HSTRING, PCWSTR},
+    Win32::System::JobObjects::{
+        FreeMemoryJobObject, QueryIoRateControlInformationJobObject,
+        SetIoRateControlInformationJobObject, JOBOBJECT_IO_RATE_CONTROL_INFORMATION,
+        JOB_OBJECT_IO_RATE_CONTROL_ENABLE,
+    },
+};
+
+use crate::{Job, JobError};
+
+/// Contains I/O rate control information for a job object on a single volume, with helper
+/// methods for easy limit manipulation. To apply limits, pass the instance of this struct to
+/// `job.set_io_rate_control_info`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IoRateControlInfo {
+    volume_name: Option<String>,
+    max_iops: i64,
+    max_bandwidth: i64,
+    reservation_iops: i64,
+    control_flags: u32,
+}
+
+impl IoRateControlInfo {
+    /// Return an empty I/O rate control info object which applies to all volumes,
+    /// without any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return an empty I/O rate control info object which applies to `volume_name`
+    /// (for example, `\\?\Volume{...}`), without any limits.
+    pub fn for_volume(volume_name: impl Into<String>) -> Self {
+        Self {
+            volume_name: Some(volume_name.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Return the volume these settings apply to, or `None` if they apply to all volumes.
+    pub fn volume_name(&self) -> Option<&str> {
+        self.volume_name.as_deref()
+    }
+
+    /// Return `true` if I/O rate control is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.control_flags & JOB_OBJECT_IO_RATE_CONTROL_ENABLE.0 as u32 != 0
+    }
+
+    /// Return the maximum I/O operations per second, or 0 if not limited.
+    pub fn max_iops(&self) -> i64 {
+        self.max_iops
+    }
+
+    /// Return the maximum I/O bandwidth in bytes per second, or 0 if not limited.
+    pub fn max_bandwidth(&self) -> i64 {
+        self.max_bandwidth
+    }
+
+    /// Return the reserved I/O operations per second, or 0 if nothing is reserved.
+    pub fn reservation_iops(&self) -> i64 {
+        self.reservation_iops
+    }
+
+    /// Limit the job to `max_iops` I/O operations per second.
+    pub fn limit_max_iops(&mut self, max_iops: i64) -> &mut Self {
+        self.max_iops = max_iops;
+        self.control_flags |= JOB_OBJECT_IO_RATE_CONTROL_ENABLE.0 as u32;
+
+        self
+    }
+
+    /// Limit the job to `bytes_per_sec` of I/O bandwidth.
+    pub fn limit_max_bandwidth(&mut self, bytes_per_sec: i64) -> &mut Self {
+        self.max_bandwidth = bytes_per_sec;
+        self.control_flags |= JOB_OBJECT_IO_RATE_CONTROL_ENABLE.0 as u32;
+
+        self
+    }
+
+    /// Reserve `reservation_iops` I/O operations per second for the job.
+    pub fn limit_reservation_iops(&mut self, reservation_iops: i64) -> &mut Self {
+        self.reservation_iops = reservation_iops;
+        self.control_flags |= JOB_OBJECT_IO_RATE_CONTROL_ENABLE.0 as u32;
+
+        self
+    }
+
+    /// Clear all limits.
+    pub fn clear_limits(&mut self) -> &mut Self {
+        self.max_iops = 0;
+        self.max_bandwidth = 0;
+        self.reservation_iops = 0;
+        self.control_flags = 0;
+
+        self
+    }
+}
+
+impl Job {
+    /// Return the I/O rate control information for a job object.
+    /// Pass `None` as the `volume_name` to query without filtering by volume.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-queryioratecontrolinformationjobobject).
+    pub fn query_io_rate_control_info(
+        &self,
+        volume_name: Option<&str>,
+    ) -> Result<Vec<IoRateControlInfo>, JobError> {
+        let volume_name = volume_name.map(HSTRING::from);
+        let volume_name = volume_name
+            .as_ref()
+            .map_or(PCWSTR::null(), |name| PCWSTR(name.as_ptr()));
+
+        let mut blocks = std::ptr::null_mut();
+        let mut count = 0u32;
+
+        let res = unsafe {
+            QueryIoRateControlInformationJobObject(
+                self.handle,
+                volume_name,
+                &mut blocks,
+                &mut count,
+            )
+        };
+
+        if res == 0 {
+            return Err(JobError::GetInfoFailed {
+                class: "JobObjectIoRateControlInformation",
+                source: io::Error::last_os_error(),
+            });
+        }
+
+        if blocks.is_null() {
+            return Ok(vec![]);
+        }
+
+        let list = unsafe { slice::from_raw_parts(blocks, count as usize) }
+            .iter()
+            .map(|block| {
+                let volume_name = if block.VolumeName.is_null() {
+                    None
+                } else {
+                    unsafe { block.VolumeName.to_string() }
+                        .ok()
+                        .filter(|name| !name.is_empty())
+                };
+
+                IoRateControlInfo {
+                    volume_name,
+                    max_iops: block.MaxIops,
+                    max_bandwidth: block.MaxBandwidth,
+                    reservation_iops: block.ReservationIops,
+                    control_flags: block.ControlFlags,
+                }
+            })
+            .collect();
+
+        unsafe { FreeMemoryJobObject(blocks as *const _) };
+
+        Ok(list)
+    }
+
+    /// Set the I/O rate control information for a job object.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(job = self.handle.0, volume = ?info.volume_name, flags = info.control_flags),
+            err
+        )
+    )]
+    pub fn set_io_rate_control_info(&self, info: &IoRateControlInfo) -> Result<(), JobError> {
+        let volume_name = info.volume_name.as_deref().map(HSTRING::from);
+
+        let raw = JOBOBJECT_IO_RATE_CONTROL_INFORMATION {
+            MaxIops: info.max_iops,
+            MaxBandwidth: info.max_bandwidth,
+            ReservationIops: info.reservation_iops,
+            VolumeName: volume_name
+                .as_ref()
+                .map_or(PCWSTR::null(), |name| PCWSTR(name.as_ptr())),
+            BaseIoSize: 0,
+            ControlFlags: info.control_flags,
+        };
+
+        let res = unsafe { SetIoRateControlInformationJobObject(self.handle, &raw) };
+
+        if res == 0 {
+            return Err(JobError::SetInfoFailed {
+                class: "JobObjectIoRateControlInformation",
+                source: io::Error::last_os_error(),
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Write},
+    os::windows::{io::AsRawHandle, process::CommandExt},
+    process::{self, Child, Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use windows::Win32::{
+    Foundation::{DuplicateHandle, BOOL, DUPLICATE_SAME_ACCESS, HANDLE},
+    System::{
+        JobObjects::{IsProcessInJob, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE},
+        Threading::{
+            GetCurrentProcess, OpenProcess, WaitForSingleObject, CREATE_BREAKAWAY_FROM_JOB,
+            CREATE_NO_WINDOW, INFINITE, PROCESS_SYNCHRONIZE,
+        },
+    },
+};
+
+use crate::handle::OwnedHandle;
+use crate::{Job, JobError};
+
+/// The environment variable which holds the pid of the parent of a janitor helper process.
+const PARENT_PID_ENV: &str = "WIN32JOB_JANITOR_PARENT_PID";
+
+/// The line written by the helper once it is watching its parent.
+const READY_LINE: &str = "win32job-janitor-ready";
+
+/// How long to wait for the helper to write `READY_LINE`.
+const READY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A helper process which holds a duplicated handle to a kill-on-close job,
+/// and exits (closing the handle) when the current process exits.
+///
+/// Since the handle lives in another process, the job's processes are killed even if the
+/// current process is killed with `TerminateProcess` (where `Drop` never runs).
+///
+/// The helper is the current executable by default, which must call
+/// `Janitor::run_if_helper` at the very start of `main`.
+/// Alternatively, the `win32job-janitor` binary (built with the `janitor` feature)
+/// can be shipped alongside the application and used with `Janitor::spawn_with`.
+///
+/// ```edition2021,no_run
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+/// Janitor::run_if_helper();
+///
+/// let job = Job::create()?;
+/// let janitor = Janitor::spawn(&job)?;
+///
+/// let child = std::process::Command::new("cmd")
+///     .args(["/C", "ping -n 9999 127.0.0.1"])
+///     .spawn()?;
+/// job.assign_child(&child)?;
+///
+/// // `child` is killed once we exit, even if we are killed.
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Janitor {
+    helper: Child,
+}
+
+impl Janitor {
+    /// Spawn a janitor for `job`, using the current executable as the helper.
+    pub fn spawn(job: &Job) -> Result<Self, JobError> {
+        let exe = env::current_exe().map_err(JobError::SpawnFailed)?;
+
+        Self::spawn_with(job, Command::new(exe))
+    }
+
+    /// Spawn a janitor for `job`, using `command` as the helper,
+    /// which must call `Janitor::run_if_helper` (like `win32job-janitor`).
+    ///
+    /// The job is set to kill all of its processes when the last handle to it is closed.
+    ///
+    /// Fails if the current process is itself a janitor helper (which would spawn helpers
+    /// recursively), or if the helper isn't ready within 30 seconds (in which case it is killed).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job = job.handle.0), err)
+    )]
+    pub fn spawn_with(job: &Job, mut command: Command) -> Result<Self, JobError> {
+        if env::var_os(PARENT_PID_ENV).is_some() {
+            return Err(JobError::SpawnFailed(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the current process is a janitor helper, which can't spawn janitors",
+            )));
+        }
+
+        let mut in_job = BOOL::default();
+
+        unsafe { IsProcessInJob(GetCurrentProcess(), job.handle, &mut in_job) }.map_err(|e| {
+            JobError::GetInfoFailed {
+                class: "IsProcessInJob",
+                source: e.into(),
+            }
+        })?;
+
+        // The helper must not be a part of the job, or the job would never be closed.
+        let mut creation_flags = CREATE_NO_WINDOW.0;
+        if in_job.as_bool() {
+            creation_flags |= CREATE_BREAKAWAY_FROM_JOB.0;
+        }
+
+        let helper = command
+            .env(PARENT_PID_ENV, process::id().to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .creation_flags(creation_flags)
+            .spawn()
+            .map_err(JobError::SpawnFailed)?;
+
+        let mut janitor = Janitor { helper };
+
+        janitor.wait_until_ready()?;
+
+        let mut info = job.query_extended_limit_info()?;
+        info.0.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        job.set_extended_limit_info(&info)?;
+
+        // The helper never uses the handle: it is closed when the helper exits.
+        let mut handle = HANDLE::default();
+
+        unsafe {
+            DuplicateHandle(
+                GetCurrentProcess(),
+                job.handle,
+                HANDLE(janitor.helper.as_raw_handle() as isize),
+                &mut handle,
+                0,
+                false,
+                DUPLICATE_SAME_ACCESS,
+            )
+        }
+        .map_err(|e| JobError::DuplicateFailed(e.into()))?;
+
+        Ok(janitor)
+    }
+
+    /// Wait for the helper to report that it is watching the current process.
+    fn wait_until_ready(&mut self) -> Result<(), JobError> {
+        let stdout = self.helper.stdout.take().expect("stdout is piped");
+        let (sender, receiver) = mpsc::channel();
+
+        // The reader exits once the helper is ready, or killed (when `self` is dropped).
+        thread::Builder::new()
+            .name("win32job-janitor-ready".to_owned())
+            .spawn(move || {
+                // Skip any other output of the helper (such as a test harness).
+                let ready = BufReader::new(stdout)
+                    .lines()
+                    .map_while(Result::ok)
+                    .any(|line| line == READY_LINE);
+
+                let _ = sender.send(ready);
+            })
+            .map_err(JobError::SpawnFailed)?;
+
+        match receiver.recv_timeout(READY_TIMEOUT) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(JobError::SpawnFailed(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the janitor helper exited before it was ready",
+            ))),
+            Err(_) => Err(JobError::SpawnFailed(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the janitor helper was not ready in time",
+            ))),
+        }
+    }
+
+    /// Return the pid of the helper process.
+    pub fn id(&self) -> u32 {
+        self.helper.id()
+    }
+
+    /// If the current process was spawned as a janitor helper, wait for the parent process to exit,
+    /// and then exit. Otherwise, return immediately.
+    pub fn run_if_helper() {
+        let Some(parent_pid) = env::var(PARENT_PID_ENV)
+            .ok()
+            .and_then(|pid| pid.parse::<u32>().ok())
+        else {
+            return;
+        };
+
+        let parent = match unsafe { OpenProcess(PROCESS_SYNCHRONIZE, false, parent_pid) } {
+            Ok(parent) => OwnedHandle(parent),
+            // The parent is already gone.
+            Err(_) => process::exit(1),
+        };
+
+        let mut stdout = io::stdout();
+        if writeln!(stdout, "{}", READY_LINE)
+            .and_then(|_| stdout.flush())
+            .is_err()
+        {
+            process::exit(1);
+        }
+
+        unsafe { WaitForSingleObject(parent.0, INFINITE) };
+
+        process::exit(0);
+    }
+}
+
+impl Drop for Janitor {
+    /// Kill the helper, closing its handle to the job.
+    /// The job's processes are killed once all the other handles to the job are closed.
+    fn drop(&mut self) {
+        if let Err(err) = self.helper.kill() {
+            log_warn!(
+                "Failed to kill janitor helper {}: {}",
+                self.helper.id(),
+                err
+            );
+        }
+
+        let _ = self.helper.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, io};
+
+    use rusty_fork::rusty_fork_test;
+
+    use crate::{Janitor, Job, JobError};
+
+    #[test]
+    fn helper() {
+        Janitor::run_if_helper();
+    }
+
+    #[cfg(feature = "full")]
+    #[test]
+    fn job_outlives_handle() {
+        use std::{process::Command, thread, time::Duration};
+
+        use crate::test_util::DisposableChild;
+
+        let job = Job::create().unwrap();
+
+        let mut helper = Command::new(env::current_exe().expect("the path of the test executable"));
+        helper.args(["--exact", "janitor::tests::helper", "--nocapture"]);
+        let janitor = Janitor::spawn_with(&job, helper).unwrap();
+
+        let mut child = DisposableChild::spawn_in(&job).unwrap();
+
+        // The janitor keeps the job (and the child) alive.
+        drop(job);
+        thread::sleep(Duration::from_millis(100));
+        assert!(child.try_wait().unwrap().is_none());
+
+        drop(janitor);
+        child.wait().unwrap();
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn helper_cannot_spawn_janitors() {
+            env::set_var("WIN32JOB_JANITOR_PARENT_PID", "1");
+
+            let job = Job::create().unwrap();
+            let err = Janitor::spawn(&job).unwrap_err();
+            assert!(matches!(err, JobError::SpawnFailed(e) if e.kind() == io::ErrorKind::InvalidInput));
+        }
+    }
+}
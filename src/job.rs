@@ -1,23 +1,61 @@
 use windows::{
-    core::PCWSTR,
+    core::{HSTRING, PCWSTR},
     Win32::{
-        Foundation::{CloseHandle, HANDLE},
-        System::JobObjects::{
-            AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
-            QueryInformationJobObject, SetInformationJobObject,
+        Foundation::{
+            CloseHandle, DuplicateHandle, BOOL, DUPLICATE_SAME_ACCESS, ERROR_INVALID_PARAMETER,
+            HANDLE, WAIT_FAILED, WAIT_TIMEOUT,
+        },
+        System::{
+            JobObjects::{
+                AssignProcessToJobObject, CreateJobObjectW, IsProcessInJob, OpenJobObjectW,
+                TerminateJobObject, JOB_OBJECT_LIMIT_BREAKAWAY_OK,
+                JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, JOB_OBJECT_LIMIT_SILENT_BREAKAWAY_OK,
+            },
+            Threading::{
+                GetCurrentProcess, GetProcessId, OpenProcess, TerminateProcess,
+                WaitForMultipleObjects, WaitForSingleObject, INFINITE,
+                PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_QUOTA, PROCESS_SYNCHRONIZE,
+                PROCESS_TERMINATE,
+            },
         },
     },
 };
 
 use crate::error::JobError;
+use crate::handle::{granted_access, object_name, OwnedHandle};
 use crate::limits::ExtendedLimitInfo;
-use std::{ffi::c_void, mem};
+use crate::{JobAccess, OpenedProcess, Pid};
+use std::{
+    io, mem,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
 
 pub use crate::utils::get_current_process;
 
+/// What happens to a job when a `Job` is dropped (see `Job::set_drop_behavior`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DropBehavior {
+    /// Only close the handle. The job's processes keep running, unless the job has the
+    /// kill on job close limit and this was its last handle.
+    #[default]
+    CloseHandle,
+    /// Terminate all the processes of the job with `exit_code`, and then close the handle.
+    TerminateFirst { exit_code: u32 },
+    /// Clear the kill on job close limit, so closing the handle never kills the job's
+    /// processes (even if it is the last handle), and then close the handle.
+    ClearKillOnCloseThenClose,
+}
+
 #[derive(Debug)]
 pub struct Job {
     pub(crate) handle: HANDLE,
+    drop_behavior: DropBehavior,
+    /// Serializes setting the information classes of the job through this `Job` and its clones
+    /// (see `swap_info`).
+    pub(crate) set_info_lock: Arc<Mutex<()>>,
 }
 
 unsafe impl Send for Job {}
@@ -25,10 +63,71 @@ unsafe impl Sync for Job {}
 
 impl Job {
     /// Create an anonymous job object.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", ret, err))]
     pub fn create() -> Result<Self, JobError> {
         unsafe { CreateJobObjectW(None, PCWSTR::null()) }
             .map_err(|e| JobError::CreateFailed(e.into()))
-            .map(|handle| Self { handle })
+            .map(Self::with_handle)
+    }
+
+    /// Create a named job object, or open the existing job object if a job with this name
+    /// already exists.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", ret, err))]
+    pub fn create_named(name: &str) -> Result<Self, JobError> {
+        unsafe { CreateJobObjectW(None, &HSTRING::from(name)) }
+            .map_err(|e| JobError::CreateFailed(e.into()))
+            .map(Self::with_handle)
+    }
+
+    /// Make sure only one process holds the named job at a time, like a session-wide
+    /// (or machine-wide, with a `Global\` prefix) mutex which is released when the holder exits,
+    /// even if it crashes.
+    ///
+    /// Return the job if the current process holds it (and was assigned to it), or `None` if
+    /// another live process already holds it.
+    ///
+    /// Only one process at a time can be assigned to the job (which is enforced by the OS,
+    /// using an active process limit of 1), so child processes of the holder silently
+    /// break away from the job. Other limits which are already set on the job are kept.
+    /// Note that the current process can't be removed from the job.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
+    pub fn acquire_singleton(name: &str) -> Result<Option<Self>, JobError> {
+        let job = Self::create_named(name)?;
+        let current_pid = Pid::current();
+
+        let holders = job.query_process_id_list()?;
+        if holders.iter().any(|&pid| pid != current_pid) {
+            return Ok(None);
+        }
+
+        // Keep any limits which are already set on the job.
+        let mut info = job.query_extended_limit_info()?;
+        info.limit_active_processes(1).limit_silent_breakaway_ok();
+        job.set_extended_limit_info(&info)?;
+
+        if let Err(err) = job.assign_current_process() {
+            // Another process was assigned between the check and the assignment.
+            if job
+                .query_process_id_list()?
+                .iter()
+                .any(|&pid| pid != current_pid)
+            {
+                return Ok(None);
+            }
+
+            return Err(err);
+        }
+
+        Ok(Some(job))
+    }
+
+    /// Open an existing named job object.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-openjobobjectw).
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", ret, err))]
+    pub fn open(name: &str) -> Result<Self, JobError> {
+        unsafe { OpenJobObjectW(JobAccess::ALL.bits(), false, &HSTRING::from(name)) }
+            .map_err(|e| JobError::OpenFailed(e.into()))
+            .map(Self::with_handle)
     }
 
     /// Create an anonymous job object and sets it's limit according to `info`.
@@ -39,16 +138,80 @@ impl Job {
         Ok(job)
     }
 
+    /// Duplicate the handle to the job, returning a new `Job` which refers to the same job object.
+    /// Each `Job` has its own handle, which is closed independently when it is dropped.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job = self.handle.0), ret, err)
+    )]
+    pub fn try_clone(&self) -> Result<Self, JobError> {
+        let mut handle = HANDLE::default();
+
+        unsafe {
+            DuplicateHandle(
+                GetCurrentProcess(),
+                self.handle,
+                GetCurrentProcess(),
+                &mut handle,
+                0,
+                false,
+                DUPLICATE_SAME_ACCESS,
+            )
+        }
+        .map_err(|e| JobError::DuplicateFailed(e.into()))?;
+
+        Ok(Self {
+            set_info_lock: self.set_info_lock.clone(),
+            ..Self::with_handle(handle)
+        })
+    }
+
+    fn with_handle(handle: HANDLE) -> Self {
+        Self {
+            handle,
+            drop_behavior: DropBehavior::default(),
+            set_info_lock: Arc::default(),
+        }
+    }
+
+    /// Set what happens to the job when this `Job` is dropped.
+    /// Note that this only applies to this handle, and not to clones created with `try_clone`.
+    pub fn set_drop_behavior(&mut self, behavior: DropBehavior) -> &mut Self {
+        self.drop_behavior = behavior;
+
+        self
+    }
+
+    /// Return what happens to the job when this `Job` is dropped.
+    pub fn drop_behavior(&self) -> DropBehavior {
+        self.drop_behavior
+    }
+
     /// Return the underlying handle to the job.
     /// Note that this handle will be closed once the `Job` object is dropped.
     pub fn handle(&self) -> isize {
         self.handle.0
     }
 
+    /// Return the kernel name of the job (like `\Sessions\1\BaseNamedObjects\my-job`),
+    /// or `None` if the job is anonymous. This identifies jobs whose handle was inherited
+    /// or duplicated from another process.
+    pub fn name(&self) -> Result<Option<String>, JobError> {
+        let name = object_name(self.handle).map_err(|source| JobError::GetInfoFailed {
+            class: "ObjectNameInformation",
+            source,
+        })?;
+
+        Ok((!name.is_empty()).then_some(name))
+    }
+
     /// Return the underlying handle to the job, consuming the job.
     /// Note that the handle will NOT be closed, so it is the caller's responsibly to close it.
     pub fn into_handle(self) -> isize {
-        let job = mem::ManuallyDrop::new(self);
+        let mut job = mem::ManuallyDrop::new(self);
+
+        // Release the lock, which is the only other owned field. It is never used again.
+        unsafe { std::ptr::drop_in_place(&mut job.set_info_lock) };
 
         job.handle.0
     }
@@ -56,39 +219,134 @@ impl Job {
     /// Return basic and extended limit information for a job object.
     /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_extended_limit_information).
     pub fn query_extended_limit_info(&self) -> Result<ExtendedLimitInfo, JobError> {
-        let mut info = ExtendedLimitInfo::default();
+        self.query_info()
+    }
 
-        unsafe {
-            QueryInformationJobObject(
-                self.handle,
-                JobObjectExtendedLimitInformation,
-                &mut info.0 as *mut _ as *mut c_void,
-                mem::size_of_val(&info.0) as u32,
-                None,
-            )
-        }
-        .map_err(|e| JobError::GetInfoFailed(e.into()))?;
-        Ok(info)
+    /// Like `query_extended_limit_info`, but fill a caller owned `info` (which can be reused across calls).
+    pub fn query_extended_limit_info_into(
+        &self,
+        info: &mut ExtendedLimitInfo,
+    ) -> Result<(), JobError> {
+        self.query_info_into(info)
     }
 
     /// Set the basic and extended limit information for a job object.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(job = self.handle.0, flags = info.0.BasicLimitInformation.LimitFlags.0),
+            err
+        )
+    )]
     pub fn set_extended_limit_info(&self, info: &ExtendedLimitInfo) -> Result<(), JobError> {
-        unsafe {
-            SetInformationJobObject(
-                self.handle,
-                JobObjectExtendedLimitInformation,
-                &info.0 as *const _ as *const c_void,
-                mem::size_of_val(&info.0) as u32,
-            )
-            .map_err(|e| JobError::SetInfoFailed(e.into()))
-        }
+        self.set_info(info)
+    }
+
+    /// Set the basic and extended limit information for a job object, and return the previous one.
+    /// This can be used to temporarily relax limits, and then restore them.
+    /// See `swap_info` for the guarantees against concurrent changes.
+    pub fn swap_extended_limit_info(
+        &self,
+        info: &ExtendedLimitInfo,
+    ) -> Result<ExtendedLimitInfo, JobError> {
+        self.swap_info(info)
+    }
+
+    /// Prevent the processes of the job from creating any child processes.
+    ///
+    /// The active process limit is set to the number of processes which are already in the job
+    /// (so call this after assigning the sandboxed process), and breakaway is denied,
+    /// so children can't be created outside of the job either.
+    /// Return the active process limit which was set.
+    ///
+    /// An empty job is limited to a single process, so the sandboxed process can still be assigned.
+    pub fn no_child_processes(&self) -> Result<u32, JobError> {
+        let active = self.query_basic_accounting_info()?.active_processes();
+        let limit = active.max(1);
+
+        let mut info = self.query_extended_limit_info()?;
+        info.0.BasicLimitInformation.LimitFlags &=
+            !(JOB_OBJECT_LIMIT_BREAKAWAY_OK | JOB_OBJECT_LIMIT_SILENT_BREAKAWAY_OK);
+        info.limit_active_processes(limit);
+        self.set_extended_limit_info(&info)?;
+
+        Ok(limit)
     }
 
     /// Assigns a process to the job object.
     /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-assignprocesstojobobject).
+    /// On failure, the error includes the pid and image name of the process
+    /// (see `JobError::AssignProcessFailed`).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), err)
+    )]
     pub fn assign_process(&self, proc_handle: isize) -> Result<(), JobError> {
-        unsafe { AssignProcessToJobObject(self.handle, HANDLE(proc_handle)) }
-            .map_err(|e| JobError::AssignFailed(e.into()))
+        self.assign_process_with_pid(HANDLE(proc_handle), None)
+    }
+
+    /// Assigns the process to the job, where `pid` is the pid of the process (if known).
+    fn assign_process_with_pid(
+        &self,
+        proc_handle: HANDLE,
+        pid: Option<u32>,
+    ) -> Result<(), JobError> {
+        unsafe { AssignProcessToJobObject(self.handle, proc_handle) }
+            .map_err(|e| assign_error(proc_handle, pid, e.into()))
+    }
+
+    /// Like `assign_process`, but first verify that `proc_handle` has the access rights
+    /// required to assign it to a job, returning `JobError::MissingProcessAccess`
+    /// (instead of a bare "access denied" error) if it does not.
+    pub fn assign_process_verified(&self, proc_handle: isize) -> Result<(), JobError> {
+        Self::verify_assign_access(proc_handle)?;
+
+        self.assign_process(proc_handle)
+    }
+
+    /// Verify that `proc_handle` has the `PROCESS_SET_QUOTA` and `PROCESS_TERMINATE` access rights,
+    /// which are required to assign it to a job.
+    pub fn verify_assign_access(proc_handle: isize) -> Result<(), JobError> {
+        let access =
+            granted_access(HANDLE(proc_handle)).map_err(|source| JobError::GetInfoFailed {
+                class: "ObjectBasicInformation",
+                source,
+            })?;
+
+        for (right, name) in [
+            (PROCESS_SET_QUOTA, "PROCESS_SET_QUOTA"),
+            (PROCESS_TERMINATE, "PROCESS_TERMINATE"),
+        ] {
+            if access & right.0 != right.0 {
+                return Err(JobError::MissingProcessAccess(name));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Opens the process with the given pid, and assigns it to the job object.
+    pub fn assign_process_by_pid(&self, pid: impl Into<Pid>) -> Result<(), JobError> {
+        self.assign_pid(pid.into().as_u32())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), err)
+    )]
+    fn assign_pid(&self, pid: u32) -> Result<(), JobError> {
+        let proc_handle = unsafe { OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, false, pid) }
+            .map_err(|e| JobError::OpenProcessFailed(e.into()))?;
+
+        let res = self.assign_process_with_pid(proc_handle, Some(pid));
+
+        unsafe {
+            let _ = CloseHandle(proc_handle);
+        }
+
+        res
     }
 
     /// Assigns the current process to the job object.
@@ -97,21 +355,366 @@ impl Job {
 
         self.assign_process(current_proc_handle)
     }
+
+    /// Terminates all processes currently associated with the job, using `exit_code`
+    /// as the exit code of each of them.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-terminatejobobject).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), err)
+    )]
+    pub fn terminate(&self, exit_code: u32) -> Result<(), JobError> {
+        unsafe { TerminateJobObject(self.handle, exit_code) }
+            .map_err(|e| JobError::TerminateFailed(e.into()))
+    }
+
+    /// Terminates all processes currently associated with the job (like `terminate`),
+    /// and wait up to `timeout` (or forever, if `None`) for all of them to actually exit.
+    /// Termination is asynchronous, so this is needed before, for example, deleting files
+    /// which are used by the job's processes.
+    ///
+    /// Return `true` if the job became empty before the timeout elapsed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), ret, err)
+    )]
+    pub fn terminate_and_wait(
+        &self,
+        exit_code: u32,
+        timeout: Option<Duration>,
+    ) -> Result<bool, JobError> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let remaining = || {
+            deadline.map_or(INFINITE, |deadline| {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                remaining.as_millis().min(INFINITE as u128 - 1) as u32
+            })
+        };
+
+        // Open the processes before terminating them, so their handles can be waited on.
+        let processes: Vec<_> = self
+            .query_process_id_list()?
+            .into_iter()
+            .filter_map(|pid| unsafe { OpenProcess(PROCESS_SYNCHRONIZE, false, pid.as_u32()) }.ok())
+            .map(OwnedHandle)
+            .collect();
+
+        self.terminate(exit_code)?;
+
+        for process in &processes {
+            if unsafe { WaitForSingleObject(process.0, remaining()) } == WAIT_TIMEOUT {
+                return Ok(false);
+            }
+        }
+
+        // Processes which could not be opened are polled for.
+        loop {
+            if self.is_empty()? {
+                return Ok(true);
+            }
+
+            if remaining() == 0 {
+                return Ok(false);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Block until the job has no active processes, or until `timeout` (or forever, if `None`)
+    /// elapses. Return `true` if the job is empty.
+    ///
+    /// Unlike `JobMonitor::wait_until_empty`, this does not associate a completion port with
+    /// the job, so it can be used when the job's port is owned by other code. The best
+    /// available strategy is picked automatically: the job's processes are waited on directly
+    /// when they can be opened, and otherwise the job's accounting is polled, with an interval
+    /// that grows the longer the job stays active.
+    pub fn wait_until_empty(&self, timeout: Option<Duration>) -> Result<bool, JobError> {
+        const MIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+        const MAX_POLL_INTERVAL: Duration = Duration::from_millis(100);
+        // The maximum number of handles `WaitForMultipleObjects` accepts.
+        const MAXIMUM_WAIT_OBJECTS: usize = 64;
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut interval = MIN_POLL_INTERVAL;
+
+        loop {
+            if self.is_empty()? {
+                return Ok(true);
+            }
+
+            let remaining =
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if remaining.is_some_and(|remaining| remaining.is_zero()) {
+                return Ok(false);
+            }
+
+            let processes: Vec<_> = self
+                .query_process_id_list()?
+                .into_iter()
+                .filter_map(|pid| {
+                    unsafe { OpenProcess(PROCESS_SYNCHRONIZE, false, pid.as_u32()) }.ok()
+                })
+                .map(OwnedHandle)
+                .take(MAXIMUM_WAIT_OBJECTS)
+                .collect();
+
+            if processes.is_empty() {
+                let sleep = remaining.map_or(interval, |remaining| remaining.min(interval));
+                thread::sleep(sleep);
+                interval = (interval * 2).min(MAX_POLL_INTERVAL);
+
+                continue;
+            }
+
+            // Wake up when any of the processes exits, and check the job again.
+            let handles: Vec<_> = processes.iter().map(|process| process.0).collect();
+            let timeout = remaining.map_or(INFINITE, |remaining| {
+                remaining.as_millis().min(INFINITE as u128 - 1) as u32
+            });
+
+            if unsafe { WaitForMultipleObjects(&handles, false, timeout) } == WAIT_FAILED {
+                return Err(JobError::SpawnFailed(io::Error::last_os_error()));
+            }
+            interval = MIN_POLL_INTERVAL;
+        }
+    }
+
+    /// Return `true` if the process with the given pid is associated with the job.
+    /// A process which does not exist (for example, because it exited) is not in the job.
+    pub fn contains_process(&self, pid: impl Into<Pid>) -> Result<bool, JobError> {
+        let pid = pid.into().as_u32();
+
+        let proc_handle =
+            match unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) } {
+                Ok(proc_handle) => OwnedHandle(proc_handle),
+                Err(err) if err.code() == ERROR_INVALID_PARAMETER.to_hresult() => return Ok(false),
+                Err(err) => return Err(JobError::OpenProcessFailed(err.into())),
+            };
+
+        let mut in_job = BOOL::default();
+
+        unsafe { IsProcessInJob(proc_handle.0, self.handle, &mut in_job) }.map_err(|e| {
+            JobError::GetInfoFailed {
+                class: "IsProcessInJob",
+                source: e.into(),
+            }
+        })?;
+
+        Ok(in_job.as_bool())
+    }
+
+    /// Terminates a single process of the job, using `exit_code` as its exit code.
+    /// Returns `JobError::ProcessNotInJob` if the process with the given pid is not
+    /// associated with the job (the process is not terminated in that case).
+    pub fn kill_process(&self, pid: impl Into<Pid>, exit_code: u32) -> Result<(), JobError> {
+        self.kill_pid(pid.into().as_u32(), exit_code)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), err)
+    )]
+    fn kill_pid(&self, pid: u32, exit_code: u32) -> Result<(), JobError> {
+        let proc_handle = unsafe {
+            OpenProcess(
+                PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_TERMINATE,
+                false,
+                pid,
+            )
+        }
+        .map_err(|e| JobError::OpenProcessFailed(e.into()))?;
+
+        let res = self.kill_process_handle(proc_handle, pid, exit_code);
+
+        unsafe {
+            let _ = CloseHandle(proc_handle);
+        }
+
+        res
+    }
+
+    pub(crate) fn kill_process_handle(
+        &self,
+        proc_handle: HANDLE,
+        pid: u32,
+        exit_code: u32,
+    ) -> Result<(), JobError> {
+        let mut in_job = BOOL::default();
+
+        // The check is done on the opened handle, so the pid cannot be reused
+        // by another process between the check and the termination.
+        unsafe { IsProcessInJob(proc_handle, self.handle, &mut in_job) }.map_err(|e| {
+            JobError::GetInfoFailed {
+                class: "IsProcessInJob",
+                source: e.into(),
+            }
+        })?;
+
+        if !in_job.as_bool() {
+            return Err(JobError::ProcessNotInJob(Pid::new(pid)));
+        }
+
+        unsafe { TerminateProcess(proc_handle, exit_code) }
+            .map_err(|e| JobError::TerminateProcessFailed(e.into()))
+    }
 }
 
 impl Drop for Job {
     fn drop(&mut self) {
-        unsafe {
-            let _ = CloseHandle(self.handle);
+        match self.drop_behavior {
+            DropBehavior::CloseHandle => {}
+            DropBehavior::TerminateFirst { exit_code } => {
+                if let Err(err) = self.terminate(exit_code) {
+                    log_warn!("Failed to terminate job {:?}: {}", self.handle, err);
+                }
+            }
+            DropBehavior::ClearKillOnCloseThenClose => {
+                let res = self.query_extended_limit_info().and_then(|mut info| {
+                    info.0.BasicLimitInformation.LimitFlags &= !JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+                    self.set_extended_limit_info(&info)
+                });
+
+                if let Err(err) = res {
+                    log_warn!(
+                        "Failed to clear kill on job close of {:?}: {}",
+                        self.handle,
+                        err
+                    );
+                }
+            }
+        }
+
+        if let Err(err) = unsafe { CloseHandle(self.handle) } {
+            log_warn!("Failed to close job handle {:?}: {}", self.handle, err);
         }
     }
 }
 
+/// Describe the process which could not be assigned to the job, so the error
+/// says which process it was.
+fn assign_error(proc_handle: HANDLE, pid: Option<u32>, source: io::Error) -> JobError {
+    let pid = pid.or_else(|| match unsafe { GetProcessId(proc_handle) } {
+        0 => None,
+        pid => Some(pid),
+    });
+
+    let Some(pid) = pid else {
+        return JobError::AssignFailed(source);
+    };
+
+    // `proc_handle` may not have the query access rights, but it keeps the pid from being reused.
+    let process = OpenedProcess::open(pid).ok();
+
+    #[cfg(windows)]
+    let image_name = process
+        .as_ref()
+        .and_then(|process| process.image_path().ok())
+        .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()));
+    #[cfg(not(windows))]
+    let image_name = None;
+
+    let exited = process
+        .and_then(|process| process.times().ok())
+        .is_some_and(|times| times.exit_time.is_some());
+
+    JobError::AssignProcessFailed {
+        pid: Pid::new(pid),
+        image_name,
+        exited,
+        source,
+    }
+}
+
 #[cfg(test)]
+// For the original `it_works` test, which is kept as is.
+#[allow(clippy::identity_op, clippy::unnecessary_mut_passed)]
 mod tests {
-    use windows::Win32::System::JobObjects::JOB_OBJECT_LIMIT_WORKINGSET;
+    use windows::Win32::System::JobObjects::{
+        JOB_OBJECT_LIMIT_ACTIVE_PROCESS, JOB_OBJECT_LIMIT_WORKINGSET,
+    };
+
+    use rusty_fork::rusty_fork_test;
+
+    use crate::{ExtendedLimitInfo, Job, Pid};
+
+    #[cfg(all(windows, feature = "full"))]
+    #[test]
+    fn singleton_held() {
+        use crate::test_util::DisposableChild;
+
+        let name = format!("win32job-test-singleton-{}", std::process::id());
+        let job = Job::create_named(&name).unwrap();
+
+        let child = DisposableChild::spawn_in(&job).unwrap();
+
+        assert!(Job::acquire_singleton(&name).unwrap().is_none());
+
+        job.terminate(1).unwrap();
+        child.wait().unwrap();
+    }
+
+    rusty_fork_test! {
+        #[cfg(all(windows, feature = "full"))]
+        #[test]
+        fn singleton() {
+            use windows::Win32::System::JobObjects::{
+                JOB_OBJECT_LIMIT_JOB_MEMORY, JOB_OBJECT_LIMIT_SILENT_BREAKAWAY_OK,
+            };
+
+            use crate::test_util::DisposableChild;
 
-    use crate::Job;
+            let name = format!("win32job-test-singleton-{}", std::process::id());
+
+            let existing = Job::create_named(&name).unwrap();
+            let mut info = ExtendedLimitInfo::new();
+            info.limit_job_memory(1 << 36);
+            existing.set_extended_limit_info(&info).unwrap();
+
+            let job = Job::acquire_singleton(&name).unwrap().unwrap();
+            assert_eq!(job.query_process_id_list().unwrap(), [Pid::current()]);
+
+            // The existing limits are kept.
+            let info = job.query_extended_limit_info().unwrap();
+            assert_eq!(
+                info.0.BasicLimitInformation.LimitFlags,
+                JOB_OBJECT_LIMIT_ACTIVE_PROCESS
+                    | JOB_OBJECT_LIMIT_SILENT_BREAKAWAY_OK
+                    | JOB_OBJECT_LIMIT_JOB_MEMORY
+            );
+            assert_eq!(info.0.JobMemoryLimit, 1 << 36);
+
+            // Acquiring again from the holder succeeds.
+            assert!(Job::acquire_singleton(&name).unwrap().is_some());
+
+            // Children silently break away from the job.
+            let child = DisposableChild::spawn().unwrap();
+            assert!(!job.contains_process(child.id()).unwrap());
+            assert_eq!(job.query_process_id_list().unwrap(), [Pid::current()]);
+        }
+
+        #[test]
+        fn no_child_processes() {
+            let job = Job::create().unwrap();
+            job.assign_current_process().unwrap();
+
+            let mut info = ExtendedLimitInfo::new();
+            info.limit_breakaway_ok();
+            job.set_extended_limit_info(&info).unwrap();
+
+            assert_eq!(job.no_child_processes().unwrap(), 1);
+
+            let info = job.query_extended_limit_info().unwrap();
+            assert_eq!(info.0.BasicLimitInformation.LimitFlags, JOB_OBJECT_LIMIT_ACTIVE_PROCESS);
+
+            assert!(std::process::Command::new("cmd")
+                .args(["/C", "exit 0"])
+                .spawn()
+                .is_err());
+        }
+    }
 
     #[test]
     fn it_works() {
@@ -135,4 +738,275 @@ mod tests {
         info.0.BasicLimitInformation.LimitFlags.0 = 0;
         job.set_extended_limit_info(&mut info).unwrap();
     }
+
+    #[test]
+    fn query_extended_limit_info_into() {
+        let job = Job::create().unwrap();
+
+        let mut info = ExtendedLimitInfo::new();
+        info.limit_working_memory(1024 * 1024, 4 * 1024 * 1024);
+        job.set_extended_limit_info(&info).unwrap();
+
+        let mut queried = ExtendedLimitInfo::new();
+        job.query_extended_limit_info_into(&mut queried).unwrap();
+        assert_eq!(queried, info);
+        assert_eq!(
+            queried.0.BasicLimitInformation.MaximumWorkingSetSize,
+            4 * 1024 * 1024
+        );
+    }
+
+    #[test]
+    fn swap_extended_limit_info() {
+        let job = Job::create().unwrap();
+
+        let mut info = ExtendedLimitInfo::new();
+        info.limit_working_memory(1024 * 1024, 4 * 1024 * 1024);
+        job.set_extended_limit_info(&info).unwrap();
+
+        let previous = job
+            .swap_extended_limit_info(&ExtendedLimitInfo::new())
+            .unwrap();
+        assert_eq!(previous, info);
+        assert_eq!(
+            job.query_extended_limit_info().unwrap(),
+            ExtendedLimitInfo::new()
+        );
+
+        let cleared = job.swap_extended_limit_info(&previous).unwrap();
+        assert_eq!(cleared, ExtendedLimitInfo::new());
+        assert_eq!(job.query_extended_limit_info().unwrap(), info);
+    }
+
+    #[cfg(all(windows, feature = "full"))]
+    #[test]
+    fn terminate_and_wait() {
+        use std::time::Duration;
+
+        use crate::test_util::DisposableChild;
+
+        let job = Job::create().unwrap();
+
+        let child = DisposableChild::spawn_in(&job).unwrap();
+
+        assert!(job
+            .terminate_and_wait(1, Some(Duration::from_secs(5)))
+            .unwrap());
+        assert!(job.is_empty().unwrap());
+
+        assert_eq!(child.wait().unwrap().code(), Some(1));
+    }
+
+    #[cfg(all(windows, feature = "full"))]
+    #[test]
+    fn wait_until_empty() {
+        use std::time::Duration;
+
+        use crate::test_util::DisposableChild;
+
+        let job = Job::create().unwrap();
+
+        assert!(job.wait_until_empty(Some(Duration::ZERO)).unwrap());
+
+        let child = DisposableChild::spawn_exiting_in(&job, 0, Duration::from_secs(2)).unwrap();
+
+        assert!(!job
+            .wait_until_empty(Some(Duration::from_millis(100)))
+            .unwrap());
+        assert!(job.wait_until_empty(Some(Duration::from_secs(10))).unwrap());
+
+        child.wait().unwrap();
+    }
+
+    #[cfg(all(windows, feature = "full"))]
+    #[test]
+    fn assign_failure_includes_process() {
+        use windows::Win32::{
+            Foundation::CloseHandle,
+            System::Threading::{
+                OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE,
+            },
+        };
+
+        use crate::test_util::DisposableChild;
+        use crate::JobError;
+
+        let child = DisposableChild::spawn().unwrap();
+
+        // Without `PROCESS_SET_QUOTA`, the assignment is denied.
+        let limited = unsafe {
+            OpenProcess(
+                PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_TERMINATE,
+                false,
+                child.id(),
+            )
+        }
+        .unwrap();
+
+        let job = Job::create().unwrap();
+
+        match job.assign_process(limited.0) {
+            Err(JobError::AssignProcessFailed {
+                pid,
+                image_name,
+                exited,
+                ..
+            }) => {
+                assert_eq!(pid, Pid::new(child.id()));
+                assert!(image_name.unwrap().eq_ignore_ascii_case("ping.exe"));
+                assert!(!exited);
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+
+        unsafe { CloseHandle(limited) }.unwrap();
+        child.kill().unwrap();
+    }
+
+    #[test]
+    fn name() {
+        assert_eq!(Job::create().unwrap().name().unwrap(), None);
+
+        let job = Job::create_named("win32job-test-name").unwrap();
+        let name = job.name().unwrap().unwrap();
+        assert!(name.ends_with(r"\BaseNamedObjects\win32job-test-name"));
+    }
+
+    #[cfg(all(windows, feature = "full"))]
+    #[test]
+    fn drop_behavior() {
+        use windows::Win32::System::JobObjects::JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+
+        use crate::test_util::DisposableChild;
+        use crate::DropBehavior;
+
+        let mut job = Job::create().unwrap();
+        job.set_drop_behavior(DropBehavior::TerminateFirst { exit_code: 4 });
+
+        let child = DisposableChild::spawn_in(&job).unwrap();
+
+        drop(job);
+        assert_eq!(child.wait().unwrap().code(), Some(4));
+
+        let mut info = ExtendedLimitInfo::new();
+        info.limit_kill_on_job_close();
+        let mut job = Job::create_with_limit_info(&info).unwrap();
+        job.set_drop_behavior(DropBehavior::ClearKillOnCloseThenClose);
+        let clone = job.try_clone().unwrap();
+
+        drop(job);
+        let flags = clone
+            .query_extended_limit_info()
+            .unwrap()
+            .0
+            .BasicLimitInformation
+            .LimitFlags;
+        assert_eq!(
+            flags & JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+            Default::default()
+        );
+    }
+
+    #[cfg(all(windows, feature = "full"))]
+    #[test]
+    fn verify_assign_access() {
+        use windows::Win32::{
+            Foundation::CloseHandle,
+            System::Threading::{
+                OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SET_QUOTA,
+                PROCESS_TERMINATE,
+            },
+        };
+
+        use crate::test_util::DisposableChild;
+        use crate::JobError;
+
+        let child = DisposableChild::spawn().unwrap();
+
+        let limited = unsafe {
+            OpenProcess(
+                PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_TERMINATE,
+                false,
+                child.id(),
+            )
+        }
+        .unwrap();
+
+        assert!(matches!(
+            Job::verify_assign_access(limited.0),
+            Err(JobError::MissingProcessAccess("PROCESS_SET_QUOTA"))
+        ));
+        assert!(matches!(
+            Job::verify_assign_access(0),
+            Err(JobError::GetInfoFailed { .. })
+        ));
+
+        let full = unsafe { OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, false, child.id()) }
+            .unwrap();
+
+        let job = Job::create().unwrap();
+        job.assign_process_verified(full.0).unwrap();
+
+        unsafe { CloseHandle(limited) }.unwrap();
+        unsafe { CloseHandle(full) }.unwrap();
+        job.terminate(1).unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn named_job() {
+        let name = format!("win32job-test-named-{}", std::process::id());
+
+        let job = Job::create_named(&name).unwrap();
+        let opened = Job::open(&name).unwrap();
+
+        opened.assign_current_process().unwrap();
+
+        let pids = job.query_process_id_list().unwrap();
+        assert!(pids.contains(&Pid::new(std::process::id())));
+
+        assert!(Job::open("win32job-test-no-such-job").is_err());
+    }
+
+    #[cfg(all(windows, feature = "full"))]
+    #[test]
+    fn kill_process() {
+        use crate::test_util::DisposableChild;
+        use crate::JobError;
+
+        let job = Job::create().unwrap();
+
+        let child = DisposableChild::spawn().unwrap();
+
+        assert!(matches!(
+            job.kill_process(child.id(), 3),
+            Err(JobError::ProcessNotInJob(_))
+        ));
+
+        job.assign_process_by_pid(child.id()).unwrap();
+        job.kill_process(child.id(), 3).unwrap();
+
+        assert_eq!(child.wait().unwrap().code(), Some(3));
+    }
+
+    #[cfg(all(windows, feature = "full"))]
+    #[test]
+    fn contains_process() {
+        use crate::test_util::DisposableChild;
+
+        let job = Job::create().unwrap();
+
+        let child = DisposableChild::spawn().unwrap();
+        let pid = Pid::new(child.id());
+
+        assert!(!job.contains_process(pid).unwrap());
+        job.assign_process_by_pid(pid).unwrap();
+        assert!(job.contains_process(pid).unwrap());
+
+        job.terminate(1).unwrap();
+
+        // Once the child is waited for, its last handle is closed and the process no longer exists.
+        child.wait().unwrap();
+        assert!(!job.contains_process(pid).unwrap());
+    }
 }
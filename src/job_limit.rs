@@ -0,0 +1,259 @@
+use std::fmt;
+
+use crate::{
+    CpuRateControlInfo, ExtendedLimitInfo, Job, JobError, LimitValidationError, PriorityClass,
+    SchedulingClass,
+};
+
+/// A single limit of a job, for declaratively applying limits using `job.apply_limits`.
+///
+/// ```edition2021
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// const GIB: usize = 1024 * 1024 * 1024;
+///
+/// let job = Job::create()?;
+/// job.apply_limits(&[
+///     JobLimit::JobMemory(2 * GIB),
+///     JobLimit::ActiveProcesses(64),
+///     JobLimit::KillOnClose,
+/// ])?;
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum JobLimit {
+    /// Minimum and maximum working set sizes of each process, in bytes (see `limit_working_memory`).
+    WorkingSet { min: usize, max: usize },
+    /// Committed memory of all the processes of the job, in bytes (see `limit_job_memory`).
+    JobMemory(usize),
+    /// Committed memory of each process of the job, in bytes (see `limit_process_memory`).
+    ProcessMemory(usize),
+    /// Number of simultaneously active processes (see `limit_active_processes`).
+    ActiveProcesses(u32),
+    /// Priority class of all the processes (see `limit_priority_class`).
+    PriorityClass(PriorityClass),
+    /// Scheduling class of all the processes (see `limit_scheduling_class`).
+    SchedulingClass(SchedulingClass),
+    /// Processor affinity of all the processes (see `limit_affinity`).
+    Affinity(usize),
+    /// Kill all the processes when the last handle to the job is closed (see `limit_kill_on_job_close`).
+    KillOnClose,
+    /// Allow breaking away from the job (see `limit_breakaway_ok`).
+    BreakawayOk,
+    /// Silently break away from the job (see `limit_silent_breakaway_ok`).
+    SilentBreakawayOk,
+    /// Hard cap on CPU usage, as a rate (see `CpuRateControlInfo::limit_hard_cap`).
+    CpuHardCap(u32),
+}
+
+impl JobLimit {
+    /// Return `true` if the limit is set using `CpuRateControlInfo` (rather than `ExtendedLimitInfo`).
+    fn is_cpu_rate(&self) -> bool {
+        matches!(self, JobLimit::CpuHardCap(_))
+    }
+
+    /// Check the limits which the OS would otherwise reject (or silently misapply) when set.
+    fn validate(&self) -> Result<(), LimitValidationError> {
+        match *self {
+            JobLimit::CpuHardCap(rate) if rate == 0 || rate > 10000 => {
+                Err(LimitValidationError::CpuRateOutOfRange(rate))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Add the limit to `extended` or to `cpu_rate`.
+    fn apply_to(&self, extended: &mut ExtendedLimitInfo, cpu_rate: &mut CpuRateControlInfo) {
+        match *self {
+            JobLimit::WorkingSet { min, max } => {
+                extended.limit_working_memory(min, max);
+            }
+            JobLimit::JobMemory(limit) => {
+                extended.limit_job_memory(limit);
+            }
+            JobLimit::ProcessMemory(limit) => {
+                extended.limit_process_memory(limit);
+            }
+            JobLimit::ActiveProcesses(limit) => {
+                extended.limit_active_processes(limit);
+            }
+            JobLimit::PriorityClass(priority_class) => {
+                extended.limit_priority_class(priority_class);
+            }
+            JobLimit::SchedulingClass(scheduling_class) => {
+                extended.limit_scheduling_class(scheduling_class);
+            }
+            JobLimit::Affinity(affinity) => {
+                extended.limit_affinity(affinity);
+            }
+            JobLimit::KillOnClose => {
+                extended.limit_kill_on_job_close();
+            }
+            JobLimit::BreakawayOk => {
+                extended.limit_breakaway_ok();
+            }
+            JobLimit::SilentBreakawayOk => {
+                extended.limit_silent_breakaway_ok();
+            }
+            JobLimit::CpuHardCap(rate) => {
+                cpu_rate.limit_hard_cap(rate);
+            }
+        }
+    }
+}
+
+impl fmt::Display for JobLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobLimit::WorkingSet { min, max } => write!(f, "working set {}-{} bytes", min, max),
+            JobLimit::JobMemory(limit) => write!(f, "job memory {} bytes", limit),
+            JobLimit::ProcessMemory(limit) => write!(f, "process memory {} bytes", limit),
+            JobLimit::ActiveProcesses(limit) => write!(f, "{} active processes", limit),
+            JobLimit::PriorityClass(priority_class) => {
+                write!(f, "priority class {:?}", priority_class)
+            }
+            JobLimit::SchedulingClass(scheduling_class) => {
+                write!(f, "scheduling class {}", scheduling_class.value())
+            }
+            JobLimit::Affinity(affinity) => write!(f, "affinity {:#x}", affinity),
+            JobLimit::KillOnClose => write!(f, "kill on close"),
+            JobLimit::BreakawayOk => write!(f, "breakaway ok"),
+            JobLimit::SilentBreakawayOk => write!(f, "silent breakaway ok"),
+            JobLimit::CpuHardCap(rate) => {
+                write!(f, "CPU hard cap {}.{:02}%", rate / 100, rate % 100)
+            }
+        }
+    }
+}
+
+impl Job {
+    /// Add `limits` to the current limits of the job.
+    /// Only the information classes which are affected by `limits` are queried and set.
+    ///
+    /// The limits are validated before anything is set. If setting the CPU rate control fails
+    /// after the extended limits were set, the extended limits are restored (best-effort) and
+    /// `JobError::ApplyConfigFailed` is returned, reporting whether restoring them succeeded.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), err)
+    )]
+    pub fn apply_limits(&self, limits: &[JobLimit]) -> Result<(), JobError> {
+        for limit in limits {
+            limit.validate()?;
+        }
+
+        let sets_extended = limits.iter().any(|limit| !limit.is_cpu_rate());
+        let sets_cpu_rate = limits.iter().any(JobLimit::is_cpu_rate);
+
+        let previous = match sets_extended {
+            true => Some(self.query_extended_limit_info()?),
+            false => None,
+        };
+        let mut extended = previous.clone().unwrap_or_default();
+        let mut cpu_rate = match sets_cpu_rate {
+            true => self.query_cpu_rate_control_info()?,
+            false => CpuRateControlInfo::new(),
+        };
+
+        for limit in limits {
+            limit.apply_to(&mut extended, &mut cpu_rate);
+        }
+
+        if sets_extended {
+            self.set_extended_limit_info(&extended)?;
+        }
+
+        if sets_cpu_rate {
+            if let Err(err) = self.set_cpu_rate_control_info(&cpu_rate) {
+                let Some(previous) = previous else {
+                    return Err(err);
+                };
+
+                let restored = match self.set_extended_limit_info(&previous) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        log_warn!("Failed to restore extended_limits: {}", err);
+                        false
+                    }
+                };
+
+                return Err(JobError::ApplyConfigFailed {
+                    class: "cpu_rate_control",
+                    restored,
+                    source: Box::new(err),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::Win32::System::JobObjects::{
+        JOB_OBJECT_LIMIT_ACTIVE_PROCESS, JOB_OBJECT_LIMIT_JOB_MEMORY,
+    };
+
+    use crate::{Job, JobError, JobLimit, LimitValidationError};
+
+    #[test]
+    fn apply_limits() {
+        let job = Job::create().unwrap();
+
+        job.apply_limits(&[
+            JobLimit::JobMemory(512 * 1024 * 1024),
+            JobLimit::ActiveProcesses(64),
+            JobLimit::CpuHardCap(5000),
+        ])
+        .unwrap();
+
+        let info = job.query_extended_limit_info().unwrap();
+        let flags = info.0.BasicLimitInformation.LimitFlags;
+        assert!(flags.contains(JOB_OBJECT_LIMIT_JOB_MEMORY | JOB_OBJECT_LIMIT_ACTIVE_PROCESS));
+        assert_eq!(info.0.JobMemoryLimit, 512 * 1024 * 1024);
+        assert_eq!(info.0.BasicLimitInformation.ActiveProcessLimit, 64);
+
+        let cpu_rate = job.query_cpu_rate_control_info().unwrap();
+        assert_eq!(cpu_rate.hard_cap(), Some(5000));
+    }
+
+    #[test]
+    fn rejects_invalid_hard_cap() {
+        let job = Job::create().unwrap();
+
+        for rate in [0, 10001] {
+            let err = job
+                .apply_limits(&[JobLimit::ActiveProcesses(8), JobLimit::CpuHardCap(rate)])
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                JobError::InvalidLimit(LimitValidationError::CpuRateOutOfRange(r)) if r == rate
+            ));
+        }
+
+        // Nothing was set.
+        let info = job.query_extended_limit_info().unwrap();
+        assert!(!info
+            .0
+            .BasicLimitInformation
+            .LimitFlags
+            .contains(JOB_OBJECT_LIMIT_ACTIVE_PROCESS));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            JobLimit::ActiveProcesses(64).to_string(),
+            "64 active processes"
+        );
+        assert_eq!(
+            JobLimit::CpuHardCap(2550).to_string(),
+            "CPU hard cap 25.50%"
+        );
+    }
+}
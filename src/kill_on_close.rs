@@ -0,0 +1,94 @@
+use std::sync::Mutex;
+
+use windows::Win32::{
+    Foundation::CompareObjectHandles, System::JobObjects::JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+};
+
+use crate::{Job, JobError};
+
+/// Duplicated handles to the armed jobs.
+static ARMED_JOBS: Mutex<Vec<Job>> = Mutex::new(Vec::new());
+
+impl Job {
+    /// Return `true` if both handles refer to the same job object.
+    fn is_same_job(&self, other: &Job) -> bool {
+        unsafe { CompareObjectHandles(self.handle, other.handle) }.as_bool()
+    }
+}
+
+impl Job {
+    /// Set the job to kill all of its processes when the last handle to it is closed,
+    /// and keep a duplicated handle to the job in a process-global holder
+    /// until `disarm_kill_on_close` is called.
+    ///
+    /// This way, dropping this `Job` (for example, on an early return) will not kill the
+    /// job's processes (which might include the current process). They are only killed
+    /// when the job is disarmed and all of its handles are closed, or when the current
+    /// process exits.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job = self.handle.0), err)
+    )]
+    pub fn arm_kill_on_close(&self) -> Result<(), JobError> {
+        let mut armed_jobs = ARMED_JOBS.lock().unwrap();
+
+        if armed_jobs.iter().any(|armed| armed.is_same_job(self)) {
+            return Ok(());
+        }
+
+        let mut info = self.query_extended_limit_info()?;
+        info.0.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        self.set_extended_limit_info(&info)?;
+
+        armed_jobs.push(self.try_clone()?);
+
+        Ok(())
+    }
+
+    /// Release the duplicated handle kept by `arm_kill_on_close`.
+    /// Can be called with any `Job` which refers to the same job object.
+    /// Return `true` if the job was armed.
+    pub fn disarm_kill_on_close(&self) -> bool {
+        let mut armed_jobs = ARMED_JOBS.lock().unwrap();
+
+        match armed_jobs.iter().position(|armed| armed.is_same_job(self)) {
+            Some(index) => {
+                armed_jobs.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusty_fork::rusty_fork_test;
+    use windows::Win32::System::JobObjects::JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+
+    use crate::Job;
+
+    rusty_fork_test! {
+        #[test]
+        fn arm_kill_on_close() {
+            let job = Job::create().unwrap();
+            job.assign_current_process().unwrap();
+
+            job.arm_kill_on_close().unwrap();
+
+            let info = job.query_extended_limit_info().unwrap();
+            assert!(info
+                .0
+                .BasicLimitInformation
+                .LimitFlags
+                .contains(JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE));
+
+            assert!(job.try_clone().unwrap().disarm_kill_on_close());
+            assert!(!job.disarm_kill_on_close());
+
+            // Dropping an armed job must not kill the current process.
+            job.arm_kill_on_close().unwrap();
+            drop(job);
+        }
+    }
+}
@@ -45,15 +45,161 @@
 //! #   Ok(())
 //! # }
 //! ```
+#[macro_use]
+mod macros;
+
+mod access;
+mod accounting;
+#[cfg(feature = "full")]
+mod bounded;
+mod builder;
+#[cfg(windows)]
+mod child;
+#[cfg(feature = "full")]
+mod churn;
+#[cfg(all(windows, feature = "full"))]
+mod command;
+#[cfg(feature = "full")]
+mod config;
+#[cfg(all(any(feature = "toml", feature = "json"), feature = "full"))]
+pub mod config_file;
+#[cfg(feature = "full")]
+mod console;
+#[cfg(feature = "full")]
+mod cpu_rate;
+mod cpu_set;
+#[cfg(all(windows, feature = "full"))]
+mod desktop;
 mod error;
+#[cfg(all(feature = "etw", feature = "full"))]
+mod etw;
+#[cfg(feature = "full")]
+mod governor;
+#[cfg(all(windows, feature = "full"))]
+mod group;
+mod handle;
+mod identity;
+mod info;
+mod io_counters;
+#[cfg(feature = "full")]
+mod io_rate;
+#[cfg(all(windows, feature = "janitor"))]
+mod janitor;
 mod job;
+#[cfg(feature = "full")]
+mod job_limit;
+#[cfg(feature = "full")]
+mod kill_on_close;
 mod limits;
+#[cfg(all(feature = "minidump", feature = "full"))]
+mod minidump;
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "full")]
+mod monitor;
+#[cfg(feature = "full")]
+mod net_rate;
+#[cfg(feature = "full")]
+mod notification;
+#[cfg(feature = "full")]
+mod notification_limits;
+mod ops;
+mod pid;
+#[cfg(feature = "full")]
+mod port;
+mod process;
 mod query;
+#[cfg(all(windows, feature = "full"))]
+mod sandbox;
+#[cfg(all(feature = "tokio", feature = "full"))]
+mod shutdown;
+#[cfg(feature = "full")]
+mod snapshot;
+#[cfg(all(windows, feature = "full"))]
+mod supervisor;
+pub mod sys;
+#[cfg(all(windows, feature = "full", any(test, feature = "test-util")))]
+pub mod test_util;
+#[cfg(all(windows, feature = "full"))]
+mod tree;
+#[cfg(feature = "full")]
+mod trimmer;
+#[cfg(feature = "full")]
+mod ui;
 pub mod utils;
+#[cfg(feature = "full")]
+mod window;
 
-pub use crate::error::JobError;
-pub use crate::job::Job;
-pub use crate::limits::{ExtendedLimitInfo, PriorityClass};
+pub use crate::access::{JobAccess, JobHandleInfo};
+pub use crate::accounting::{BasicAccountingInfo, BasicAndIoAccountingInfo, CpuTime};
+#[cfg(feature = "full")]
+pub use crate::bounded::{BoundedReceiver, OverflowPolicy};
+pub use crate::builder::{AppliedLimits, LimitsBuilder};
+#[cfg(feature = "full")]
+pub use crate::churn::ProcessChurn;
+#[cfg(all(windows, feature = "full"))]
+pub use crate::command::JobCommand;
+#[cfg(feature = "full")]
+pub use crate::config::{ClassCopyResult, ConfigDifference, JobConfig};
+#[cfg(feature = "full")]
+pub use crate::cpu_rate::CpuRateControlInfo;
+pub use crate::cpu_set::CpuSet;
+#[cfg(all(windows, feature = "full"))]
+pub use crate::desktop::IsolatedDesktop;
+pub use crate::error::{JobError, LimitValidationError};
+#[cfg(all(feature = "etw", feature = "full"))]
+pub use crate::etw::EtwProvider;
+#[cfg(feature = "full")]
+pub use crate::governor::CpuGovernor;
+#[cfg(all(windows, feature = "full"))]
+pub use crate::group::{CommandGroupExt, GroupChild};
+pub use crate::identity::ProcessIdentity;
+pub use crate::info::{JobInformation, SettableJobInformation};
+pub use crate::io_counters::IoCounters;
+#[cfg(feature = "full")]
+pub use crate::io_rate::IoRateControlInfo;
+#[cfg(all(windows, feature = "janitor"))]
+pub use crate::janitor::Janitor;
+pub use crate::job::{DropBehavior, Job};
+#[cfg(feature = "full")]
+pub use crate::job_limit::JobLimit;
+pub use crate::limits::{
+    ExtendedLimitInfo, LimitKind, PriorityClass, SchedulingClass, WorkingSetSizes,
+};
+#[cfg(all(feature = "minidump", feature = "full"))]
+pub use crate::minidump::MinidumpType;
+#[cfg(feature = "mock")]
+pub use crate::mock::MockJob;
+#[cfg(feature = "full")]
+pub use crate::monitor::{
+    JobMonitor, JobMonitorBuilder, PanicPolicy, ProcessDetails, ProcessExit, UntilEmpty,
+};
+#[cfg(feature = "full")]
+pub use crate::net_rate::NetRateControlInfo;
+#[cfg(feature = "full")]
+pub use crate::notification::{JobNotification, NotificationFilter, TimedNotification};
+#[cfg(feature = "full")]
+pub use crate::notification_limits::{
+    NotificationLimitInfo, NotificationLimitInfo2, RateControlTolerance, ToleranceInterval,
+};
+pub use crate::ops::JobOps;
+pub use crate::pid::Pid;
+#[cfg(feature = "full")]
+pub use crate::port::CompletionPort;
+pub use crate::process::OpenedProcess;
+pub use crate::query::QueryBuffer;
+#[cfg(all(windows, feature = "full"))]
+pub use crate::sandbox::{SandboxChild, SandboxCommand};
+#[cfg(feature = "full")]
+pub use crate::snapshot::JobSnapshot;
+#[cfg(all(windows, feature = "full"))]
+pub use crate::supervisor::{JobSupervisor, RestartPolicy};
+#[cfg(all(windows, feature = "full"))]
+pub use crate::tree::{ProcessNode, ProcessTree};
+#[cfg(feature = "full")]
+pub use crate::trimmer::{TrimAction, WorkingSetTrimmer};
+#[cfg(feature = "full")]
+pub use crate::ui::UiRestrictions;
 
 // Cannot use `cfg(test)` here since `rustdoc` won't look at it.
 #[cfg(debug_assertions)]
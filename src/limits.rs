@@ -1,9 +1,13 @@
+use std::time::Duration;
+
 use windows::Win32::System::{
     JobObjects::{
-        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_AFFINITY,
-        JOB_OBJECT_LIMIT_BREAKAWAY_OK, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
-        JOB_OBJECT_LIMIT_PRIORITY_CLASS, JOB_OBJECT_LIMIT_SCHEDULING_CLASS,
-        JOB_OBJECT_LIMIT_WORKINGSET,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT, JOB_OBJECT_LIMIT_ACTIVE_PROCESS,
+        JOB_OBJECT_LIMIT_AFFINITY, JOB_OBJECT_LIMIT_BREAKAWAY_OK, JOB_OBJECT_LIMIT_JOB_MEMORY,
+        JOB_OBJECT_LIMIT_JOB_TIME, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+        JOB_OBJECT_LIMIT_PRIORITY_CLASS, JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+        JOB_OBJECT_LIMIT_PROCESS_TIME, JOB_OBJECT_LIMIT_SCHEDULING_CLASS,
+        JOB_OBJECT_LIMIT_SILENT_BREAKAWAY_OK, JOB_OBJECT_LIMIT_WORKINGSET,
     },
     Threading::{
         ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS,
@@ -11,10 +15,24 @@ use windows::Win32::System::{
     },
 };
 
-#[derive(Debug)]
+use crate::utils::{
+    duration_from_100ns, duration_to_100ns, get_current_process, get_page_size,
+    get_process_affinity_mask,
+};
+use crate::{IoCounters, JobError, LimitValidationError};
+
+/// The smallest minimum working set size, in pages.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-setprocessworkingsetsizeex).
+const MIN_WORKING_SET_PAGES: usize = 20;
+
+/// The smallest maximum working set size, in pages.
+const MIN_MAX_WORKING_SET_PAGES: usize = 13;
+
+#[derive(Debug, Clone)]
 pub struct ExtendedLimitInfo(pub(crate) JOBOBJECT_EXTENDED_LIMIT_INFORMATION);
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum PriorityClass {
     Normal = NORMAL_PRIORITY_CLASS.0,
@@ -25,6 +43,239 @@ pub enum PriorityClass {
     AboveNormal = ABOVE_NORMAL_PRIORITY_CLASS.0,
 }
 
+impl TryFrom<u32> for PriorityClass {
+    type Error = JobError;
+
+    /// Convert a raw priority class (for example, as returned by `GetPriorityClass`).
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        [
+            PriorityClass::Normal,
+            PriorityClass::Idle,
+            PriorityClass::High,
+            PriorityClass::Realtime,
+            PriorityClass::BelowNormal,
+            PriorityClass::AboveNormal,
+        ]
+        .into_iter()
+        .find(|priority_class| *priority_class as u32 == value)
+        .ok_or(JobError::InvalidPriorityClass(value))
+    }
+}
+
+/// A scheduling class of a job, from 0 (the least favorable) to 9 (the most favorable).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8", into = "u8"))]
+pub struct SchedulingClass(u8);
+
+impl SchedulingClass {
+    /// The scheduling class of jobs without a scheduling class limit.
+    pub const DEFAULT: SchedulingClass = SchedulingClass(5);
+
+    /// The most favorable scheduling class.
+    pub const MAX: SchedulingClass = SchedulingClass(9);
+
+    /// Return a scheduling class, or `JobError::InvalidSchedulingClass` if `value` is greater than 9.
+    pub fn new(value: u8) -> Result<Self, JobError> {
+        if value > Self::MAX.0 {
+            return Err(JobError::InvalidSchedulingClass(value));
+        }
+
+        Ok(SchedulingClass(value))
+    }
+
+    /// Return a scheduling class, clamping `value` to 9.
+    pub fn clamped(value: u8) -> Self {
+        SchedulingClass(value.min(Self::MAX.0))
+    }
+
+    /// Return the value of the scheduling class.
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for SchedulingClass {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl From<SchedulingClass> for u8 {
+    fn from(scheduling_class: SchedulingClass) -> Self {
+        scheduling_class.0
+    }
+}
+
+impl TryFrom<u8> for SchedulingClass {
+    type Error = JobError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+/// Working set sizes which were aligned to the page size of the system,
+/// as returned by `WorkingSetSizes::aligned` and `info.limit_working_memory_aligned`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorkingSetSizes {
+    /// The (aligned) minimum working set size, in bytes.
+    pub min: usize,
+    /// The (aligned) maximum working set size, in bytes.
+    pub max: usize,
+    /// The requested minimum working set size, in bytes.
+    pub requested_min: usize,
+    /// The requested maximum working set size, in bytes.
+    pub requested_max: usize,
+}
+
+impl WorkingSetSizes {
+    /// Round `min` and `max` up to a multiple of the page size of the system,
+    /// and raise them to the smallest sizes allowed by the system
+    /// (with `max` being at least `min`).
+    pub fn aligned(min: usize, max: usize) -> Self {
+        Self::aligned_to(min, max, get_page_size())
+    }
+
+    fn aligned_to(requested_min: usize, requested_max: usize, page_size: usize) -> Self {
+        let round_up = |size: usize| size.div_ceil(page_size).saturating_mul(page_size);
+
+        let min = round_up(requested_min).max(MIN_WORKING_SET_PAGES * page_size);
+        let max = round_up(requested_max)
+            .max(MIN_MAX_WORKING_SET_PAGES * page_size)
+            .max(min);
+
+        WorkingSetSizes {
+            min,
+            max,
+            requested_min,
+            requested_max,
+        }
+    }
+
+    /// Return `true` if the aligned sizes differ from the requested sizes.
+    pub fn is_adjusted(&self) -> bool {
+        self.min != self.requested_min || self.max != self.requested_max
+    }
+}
+
+/// A kind of limit of `ExtendedLimitInfo`, for removing a single limit using `info.remove_limit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum LimitKind {
+    /// The minimum and maximum working set sizes (see `limit_working_memory`).
+    WorkingSet,
+    /// The committed memory of all the processes of the job (see `limit_job_memory`).
+    JobMemory,
+    /// The committed memory of each process of the job (see `limit_process_memory`).
+    ProcessMemory,
+    /// The user-mode execution time of each process (see `limit_process_time`).
+    ProcessTime,
+    /// The total user-mode execution time of the job (see `limit_job_time`).
+    JobTime,
+    /// The number of simultaneously active processes (see `limit_active_processes`).
+    ActiveProcesses,
+    /// The priority class of all the processes (see `limit_priority_class`).
+    PriorityClass,
+    /// The scheduling class of all the processes (see `limit_scheduling_class`).
+    SchedulingClass,
+    /// The processor affinity of all the processes (see `limit_affinity`).
+    Affinity,
+    /// Killing all the processes when the last handle to the job is closed (see `limit_kill_on_job_close`).
+    KillOnClose,
+    /// Allowing breaking away from the job (see `limit_breakaway_ok`).
+    BreakawayOk,
+    /// Silently breaking away from the job (see `limit_silent_breakaway_ok`).
+    SilentBreakawayOk,
+}
+
+impl LimitKind {
+    const ALL: [LimitKind; 12] = [
+        LimitKind::WorkingSet,
+        LimitKind::JobMemory,
+        LimitKind::ProcessMemory,
+        LimitKind::ProcessTime,
+        LimitKind::JobTime,
+        LimitKind::ActiveProcesses,
+        LimitKind::PriorityClass,
+        LimitKind::SchedulingClass,
+        LimitKind::Affinity,
+        LimitKind::KillOnClose,
+        LimitKind::BreakawayOk,
+        LimitKind::SilentBreakawayOk,
+    ];
+
+    /// The limit flag of the limit.
+    fn flag(self) -> JOB_OBJECT_LIMIT {
+        match self {
+            LimitKind::WorkingSet => JOB_OBJECT_LIMIT_WORKINGSET,
+            LimitKind::JobMemory => JOB_OBJECT_LIMIT_JOB_MEMORY,
+            LimitKind::ProcessMemory => JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+            LimitKind::ProcessTime => JOB_OBJECT_LIMIT_PROCESS_TIME,
+            LimitKind::JobTime => JOB_OBJECT_LIMIT_JOB_TIME,
+            LimitKind::ActiveProcesses => JOB_OBJECT_LIMIT_ACTIVE_PROCESS,
+            LimitKind::PriorityClass => JOB_OBJECT_LIMIT_PRIORITY_CLASS,
+            LimitKind::SchedulingClass => JOB_OBJECT_LIMIT_SCHEDULING_CLASS,
+            LimitKind::Affinity => JOB_OBJECT_LIMIT_AFFINITY,
+            LimitKind::KillOnClose => JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+            LimitKind::BreakawayOk => JOB_OBJECT_LIMIT_BREAKAWAY_OK,
+            LimitKind::SilentBreakawayOk => JOB_OBJECT_LIMIT_SILENT_BREAKAWAY_OK,
+        }
+    }
+
+    /// Copy the values of the limit (if it has any) from `src` to `dst`, without changing the flags.
+    fn copy_values(
+        self,
+        dst: &mut JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        src: &JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    ) {
+        let (dst_basic, src_basic) = (&mut dst.BasicLimitInformation, &src.BasicLimitInformation);
+
+        match self {
+            LimitKind::WorkingSet => {
+                dst_basic.MinimumWorkingSetSize = src_basic.MinimumWorkingSetSize;
+                dst_basic.MaximumWorkingSetSize = src_basic.MaximumWorkingSetSize;
+            }
+            LimitKind::JobMemory => dst.JobMemoryLimit = src.JobMemoryLimit,
+            LimitKind::ProcessMemory => dst.ProcessMemoryLimit = src.ProcessMemoryLimit,
+            LimitKind::ProcessTime => {
+                dst_basic.PerProcessUserTimeLimit = src_basic.PerProcessUserTimeLimit
+            }
+            LimitKind::JobTime => dst_basic.PerJobUserTimeLimit = src_basic.PerJobUserTimeLimit,
+            LimitKind::ActiveProcesses => {
+                dst_basic.ActiveProcessLimit = src_basic.ActiveProcessLimit
+            }
+            LimitKind::PriorityClass => dst_basic.PriorityClass = src_basic.PriorityClass,
+            LimitKind::SchedulingClass => dst_basic.SchedulingClass = src_basic.SchedulingClass,
+            LimitKind::Affinity => dst_basic.Affinity = src_basic.Affinity,
+            LimitKind::KillOnClose | LimitKind::BreakawayOk | LimitKind::SilentBreakawayOk => {}
+        }
+    }
+}
+
+// Accounting fields (such as `IoInfo` and `PeakJobMemoryUsed`) are not compared, and neither are
+// the values of limits which are not set (a new job reports a `SchedulingClass` of 5).
+impl_eq_hash_by_key!(ExtendedLimitInfo, |info| {
+    let info = ExtendedLimitInfo(*info).set_limits();
+    let basic = &info.BasicLimitInformation;
+
+    (
+        basic.LimitFlags.0,
+        basic.MinimumWorkingSetSize,
+        basic.MaximumWorkingSetSize,
+        basic.PerProcessUserTimeLimit,
+        basic.PerJobUserTimeLimit,
+        basic.ActiveProcessLimit,
+        basic.Affinity,
+        basic.PriorityClass,
+        basic.SchedulingClass,
+        info.ProcessMemoryLimit,
+        info.JobMemoryLimit,
+    )
+});
+
 impl Default for ExtendedLimitInfo {
     fn default() -> Self {
         Self::new()
@@ -41,6 +292,34 @@ impl ExtendedLimitInfo {
         ExtendedLimitInfo(inner)
     }
 
+    /// Create an extended info object from the underlying `JOBOBJECT_EXTENDED_LIMIT_INFORMATION`,
+    /// such as one received from other FFI code.
+    pub fn from_raw(raw: JOBOBJECT_EXTENDED_LIMIT_INFORMATION) -> Self {
+        ExtendedLimitInfo(raw)
+    }
+
+    /// Return the underlying `JOBOBJECT_EXTENDED_LIMIT_INFORMATION`.
+    pub fn into_raw(self) -> JOBOBJECT_EXTENDED_LIMIT_INFORMATION {
+        self.0
+    }
+
+    /// Return the limit flags and the values of the limits which are set, with the values of
+    /// the other limits (which the system ignores, and may report as non-zero) cleared.
+    pub(crate) fn set_limits(&self) -> JOBOBJECT_EXTENDED_LIMIT_INFORMATION {
+        let flags = self.0.BasicLimitInformation.LimitFlags;
+
+        let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+        info.BasicLimitInformation.LimitFlags = flags;
+
+        for kind in LimitKind::ALL {
+            if flags.contains(kind.flag()) {
+                kind.copy_values(&mut info, &self.0);
+            }
+        }
+
+        info
+    }
+
     /// Causes all processes associated with the job
     /// to use the same minimum and maximum working set sizes
     pub fn limit_working_memory(&mut self, min: usize, max: usize) -> &mut Self {
@@ -52,6 +331,15 @@ impl ExtendedLimitInfo {
         self
     }
 
+    /// Like `limit_working_memory`, but align `min` and `max` to valid sizes first
+    /// (see `WorkingSetSizes::aligned`), and return the sizes which were used.
+    pub fn limit_working_memory_aligned(&mut self, min: usize, max: usize) -> WorkingSetSizes {
+        let sizes = WorkingSetSizes::aligned(min, max);
+        self.limit_working_memory(sizes.min, sizes.max);
+
+        sizes
+    }
+
     /// Causes all processes associated with the job to terminate
     /// when the last handle to the job is closed.
     /// Note, that that `drop`ing the `Job` struct closes this handle, and if it's the only handle
@@ -70,6 +358,14 @@ impl ExtendedLimitInfo {
         self
     }
 
+    /// Allows any process associated with the job to create child processes
+    /// that are not associated with the job, without passing `CREATE_BREAKAWAY_FROM_JOB`.
+    pub fn limit_silent_breakaway_ok(&mut self) -> &mut Self {
+        self.0.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_SILENT_BREAKAWAY_OK;
+
+        self
+    }
+
     /// Causes all processes associated with the job to use the same priority class.
     /// Note: Processes and threads cannot modify their priority class.
     /// The calling process must enable the `SE_INC_BASE_PRIORITY_NAME` privilege.
@@ -81,14 +377,13 @@ impl ExtendedLimitInfo {
     }
 
     /// Causes all processes in the job to use the same scheduling class.
-    /// The valid values are 0 to 9.
     /// Use 0 for the least favorable scheduling class relative to other threads,
     /// and 9 for the most favorable scheduling class relative to other threads.
     /// By default, this value is 5.
     /// Note: To use a scheduling class greater than 5,
     /// the calling process must enable the `SE_INC_BASE_PRIORITY_NAME` privilege.
-    pub fn limit_scheduling_class(&mut self, scheduling_class: u8) -> &mut Self {
-        self.0.BasicLimitInformation.SchedulingClass = scheduling_class as u32;
+    pub fn limit_scheduling_class(&mut self, scheduling_class: SchedulingClass) -> &mut Self {
+        self.0.BasicLimitInformation.SchedulingClass = scheduling_class.0 as u32;
         self.0.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_SCHEDULING_CLASS;
 
         self
@@ -102,22 +397,556 @@ impl ExtendedLimitInfo {
         self
     }
 
+    /// Limit the virtual memory that can be committed by all the processes of the job to `limit` bytes.
+    pub fn limit_job_memory(&mut self, limit: usize) -> &mut Self {
+        self.0.JobMemoryLimit = limit;
+        self.0.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+
+        self
+    }
+
+    /// Limit the virtual memory that can be committed by each process of the job to `limit` bytes.
+    pub fn limit_process_memory(&mut self, limit: usize) -> &mut Self {
+        self.0.ProcessMemoryLimit = limit;
+        self.0.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+
+        self
+    }
+
+    /// Limit the user-mode execution time of each process of the job to `time`.
+    /// Processes which exceed the limit are terminated (and `JobNotification::EndOfProcessTime`
+    /// is posted).
+    pub fn limit_process_time(&mut self, time: Duration) -> &mut Self {
+        self.0.BasicLimitInformation.PerProcessUserTimeLimit = duration_to_100ns(time);
+        self.0.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_TIME;
+
+        self
+    }
+
+    /// Limit the total user-mode execution time of the processes of the job to `time`.
+    /// Once the limit is exceeded, all the processes of the job are terminated (unless
+    /// the end-of-job-time action is changed to only post `JobNotification::EndOfJobTime`).
+    /// Setting the limit again resets the time which was used so far.
+    pub fn limit_job_time(&mut self, time: Duration) -> &mut Self {
+        self.0.BasicLimitInformation.PerJobUserTimeLimit = duration_to_100ns(time);
+        self.0.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_TIME;
+
+        self
+    }
+
+    /// Return the per-process user-mode execution time limit, if set.
+    pub fn process_time_limit(&self) -> Option<Duration> {
+        let basic = &self.0.BasicLimitInformation;
+
+        basic
+            .LimitFlags
+            .contains(JOB_OBJECT_LIMIT_PROCESS_TIME)
+            .then(|| duration_from_100ns(basic.PerProcessUserTimeLimit))
+    }
+
+    /// Return the per-job user-mode execution time limit, if set.
+    pub fn job_time_limit(&self) -> Option<Duration> {
+        let basic = &self.0.BasicLimitInformation;
+
+        basic
+            .LimitFlags
+            .contains(JOB_OBJECT_LIMIT_JOB_TIME)
+            .then(|| duration_from_100ns(basic.PerJobUserTimeLimit))
+    }
+
+    /// Like `limit_working_memory`, but return an error if `min` is greater than `max`.
+    pub fn try_limit_working_memory(
+        &mut self,
+        min: usize,
+        max: usize,
+    ) -> Result<&mut Self, LimitValidationError> {
+        if min > max {
+            return Err(LimitValidationError::WorkingSetMinExceedsMax { min, max });
+        }
+
+        Ok(self.limit_working_memory(min, max))
+    }
+
+    /// Like `limit_scheduling_class`, but take the raw scheduling class, and return an error
+    /// if it is greater than 9.
+    pub fn try_limit_scheduling_class(
+        &mut self,
+        scheduling_class: u8,
+    ) -> Result<&mut Self, LimitValidationError> {
+        let scheduling_class = SchedulingClass::new(scheduling_class)
+            .map_err(|_| LimitValidationError::SchedulingClassOutOfRange(scheduling_class))?;
+
+        Ok(self.limit_scheduling_class(scheduling_class))
+    }
+
+    /// Like `limit_affinity`, but return an error if `affinity` is empty,
+    /// or contains processors which are not in the system affinity mask.
+    pub fn try_limit_affinity(
+        &mut self,
+        affinity: usize,
+    ) -> Result<&mut Self, LimitValidationError> {
+        if affinity == 0 {
+            return Err(LimitValidationError::EmptyAffinity);
+        }
+
+        // Querying the current process' affinity does not fail in practice, but if it does,
+        // the system will still reject an invalid mask when the info is set.
+        if let Ok((_, system)) = get_process_affinity_mask(get_current_process()) {
+            if affinity & !system != 0 {
+                return Err(LimitValidationError::AffinityNotInSystemMask { affinity, system });
+            }
+        }
+
+        Ok(self.limit_affinity(affinity))
+    }
+
+    /// Like `limit_job_memory`, but return an error if `limit` is 0.
+    pub fn try_limit_job_memory(
+        &mut self,
+        limit: usize,
+    ) -> Result<&mut Self, LimitValidationError> {
+        if limit == 0 {
+            return Err(LimitValidationError::ZeroMemoryLimit);
+        }
+
+        Ok(self.limit_job_memory(limit))
+    }
+
+    /// Like `limit_process_memory`, but return an error if `limit` is 0.
+    pub fn try_limit_process_memory(
+        &mut self,
+        limit: usize,
+    ) -> Result<&mut Self, LimitValidationError> {
+        if limit == 0 {
+            return Err(LimitValidationError::ZeroMemoryLimit);
+        }
+
+        Ok(self.limit_process_memory(limit))
+    }
+
+    /// Like `limit_active_processes`, but return an error if `limit` is 0
+    /// (which would prevent any process from being assigned to the job).
+    pub fn try_limit_active_processes(
+        &mut self,
+        limit: u32,
+    ) -> Result<&mut Self, LimitValidationError> {
+        if limit == 0 {
+            return Err(LimitValidationError::ZeroActiveProcesses);
+        }
+
+        Ok(self.limit_active_processes(limit))
+    }
+
+    /// Return the minimum and maximum working set sizes, if set.
+    pub fn working_memory_limit(&self) -> Option<(usize, usize)> {
+        let basic = &self.0.BasicLimitInformation;
+
+        basic
+            .LimitFlags
+            .contains(JOB_OBJECT_LIMIT_WORKINGSET)
+            .then_some((basic.MinimumWorkingSetSize, basic.MaximumWorkingSetSize))
+    }
+
+    /// Return `true` if the processes of the job are terminated when the last handle to the job is closed.
+    pub fn kill_on_job_close(&self) -> bool {
+        self.has_flag(JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE)
+    }
+
+    /// Return `true` if child processes may break away from the job using `CREATE_BREAKAWAY_FROM_JOB`.
+    pub fn breakaway_ok(&self) -> bool {
+        self.has_flag(JOB_OBJECT_LIMIT_BREAKAWAY_OK)
+    }
+
+    /// Return `true` if child processes are never associated with the job.
+    pub fn silent_breakaway_ok(&self) -> bool {
+        self.has_flag(JOB_OBJECT_LIMIT_SILENT_BREAKAWAY_OK)
+    }
+
+    /// Return the priority class limit, if set (and valid).
+    pub fn priority_class_limit(&self) -> Option<PriorityClass> {
+        let basic = &self.0.BasicLimitInformation;
+
+        basic
+            .LimitFlags
+            .contains(JOB_OBJECT_LIMIT_PRIORITY_CLASS)
+            .then(|| PriorityClass::try_from(basic.PriorityClass).ok())
+            .flatten()
+    }
+
+    /// Return the scheduling class limit, if set.
+    pub fn scheduling_class_limit(&self) -> Option<SchedulingClass> {
+        let basic = &self.0.BasicLimitInformation;
+
+        basic
+            .LimitFlags
+            .contains(JOB_OBJECT_LIMIT_SCHEDULING_CLASS)
+            .then(|| SchedulingClass::clamped(basic.SchedulingClass.min(u8::MAX as u32) as u8))
+    }
+
+    /// Return the processor affinity limit, if set.
+    pub fn affinity_limit(&self) -> Option<usize> {
+        let basic = &self.0.BasicLimitInformation;
+
+        basic
+            .LimitFlags
+            .contains(JOB_OBJECT_LIMIT_AFFINITY)
+            .then_some(basic.Affinity)
+    }
+
+    /// Return the limit of the virtual memory committed by all the processes of the job, in bytes, if set.
+    pub fn job_memory_limit(&self) -> Option<usize> {
+        self.has_flag(JOB_OBJECT_LIMIT_JOB_MEMORY)
+            .then_some(self.0.JobMemoryLimit)
+    }
+
+    /// Return the limit of the virtual memory committed by each process of the job, in bytes, if set.
+    pub fn process_memory_limit(&self) -> Option<usize> {
+        self.has_flag(JOB_OBJECT_LIMIT_PROCESS_MEMORY)
+            .then_some(self.0.ProcessMemoryLimit)
+    }
+
+    /// Return the limit of the number of simultaneously active processes, if set.
+    pub fn active_processes_limit(&self) -> Option<u32> {
+        self.has_flag(JOB_OBJECT_LIMIT_ACTIVE_PROCESS)
+            .then_some(self.0.BasicLimitInformation.ActiveProcessLimit)
+    }
+
+    /// Return the peak virtual memory committed by any process of the job, in bytes,
+    /// when queried using `job.query_extended_limit_info`.
+    pub fn peak_process_memory_used(&self) -> usize {
+        self.0.PeakProcessMemoryUsed
+    }
+
+    /// Return the peak virtual memory committed by all the processes of the job, in bytes,
+    /// when queried using `job.query_extended_limit_info`.
+    pub fn peak_job_memory_used(&self) -> usize {
+        self.0.PeakJobMemoryUsed
+    }
+
+    fn has_flag(&self, flag: JOB_OBJECT_LIMIT) -> bool {
+        self.0.BasicLimitInformation.LimitFlags.contains(flag)
+    }
+
+    /// Limit the number of simultaneously active processes of the job to `limit`.
+    /// Creating a process beyond the limit fails (and `JobNotification::ActiveProcessLimit` is posted).
+    pub fn limit_active_processes(&mut self, limit: u32) -> &mut Self {
+        self.0.BasicLimitInformation.ActiveProcessLimit = limit;
+        self.0.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_ACTIVE_PROCESS;
+
+        self
+    }
+
+    /// Return the I/O accounting information of the job, when queried using `job.query_extended_limit_info`.
+    pub fn io_counters(&self) -> IoCounters {
+        self.0.IoInfo.into()
+    }
+
+    /// Remove a single limit: clear its flag and reset its values, keeping all the other limits.
+    ///
+    /// Unlike `clear_limits`, this can be used to lift (for example) only the memory limit of
+    /// a job, while keeping its affinity and priority class limits.
+    pub fn remove_limit(&mut self, kind: LimitKind) -> &mut Self {
+        self.0.BasicLimitInformation.LimitFlags &= !kind.flag();
+        kind.copy_values(&mut self.0, &Default::default());
+
+        self
+    }
+
     /// Clear all limits.
     pub fn clear_limits(&mut self) -> &mut Self {
         self.0.BasicLimitInformation.LimitFlags.0 = 0;
 
         self
     }
+
+    /// Overlay the limits which are set in `other` onto this info: the limit flags of both are
+    /// combined, and for limits which are set in both, the values of `other` are used.
+    ///
+    /// This can be used for layered policies, such as a base profile with per-tenant overrides:
+    ///
+    /// ```edition2021
+    /// use win32job::*;
+    ///
+    /// let mut base = ExtendedLimitInfo::new();
+    /// base.limit_job_memory(1024 * 1024 * 1024).limit_active_processes(16);
+    ///
+    /// let mut tenant = ExtendedLimitInfo::new();
+    /// tenant.limit_job_memory(256 * 1024 * 1024);
+    ///
+    /// base.merge(&tenant);
+    /// assert_eq!(base.job_memory_limit(), Some(256 * 1024 * 1024));
+    /// assert_eq!(base.active_processes_limit(), Some(16));
+    /// ```
+    pub fn merge(&mut self, other: &ExtendedLimitInfo) -> &mut Self {
+        let flags = other.0.BasicLimitInformation.LimitFlags;
+
+        for kind in LimitKind::ALL {
+            if flags.contains(kind.flag()) {
+                kind.copy_values(&mut self.0, &other.0);
+            }
+        }
+
+        // Flags without a `LimitKind` (like `JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION`) are only combined.
+        self.0.BasicLimitInformation.LimitFlags |= flags;
+
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::utils::{get_current_process, get_process_affinity_mask, get_process_memory_info};
-    use crate::{Job, PriorityClass};
+    use std::time::Duration;
+
+    use crate::utils::{
+        get_current_process, get_priority_class, get_process_affinity_mask, get_process_memory_info,
+    };
+    use crate::{
+        ExtendedLimitInfo, Job, JobError, LimitKind, LimitValidationError, PriorityClass,
+        SchedulingClass, WorkingSetSizes,
+    };
     use rusty_fork::rusty_fork_test;
 
+    #[test]
+    fn working_set_alignment() {
+        let page_size = 4096;
+
+        let sizes = WorkingSetSizes::aligned_to(1024 * 1024, 4 * 1024 * 1024, page_size);
+        assert_eq!((sizes.min, sizes.max), (1024 * 1024, 4 * 1024 * 1024));
+        assert!(!sizes.is_adjusted());
+
+        let sizes = WorkingSetSizes::aligned_to(1024 * 1024 + 1, 1000, page_size);
+        assert_eq!(sizes.min, 1024 * 1024 + page_size);
+        assert_eq!(sizes.max, sizes.min);
+        assert!(sizes.is_adjusted());
+
+        let sizes = WorkingSetSizes::aligned_to(0, 0, page_size);
+        assert_eq!(sizes.min, 20 * page_size);
+        assert_eq!(sizes.max, 20 * page_size);
+    }
+
+    #[test]
+    fn scheduling_class_validation() {
+        assert_eq!(SchedulingClass::new(9).unwrap().value(), 9);
+        assert!(matches!(
+            SchedulingClass::new(200),
+            Err(JobError::InvalidSchedulingClass(200))
+        ));
+        assert_eq!(SchedulingClass::clamped(200), SchedulingClass::MAX);
+        assert_eq!(SchedulingClass::default().value(), 5);
+    }
+
+    #[test]
+    fn compares_set_limits_only() {
+        let job = Job::create().unwrap();
+        assert_eq!(
+            job.query_extended_limit_info().unwrap(),
+            ExtendedLimitInfo::new()
+        );
+
+        let mut raw = ExtendedLimitInfo::new().into_raw();
+        raw.BasicLimitInformation.SchedulingClass = 5;
+        raw.BasicLimitInformation.ActiveProcessLimit = 3;
+        assert_eq!(ExtendedLimitInfo::from_raw(raw), ExtendedLimitInfo::new());
+
+        let mut info = ExtendedLimitInfo::new();
+        info.limit_active_processes(3);
+        assert_ne!(info, ExtendedLimitInfo::new());
+        assert_ne!(info, ExtendedLimitInfo::from_raw(raw));
+    }
+
+    #[test]
+    fn raw_conversions() {
+        let mut info = ExtendedLimitInfo::new();
+        info.limit_active_processes(3).limit_kill_on_job_close();
+
+        let raw = info.into_raw();
+        assert_eq!(raw.BasicLimitInformation.ActiveProcessLimit, 3);
+
+        let info = ExtendedLimitInfo::from_raw(raw);
+        assert_eq!(
+            info.into_raw().BasicLimitInformation.LimitFlags,
+            raw.BasicLimitInformation.LimitFlags
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let mut base = ExtendedLimitInfo::new();
+        base.limit_job_memory(1024 * 1024 * 1024)
+            .limit_affinity(0b1111)
+            .limit_priority_class(PriorityClass::BelowNormal);
+
+        let mut overrides = ExtendedLimitInfo::new();
+        overrides
+            .limit_job_memory(256 * 1024 * 1024)
+            .limit_kill_on_job_close()
+            .limit_process_time(Duration::from_secs(10));
+
+        base.merge(&overrides);
+
+        assert_eq!(base.job_memory_limit(), Some(256 * 1024 * 1024));
+        assert_eq!(base.affinity_limit(), Some(0b1111));
+        assert_eq!(
+            base.priority_class_limit(),
+            Some(PriorityClass::BelowNormal)
+        );
+        assert!(base.kill_on_job_close());
+        assert_eq!(base.process_time_limit(), Some(Duration::from_secs(10)));
+
+        // Merging an empty info changes nothing.
+        let merged = base.clone();
+        base.merge(&ExtendedLimitInfo::new());
+        assert_eq!(base, merged);
+    }
+
+    #[test]
+    fn remove_limit() {
+        let mut info = ExtendedLimitInfo::new();
+        info.limit_job_memory(64 * 1024 * 1024)
+            .limit_affinity(0b11)
+            .limit_priority_class(PriorityClass::BelowNormal)
+            .limit_kill_on_job_close();
+
+        info.remove_limit(LimitKind::JobMemory)
+            .remove_limit(LimitKind::KillOnClose)
+            .remove_limit(LimitKind::ProcessTime);
+
+        assert_eq!(info.job_memory_limit(), None);
+        assert_eq!(info.raw().JobMemoryLimit, 0);
+        assert!(!info.kill_on_job_close());
+        assert_eq!(info.affinity_limit(), Some(0b11));
+        assert_eq!(
+            info.priority_class_limit(),
+            Some(PriorityClass::BelowNormal)
+        );
+
+        let mut expected = ExtendedLimitInfo::new();
+        expected
+            .limit_affinity(0b11)
+            .limit_priority_class(PriorityClass::BelowNormal);
+        assert_eq!(info, expected);
+    }
+
+    #[test]
+    fn checked_setters() {
+        let mut info = ExtendedLimitInfo::new();
+
+        assert_eq!(
+            info.try_limit_working_memory(2, 1).unwrap_err(),
+            LimitValidationError::WorkingSetMinExceedsMax { min: 2, max: 1 }
+        );
+        assert_eq!(
+            info.try_limit_scheduling_class(10).unwrap_err(),
+            LimitValidationError::SchedulingClassOutOfRange(10)
+        );
+        assert_eq!(
+            info.try_limit_affinity(0).unwrap_err(),
+            LimitValidationError::EmptyAffinity
+        );
+        assert_eq!(
+            info.try_limit_active_processes(0).unwrap_err(),
+            LimitValidationError::ZeroActiveProcesses
+        );
+        assert_eq!(
+            info.try_limit_job_memory(0).unwrap_err(),
+            LimitValidationError::ZeroMemoryLimit
+        );
+        assert_eq!(info, ExtendedLimitInfo::new());
+
+        let (_, system) = get_process_affinity_mask(get_current_process()).unwrap();
+        if system != usize::MAX {
+            assert!(matches!(
+                info.try_limit_affinity(usize::MAX),
+                Err(LimitValidationError::AffinityNotInSystemMask { .. })
+            ));
+        }
+
+        info.try_limit_working_memory(1024 * 1024, 4 * 1024 * 1024)
+            .unwrap()
+            .try_limit_scheduling_class(3)
+            .unwrap()
+            .try_limit_affinity(system & system.wrapping_neg())
+            .unwrap()
+            .try_limit_active_processes(4)
+            .unwrap()
+            .try_limit_process_memory(16 * 1024 * 1024)
+            .unwrap();
+        assert_eq!(info.active_processes_limit(), Some(4));
+        assert_eq!(
+            info.scheduling_class_limit(),
+            Some(SchedulingClass::clamped(3))
+        );
+
+        let err: JobError = LimitValidationError::EmptyAffinity.into();
+        assert!(matches!(err, JobError::InvalidLimit(_)));
+    }
+
+    #[test]
+    fn typed_accessors() {
+        let info = ExtendedLimitInfo::new();
+        assert_eq!(info.working_memory_limit(), None);
+        assert!(!info.kill_on_job_close());
+        assert_eq!(info.priority_class_limit(), None);
+        assert_eq!(info.job_memory_limit(), None);
+        assert_eq!(info.active_processes_limit(), None);
+
+        let mut info = ExtendedLimitInfo::new();
+        info.limit_working_memory(1024 * 1024, 4 * 1024 * 1024)
+            .limit_kill_on_job_close()
+            .limit_breakaway_ok()
+            .limit_priority_class(PriorityClass::BelowNormal)
+            .limit_scheduling_class(SchedulingClass::clamped(3))
+            .limit_affinity(0b11)
+            .limit_job_memory(64 * 1024 * 1024)
+            .limit_process_memory(16 * 1024 * 1024)
+            .limit_active_processes(4);
+
+        assert_eq!(
+            info.working_memory_limit(),
+            Some((1024 * 1024, 4 * 1024 * 1024))
+        );
+        assert!(info.kill_on_job_close());
+        assert!(info.breakaway_ok());
+        assert!(!info.silent_breakaway_ok());
+        assert_eq!(
+            info.priority_class_limit(),
+            Some(PriorityClass::BelowNormal)
+        );
+        assert_eq!(
+            info.scheduling_class_limit(),
+            Some(SchedulingClass::clamped(3))
+        );
+        assert_eq!(info.affinity_limit(), Some(0b11));
+        assert_eq!(info.job_memory_limit(), Some(64 * 1024 * 1024));
+        assert_eq!(info.process_memory_limit(), Some(16 * 1024 * 1024));
+        assert_eq!(info.active_processes_limit(), Some(4));
+        assert_eq!(info.raw().BasicLimitInformation.ActiveProcessLimit, 4);
+
+        info.raw_mut().BasicLimitInformation.ActiveProcessLimit = 5;
+        assert_eq!(info.active_processes_limit(), Some(5));
+    }
+
+    #[test]
+    fn time_limits() {
+        let job = Job::create().unwrap();
+
+        let info = job.query_extended_limit_info().unwrap();
+        assert_eq!(info.process_time_limit(), None);
+        assert_eq!(info.job_time_limit(), None);
+
+        let mut info = ExtendedLimitInfo::new();
+        info.limit_process_time(Duration::from_secs(30))
+            .limit_job_time(Duration::from_millis(1500));
+        job.set_extended_limit_info(&info).unwrap();
+
+        let info = job.query_extended_limit_info().unwrap();
+        assert_eq!(info.process_time_limit(), Some(Duration::from_secs(30)));
+        assert_eq!(info.job_time_limit(), Some(Duration::from_millis(1500)));
+    }
+
     rusty_fork_test! {
         #[test]
+        #[allow(clippy::identity_op, clippy::unnecessary_mut_passed)]
         fn working_mem_limits() {
             let job = Job::create().unwrap();
             let mut info = job.query_extended_limit_info().unwrap();
@@ -145,6 +974,7 @@ mod tests {
 
     rusty_fork_test! {
         #[test]
+        #[allow(clippy::unnecessary_mut_passed)]
         fn kill_on_job_close_limits() {
             let job = Job::create().unwrap();
             let mut info = job.query_extended_limit_info().unwrap();
@@ -164,6 +994,7 @@ mod tests {
 
     rusty_fork_test! {
         #[test]
+        #[allow(clippy::unnecessary_mut_passed)]
         fn priority_class_limits() {
             let job = Job::create().unwrap();
 
@@ -176,17 +1007,25 @@ mod tests {
             let info = job.query_extended_limit_info().unwrap();
 
             assert_eq!(info.0.BasicLimitInformation.PriorityClass, PriorityClass::BelowNormal as u32);
+
+            job.assign_current_process().unwrap();
+
+            assert_eq!(
+                get_priority_class(get_current_process()).unwrap(),
+                PriorityClass::BelowNormal
+            );
         }
     }
 
     rusty_fork_test! {
         #[test]
+        #[allow(clippy::unnecessary_mut_passed)]
         fn scheduling_class_limits() {
             let job = Job::create().unwrap();
 
             let mut info = job.query_extended_limit_info().unwrap();
 
-            info.limit_scheduling_class(1);
+            info.limit_scheduling_class(SchedulingClass::new(1).unwrap());
 
             job.set_extended_limit_info(&mut info).unwrap();
 
@@ -196,8 +1035,32 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_scheduling_class() {
+        use serde::{de::IntoDeserializer, Deserialize};
+
+        let deserialize = |value: u8| {
+            SchedulingClass::deserialize(
+                IntoDeserializer::<serde::de::value::Error>::into_deserializer(value),
+            )
+        };
+
+        assert_eq!(deserialize(7).unwrap().value(), 7);
+        assert!(deserialize(200).is_err());
+    }
+
+    #[test]
+    fn io_counters() {
+        let job = Job::create().unwrap();
+
+        let info = job.query_extended_limit_info().unwrap();
+        assert_eq!(info.io_counters(), Default::default());
+    }
+
     rusty_fork_test! {
         #[test]
+        #[allow(clippy::unnecessary_mut_passed)]
         fn affinity_limits() {
             let job = Job::create().unwrap();
 
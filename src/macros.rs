@@ -0,0 +1,51 @@
+/// Log a debug message with the `log` crate, if the `log` feature is enabled.
+#[allow(unused_macros)]
+macro_rules! log_debug {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::debug!(target: "win32job", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format!($($arg)+);
+        }
+    };
+}
+
+/// Log a warning with the `log` crate, if the `log` feature is enabled.
+macro_rules! log_warn {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::warn!(target: "win32job", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format!($($arg)+);
+        }
+    };
+}
+
+/// Implement `PartialEq`, `Eq` and `Hash` for a wrapper of a raw information struct,
+/// by comparing the configuration fields returned by `$key`.
+macro_rules! impl_eq_hash_by_key {
+    ($ty:ty, |$info:ident| $key:expr) => {
+        impl $ty {
+            fn eq_key(&self) -> impl Eq + std::hash::Hash {
+                let $info = &self.0;
+                $key
+            }
+        }
+
+        impl PartialEq for $ty {
+            fn eq(&self, other: &Self) -> bool {
+                self.eq_key() == other.eq_key()
+            }
+        }
+
+        impl Eq for $ty {}
+
+        impl std::hash::Hash for $ty {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.eq_key().hash(state)
+            }
+        }
+    };
+}
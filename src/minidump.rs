@@ -0,0 +1,336 @@
+use std::{collections::HashMap, ffi::OsString, io, path::PathBuf};
+
+#[cfg(windows)]
+use std::{ffi::OsStr, iter, os::windows::ffi::OsStrExt};
+
+use windows::Win32::System::Diagnostics::Debug::{
+    MiniDumpNormal, MiniDumpWithDataSegs, MiniDumpWithFullMemory, MiniDumpWithHandleData,
+    MINIDUMP_TYPE,
+};
+#[cfg(windows)]
+use windows::{
+    core::PCWSTR,
+    Win32::System::Registry::{
+        RegCloseKey, RegCreateKeyExW, RegDeleteKeyW, RegSetValueExW, HKEY, HKEY_LOCAL_MACHINE,
+        KEY_SET_VALUE, REG_CREATED_NEW_KEY, REG_CREATE_KEY_DISPOSITION, REG_DWORD, REG_EXPAND_SZ,
+        REG_OPTION_NON_VOLATILE,
+    },
+};
+
+/// The registry key under which Windows Error Reporting reads the local dump settings
+/// of each image.
+#[cfg(windows)]
+const LOCAL_DUMPS_KEY: &str = r"SOFTWARE\Microsoft\Windows\Windows Error Reporting\LocalDumps";
+
+/// The `DumpType` which makes WER use the `CustomDumpFlags` value.
+#[cfg(windows)]
+const CUSTOM_DUMP_TYPE: u32 = 0;
+
+/// How much of the memory and state of a process to include in a minidump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MinidumpType {
+    /// Only the stacks of the threads and the list of loaded modules (`MiniDumpNormal`).
+    #[default]
+    Normal,
+    /// Also include the data sections of the loaded modules (`MiniDumpWithDataSegs`).
+    WithDataSegments,
+    /// Also include the handles of the process (`MiniDumpWithHandleData`).
+    WithHandleData,
+    /// Include all the accessible memory of the process (`MiniDumpWithFullMemory`).
+    WithFullMemory,
+}
+
+impl MinidumpType {
+    pub(crate) fn as_raw(self) -> MINIDUMP_TYPE {
+        match self {
+            MinidumpType::Normal => MiniDumpNormal,
+            MinidumpType::WithDataSegments => MiniDumpWithDataSegs,
+            MinidumpType::WithHandleData => MiniDumpWithHandleData,
+            MinidumpType::WithFullMemory => MiniDumpWithFullMemory,
+        }
+    }
+}
+
+/// Where, and which kind of, minidumps a `JobMonitor` writes.
+#[derive(Debug, Clone)]
+pub(crate) struct MinidumpConfig {
+    pub(crate) directory: PathBuf,
+    pub(crate) dump_type: MinidumpType,
+}
+
+/// The WER local dump settings registered by a `JobMonitor`, and the images of the processes
+/// it tracks, so the dump of a crashed process can be found.
+#[derive(Debug)]
+pub(crate) struct LocalDumps {
+    config: MinidumpConfig,
+    /// The images which were registered, and whether their key was created by us
+    /// (so it is deleted when the monitor is dropped).
+    registered: HashMap<OsString, bool>,
+    images: HashMap<u32, OsString>,
+}
+
+impl LocalDumps {
+    pub(crate) fn new(config: MinidumpConfig) -> Self {
+        Self {
+            config,
+            registered: HashMap::new(),
+            images: HashMap::new(),
+        }
+    }
+
+    /// Have WER write a dump of the process `pid` (running `image_name`, like `app.exe`)
+    /// if it crashes.
+    pub(crate) fn track(&mut self, pid: u32, image_name: OsString) {
+        if !self.registered.contains_key(&image_name) {
+            let created = match register_local_dumps(&image_name, &self.config) {
+                Ok(created) => created,
+                Err(err) => {
+                    log_warn!(
+                        "Failed to register local dumps for {:?}: {}",
+                        image_name,
+                        err
+                    );
+                    false
+                }
+            };
+
+            self.registered.insert(image_name.clone(), created);
+        }
+
+        self.images.insert(pid, image_name);
+    }
+
+    /// Return the path of the dump which WER wrote when the process `pid` crashed, if any.
+    pub(crate) fn crashed(&mut self, pid: u32) -> Option<PathBuf> {
+        let mut name = self.images.remove(&pid)?;
+        name.push(format!(".{}.dmp", pid));
+
+        let path = self.config.directory.join(name);
+
+        path.is_file().then_some(path)
+    }
+
+    /// Forget the process `pid`, which exited normally.
+    pub(crate) fn exited(&mut self, pid: u32) {
+        self.images.remove(&pid);
+    }
+}
+
+impl Drop for LocalDumps {
+    fn drop(&mut self) {
+        for (image_name, created) in &self.registered {
+            if !created {
+                continue;
+            }
+
+            if let Err(err) = unregister_local_dumps(image_name) {
+                log_warn!(
+                    "Failed to unregister local dumps for {:?}: {}",
+                    image_name,
+                    err
+                );
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+fn local_dumps_key(image_name: &OsStr) -> Vec<u16> {
+    OsStr::new(LOCAL_DUMPS_KEY)
+        .encode_wide()
+        .chain(iter::once(b'\\' as u16))
+        .chain(image_name.encode_wide())
+        .chain(iter::once(0))
+        .collect()
+}
+
+/// Have WER write a dump of processes running `image_name` to the directory of `config` when
+/// they crash. This is done while the process is still intact, unlike a dump written
+/// once the process exited.
+///
+/// Returns `false` (and changes nothing) if the image already has local dump settings,
+/// which are left unchanged. Requires administrator rights.
+#[cfg(windows)]
+pub(crate) fn register_local_dumps(
+    image_name: &OsStr,
+    config: &MinidumpConfig,
+) -> Result<bool, io::Error> {
+    let directory = std::path::absolute(&config.directory)?;
+    std::fs::create_dir_all(&directory)?;
+
+    let path = local_dumps_key(image_name);
+    let mut key = HKEY::default();
+    let mut disposition = REG_CREATE_KEY_DISPOSITION::default();
+
+    unsafe {
+        RegCreateKeyExW(
+            HKEY_LOCAL_MACHINE,
+            PCWSTR(path.as_ptr()),
+            0,
+            PCWSTR::null(),
+            REG_OPTION_NON_VOLATILE,
+            KEY_SET_VALUE,
+            None,
+            &mut key,
+            Some(&mut disposition),
+        )
+    }?;
+
+    if disposition != REG_CREATED_NEW_KEY {
+        let _ = unsafe { RegCloseKey(key) };
+
+        return Ok(false);
+    }
+
+    let folder: Vec<u8> = directory
+        .as_os_str()
+        .encode_wide()
+        .chain(iter::once(0))
+        .flat_map(u16::to_le_bytes)
+        .collect();
+
+    let res = set_value(key, "DumpFolder", REG_EXPAND_SZ, &folder)
+        .and_then(|_| set_value(key, "DumpType", REG_DWORD, &CUSTOM_DUMP_TYPE.to_le_bytes()))
+        .and_then(|_| {
+            let flags = config.dump_type.as_raw().0 as u32;
+
+            set_value(key, "CustomDumpFlags", REG_DWORD, &flags.to_le_bytes())
+        });
+
+    let _ = unsafe { RegCloseKey(key) };
+
+    if let Err(err) = res {
+        let _ = unregister_local_dumps(image_name);
+
+        return Err(err);
+    }
+
+    Ok(true)
+}
+
+#[cfg(not(windows))]
+pub(crate) fn register_local_dumps(
+    _image_name: &std::ffi::OsStr,
+    _config: &MinidumpConfig,
+) -> Result<bool, io::Error> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(windows)]
+fn set_value(
+    key: HKEY,
+    name: &str,
+    ty: windows::Win32::System::Registry::REG_VALUE_TYPE,
+    data: &[u8],
+) -> Result<(), io::Error> {
+    let name: Vec<u16> = name.encode_utf16().chain(iter::once(0)).collect();
+
+    unsafe { RegSetValueExW(key, PCWSTR(name.as_ptr()), 0, ty, Some(data)) }?;
+
+    Ok(())
+}
+
+/// Remove the local dump settings of `image_name`.
+#[cfg(windows)]
+pub(crate) fn unregister_local_dumps(image_name: &OsStr) -> Result<(), io::Error> {
+    let path = local_dumps_key(image_name);
+
+    unsafe { RegDeleteKeyW(HKEY_LOCAL_MACHINE, PCWSTR(path.as_ptr())) }?;
+
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub(crate) fn unregister_local_dumps(_image_name: &std::ffi::OsStr) -> Result<(), io::Error> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use std::{env, io, ptr, thread, time::Duration};
+
+    use super::{register_local_dumps, unregister_local_dumps, MinidumpConfig, MinidumpType};
+    use crate::{Job, JobCommand, JobMonitor};
+
+    /// The environment variable which makes `crash_helper` crash.
+    const CRASH_ENV: &str = "WIN32JOB_TEST_CRASH";
+
+    /// The exit code of a process which crashed with an access violation.
+    const ACCESS_VIOLATION: i32 = 0xC0000005u32 as i32;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn WerSetFlags(flags: u32) -> i32;
+    }
+
+    /// `WER_FAULT_REPORTING_NO_UI`.
+    const NO_UI: u32 = 0x20;
+
+    #[test]
+    fn crash_helper() {
+        if env::var_os(CRASH_ENV).is_some() {
+            unsafe {
+                WerSetFlags(NO_UI);
+                ptr::null_mut::<u32>().write_volatile(0);
+            }
+        }
+    }
+
+    #[test]
+    fn writes_minidump_of_crashed_process() {
+        let exe = env::current_exe().expect("the path of the test executable");
+        let image_name = exe.file_name().unwrap().to_owned();
+
+        let directory = env::temp_dir().join(format!("win32job-dumps-{}", std::process::id()));
+        let config = MinidumpConfig {
+            directory: directory.clone(),
+            dump_type: MinidumpType::Normal,
+        };
+
+        // Register up front, so the helper can't crash before the monitor registers its image.
+        match register_local_dumps(&image_name, &config) {
+            Ok(true) => {}
+            Ok(false) => panic!("{:?} already has local dump settings", image_name),
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                eprintln!("Skipping: registering local dumps requires administrator rights");
+                return;
+            }
+            Err(err) => panic!("failed to register local dumps: {}", err),
+        }
+
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::builder()
+            .write_minidumps(&directory, MinidumpType::Normal)
+            .spawn(&job)
+            .unwrap();
+
+        let mut child = JobCommand::new(&job, &exe)
+            .args(["--exact", "minidump::tests::crash_helper", "--nocapture"])
+            .env(CRASH_ENV, "1")
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        let status = child.wait().unwrap();
+
+        let mut path = monitor.minidump_path(pid);
+        for _ in 0..100 {
+            if path.is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(50));
+            path = monitor.minidump_path(pid);
+        }
+
+        unregister_local_dumps(&image_name).unwrap();
+
+        assert_eq!(status.code(), Some(ACCESS_VIOLATION));
+
+        let path = path.expect("a minidump of the crashed process");
+        assert!(path.starts_with(&directory));
+        assert!(std::fs::metadata(&path).unwrap().len() > 0);
+
+        drop(monitor);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
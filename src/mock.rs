@@ -0,0 +1,235 @@
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use windows::Win32::System::JobObjects::JOBOBJECT_BASIC_ACCOUNTING_INFORMATION;
+
+use crate::{BasicAccountingInfo, ExtendedLimitInfo, JobError, JobOps, Pid};
+
+/// An in-memory implementation of `JobOps`, which records the operations done on it
+/// instead of using a real job object.
+///
+/// The processes of a `MockJob` are only ids: assigning a process adds its id
+/// (for `assign_process`, the handle itself is used as the id), and terminating
+/// the job or killing a process removes it. Failures can be simulated using `fail_next`.
+///
+/// ```edition2021
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let job = MockJob::new();
+/// job.assign_process_by_pid(Pid::new(100))?;
+/// job.assign_process_by_pid(Pid::new(200))?;
+///
+/// job.kill_process(Pid::new(100), 1)?;
+/// assert_eq!(job.query_process_id_list()?, [Pid::new(200)]);
+///
+/// job.terminate(3)?;
+/// assert!(job.is_empty()?);
+/// assert_eq!(job.terminations(), [3]);
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct MockJob {
+    state: Mutex<MockState>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    processes: Vec<Pid>,
+    total_processes: u32,
+    limits: Option<ExtendedLimitInfo>,
+    terminations: Vec<u32>,
+    failures: VecDeque<JobError>,
+}
+
+impl MockJob {
+    /// Create a new, empty mock job.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the next operation on the job fail with `error`. Multiple failures
+    /// are returned by the next operations, in the order they were added.
+    pub fn fail_next(&self, error: JobError) -> &Self {
+        self.state().failures.push_back(error);
+
+        self
+    }
+
+    /// Simulate the exit of the process `pid`, removing it from the job.
+    /// Return `false` if the process is not in the job.
+    pub fn exit_process(&self, pid: impl Into<Pid>) -> bool {
+        let pid = pid.into();
+        let mut state = self.state();
+        let count = state.processes.len();
+
+        state.processes.retain(|&id| id != pid);
+
+        state.processes.len() != count
+    }
+
+    /// Return the exit codes of all the calls to `terminate`, in order.
+    pub fn terminations(&self) -> Vec<u32> {
+        self.state().terminations.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Return the state of the job, or the next simulated failure.
+    fn checked_state(&self) -> Result<MutexGuard<'_, MockState>, JobError> {
+        let mut state = self.state();
+
+        match state.failures.pop_front() {
+            Some(err) => Err(err),
+            None => Ok(state),
+        }
+    }
+
+    fn assign(&self, pid: Pid) -> Result<(), JobError> {
+        let mut state = self.checked_state()?;
+
+        if !state.processes.contains(&pid) {
+            state.processes.push(pid);
+            state.total_processes += 1;
+        }
+
+        Ok(())
+    }
+}
+
+impl JobOps for MockJob {
+    fn assign_process(&self, proc_handle: isize) -> Result<(), JobError> {
+        self.assign(Pid::new(proc_handle as u32))
+    }
+
+    fn assign_process_by_pid(&self, pid: Pid) -> Result<(), JobError> {
+        self.assign(pid)
+    }
+
+    fn terminate(&self, exit_code: u32) -> Result<(), JobError> {
+        let mut state = self.checked_state()?;
+
+        state.processes.clear();
+        state.terminations.push(exit_code);
+
+        Ok(())
+    }
+
+    fn kill_process(&self, pid: Pid, _exit_code: u32) -> Result<(), JobError> {
+        let mut state = self.checked_state()?;
+        let count = state.processes.len();
+
+        state.processes.retain(|&id| id != pid);
+
+        if state.processes.len() == count {
+            return Err(JobError::ProcessNotInJob(pid));
+        }
+
+        Ok(())
+    }
+
+    fn query_extended_limit_info(&self) -> Result<ExtendedLimitInfo, JobError> {
+        let state = self.checked_state()?;
+
+        Ok(state.limits.clone().unwrap_or_default())
+    }
+
+    fn set_extended_limit_info(&self, info: &ExtendedLimitInfo) -> Result<(), JobError> {
+        self.checked_state()?.limits = Some(info.clone());
+
+        Ok(())
+    }
+
+    fn query_basic_accounting_info(&self) -> Result<BasicAccountingInfo, JobError> {
+        let state = self.checked_state()?;
+
+        Ok(BasicAccountingInfo(
+            JOBOBJECT_BASIC_ACCOUNTING_INFORMATION {
+                TotalProcesses: state.total_processes,
+                ActiveProcesses: state.processes.len() as u32,
+                ..Default::default()
+            },
+        ))
+    }
+
+    fn query_process_id_list(&self) -> Result<Vec<Pid>, JobError> {
+        let state = self.checked_state()?;
+
+        Ok(state.processes.clone())
+    }
+
+    /// Return immediately, since the processes of a mock job only exit when told to
+    /// (using `exit_process`, `kill_process` or `terminate`).
+    fn wait_until_empty(&self, _timeout: Option<Duration>) -> Result<bool, JobError> {
+        let state = self.checked_state()?;
+
+        Ok(state.processes.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ExtendedLimitInfo, JobError, JobOps, MockJob, Pid};
+
+    #[test]
+    fn records_operations() {
+        let job = MockJob::new();
+
+        job.assign_process_by_pid(Pid::new(10)).unwrap();
+        job.assign_process(20).unwrap();
+        job.assign_process_by_pid(Pid::new(10)).unwrap();
+
+        assert_eq!(
+            job.query_process_id_list().unwrap(),
+            [Pid::new(10), Pid::new(20)]
+        );
+        assert_eq!(
+            job.query_basic_accounting_info().unwrap().total_processes(),
+            2
+        );
+
+        assert!(job.exit_process(10));
+        assert!(!job.exit_process(10));
+        assert!(matches!(
+            job.kill_process(Pid::new(10), 1),
+            Err(JobError::ProcessNotInJob(pid)) if pid == Pid::new(10)
+        ));
+        assert!(!job.wait_until_empty(None).unwrap());
+
+        job.terminate(7).unwrap();
+        assert!(job.is_empty().unwrap());
+        assert_eq!(job.terminations(), [7]);
+
+        let mut info = ExtendedLimitInfo::new();
+        info.limit_active_processes(3);
+        job.set_extended_limit_info(&info).unwrap();
+        assert_eq!(
+            job.query_extended_limit_info()
+                .unwrap()
+                .0
+                .BasicLimitInformation
+                .ActiveProcessLimit,
+            3
+        );
+    }
+
+    #[test]
+    fn simulates_failures() {
+        let job = MockJob::new();
+
+        job.fail_next(JobError::ProcessExited);
+
+        assert!(matches!(
+            job.assign_process_by_pid(Pid::new(1)),
+            Err(JobError::ProcessExited)
+        ));
+        job.assign_process_by_pid(Pid::new(1)).unwrap();
+        assert_eq!(job.query_process_id_list().unwrap(), [Pid::new(1)]);
+    }
+}
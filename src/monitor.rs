@@ -0,0 +1,1230 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    ffi::OsString,
+    fmt,
+    future::Future,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    pin::Pin,
+    process,
+    sync::{mpsc, Arc, Condvar, Mutex, PoisonError},
+    task::{Context, Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+use windows::Win32::System::Threading::{
+    GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+
+use crate::bounded;
+use crate::handle::OwnedHandle;
+#[cfg(feature = "minidump")]
+use crate::minidump::{LocalDumps, MinidumpConfig, MinidumpType};
+use crate::{
+    BoundedReceiver, CompletionPort, Job, JobError, JobNotification, NotificationFilter,
+    OverflowPolicy, Pid, ProcessChurn, ProcessIdentity, TimedNotification,
+};
+
+/// The completion key used for the packets of the job.
+const JOB_KEY: usize = 1;
+
+/// The completion key used to wake the monitor thread when the monitor is shut down.
+const WAKE_KEY: usize = 2;
+
+/// How long dropping a monitor waits for the monitor thread to stop.
+const DROP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The default name of the monitor thread.
+const DEFAULT_THREAD_NAME: &str = "win32job-monitor";
+
+/// What the monitor thread does when a notification callback panics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Log the panic and abort the process.
+    Abort,
+    /// Log the panic and keep delivering notifications (including to the callback which panicked).
+    LogAndContinue,
+    /// Stop the monitor thread, and return the panic message from `monitor.shutdown`
+    /// as `JobError::MonitorPanicked`.
+    #[default]
+    Forward,
+}
+
+/// The exit status of a process which was a member of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessExit {
+    /// The exit code of the process, or `None` if the process exited before
+    /// the monitor could open it.
+    pub exit_code: Option<u32>,
+    /// Whether the process exited due to an unhandled exception.
+    pub abnormal: bool,
+    /// The identity of the process, or `None` if the process exited before
+    /// the monitor could open it.
+    pub identity: Option<ProcessIdentity>,
+}
+
+/// The image path and command line of a process, captured by the monitor
+/// (see `JobMonitorBuilder::capture_process_details`) when the process joined the job.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessDetails {
+    /// The full path of the executable of the process,
+    /// or `None` if it could not be queried.
+    pub image_path: Option<PathBuf>,
+    /// The command line of the process, or `None` if it could not be queried
+    /// (for example, for protected processes, or if the process exited too quickly).
+    pub command_line: Option<OsString>,
+}
+
+/// Watches the notifications of a job on a background thread.
+///
+/// The monitor keeps track of whether the job has any active processes,
+/// and of how each of the job's processes exited.
+///
+/// Note that a job can only be associated with a single completion port,
+/// so only one monitor can be spawned for each job.
+///
+/// ```edition2021
+/// use std::{process::Command, time::Duration};
+/// use win32job::*;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// let job = Job::create()?;
+/// let monitor = JobMonitor::spawn(&job)?;
+///
+/// let child = Command::new("cmd").args(["/C", "exit 3"]).spawn()?;
+/// job.assign_process_by_pid(child.id()).ok();
+///
+/// if monitor.wait_until_empty(Some(Duration::from_secs(10))) {
+///     for (pid, exit) in monitor.exit_report().unwrap() {
+///         println!("{} exited with {:?}", pid, exit.exit_code);
+///     }
+/// }
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct JobMonitor {
+    shared: Arc<Shared>,
+    port: Arc<CompletionPort>,
+    thread: Option<thread::JoinHandle<Option<String>>>,
+}
+
+/// Configures and spawns a `JobMonitor`.
+///
+/// ```edition2021
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let job = Job::create()?;
+/// let monitor = JobMonitor::builder()
+///     .thread_name("build-jobs-monitor")
+///     .panic_policy(PanicPolicy::LogAndContinue)
+///     .spawn(&job)?;
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct JobMonitorBuilder {
+    thread_name: String,
+    panic_policy: PanicPolicy,
+    capture_process_details: bool,
+    abort_on_failure: bool,
+    #[cfg(feature = "minidump")]
+    minidumps: Option<MinidumpConfig>,
+}
+
+impl Default for JobMonitorBuilder {
+    fn default() -> Self {
+        Self {
+            thread_name: DEFAULT_THREAD_NAME.to_string(),
+            panic_policy: PanicPolicy::default(),
+            capture_process_details: false,
+            abort_on_failure: false,
+            #[cfg(feature = "minidump")]
+            minidumps: None,
+        }
+    }
+}
+
+impl JobMonitorBuilder {
+    /// Create a new builder, with the default thread name (`win32job-monitor`)
+    /// and panic policy (`PanicPolicy::Forward`).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the name of the monitor thread.
+    pub fn thread_name<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.thread_name = name.into();
+
+        self
+    }
+
+    /// Set what happens when a notification callback panics.
+    pub fn panic_policy(&mut self, policy: PanicPolicy) -> &mut Self {
+        self.panic_policy = policy;
+
+        self
+    }
+
+    /// Capture the image path and command line of each process of the job as soon as
+    /// it joins the job, so they are available from `monitor.process_details`
+    /// even after it exits. Disabled by default.
+    pub fn capture_process_details(&mut self, capture: bool) -> &mut Self {
+        self.capture_process_details = capture;
+
+        self
+    }
+
+    /// Terminate the job as soon as any of its processes exits with a nonzero exit code
+    /// (or abnormally), so the whole process tree fails fast. The other processes
+    /// are terminated with the same exit code, and the process which caused the abort
+    /// is available from `monitor.abort_cause`. Disabled by default.
+    pub fn abort_on_failure(&mut self, abort: bool) -> &mut Self {
+        self.abort_on_failure = abort;
+
+        self
+    }
+
+    /// Write a minidump of each process which crashes to `directory`, named
+    /// `<image name>.<pid>.dmp`, so crashes can be triaged without a debugger attached.
+    /// The paths of the written dumps are available from `monitor.minidump_path`.
+    ///
+    /// The dumps are written by Windows Error Reporting at the time of the crash (an exit
+    /// notification only arrives once the process is gone): when a process joins the job,
+    /// the monitor registers WER [local dumps](https://learn.microsoft.com/en-us/windows/win32/wer/collecting-user-mode-dumps)
+    /// for its image, and removes the settings it registered when it is dropped.
+    /// This requires administrator rights, and applies to every process running the image
+    /// on the machine while the monitor runs. Images which already have local dump settings
+    /// are left unchanged. A process which crashes before the monitor handles its
+    /// `NewProcess` notification is not dumped.
+    #[cfg(feature = "minidump")]
+    pub fn write_minidumps<P: Into<PathBuf>>(
+        &mut self,
+        directory: P,
+        dump_type: MinidumpType,
+    ) -> &mut Self {
+        self.minidumps = Some(MinidumpConfig {
+            directory: directory.into(),
+            dump_type,
+        });
+
+        self
+    }
+
+    /// Associate `job` with a new completion port, and start watching its notifications.
+    pub fn spawn(&self, job: &Job) -> Result<JobMonitor, JobError> {
+        JobMonitor::spawn_with(job, self)
+    }
+}
+
+/// Return the message of a panic payload.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+    subscribers: Mutex<Vec<Subscriber>>,
+    stopped: Mutex<bool>,
+    stopped_changed: Condvar,
+}
+
+/// Marks the monitor thread as stopped when dropped, even if the thread panics,
+/// and wakes up everyone waiting for the job to become empty (which it will never observe).
+struct StoppedGuard(Arc<Shared>);
+
+impl Drop for StoppedGuard {
+    fn drop(&mut self) {
+        *self
+            .0
+            .stopped
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = true;
+        self.0.stopped_changed.notify_all();
+
+        let mut state = self.0.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.stopped = true;
+        for (_, waker) in state.wakers.drain() {
+            waker.wake();
+        }
+        drop(state);
+
+        self.0.changed.notify_all();
+    }
+}
+
+type Callback = Box<dyn FnMut(JobNotification) + Send>;
+
+enum Sink {
+    Channel(mpsc::Sender<JobNotification>),
+    Timed(mpsc::Sender<TimedNotification>),
+    Bounded(bounded::Sender),
+    Callback(Callback),
+}
+
+struct Subscriber {
+    filter: NotificationFilter,
+    sink: Sink,
+}
+
+impl fmt::Debug for Subscriber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscriber")
+            .field("filter", &self.filter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Subscriber {
+    /// Deliver `notification` if it matches the filter.
+    /// Return `false` if the subscriber is gone, and should be removed.
+    fn deliver(&mut self, timed: TimedNotification) -> bool {
+        let notification = timed.notification;
+
+        if !self.filter.matches(&notification) {
+            return true;
+        }
+
+        match &mut self.sink {
+            Sink::Channel(sender) => sender.send(notification).is_ok(),
+            Sink::Timed(sender) => sender.send(timed).is_ok(),
+            Sink::Bounded(sender) => sender.send(notification),
+            Sink::Callback(callback) => {
+                callback(notification);
+                true
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TrackedProcess {
+    handle: OwnedHandle,
+    identity: Option<ProcessIdentity>,
+}
+
+#[derive(Debug)]
+struct State {
+    empty: bool,
+    churn: ProcessChurn,
+    processes: HashMap<Pid, TrackedProcess>,
+    exits: HashMap<Pid, ProcessExit>,
+    capture_details: bool,
+    details: HashMap<Pid, ProcessDetails>,
+    /// A handle to the job, if it should be terminated when a process fails.
+    abort_job: Option<Job>,
+    abort_cause: Option<(Pid, ProcessExit)>,
+    #[cfg(feature = "minidump")]
+    local_dumps: Option<LocalDumps>,
+    #[cfg(feature = "minidump")]
+    dumps: HashMap<Pid, PathBuf>,
+    wakers: HashMap<u64, Waker>,
+    next_waker_id: u64,
+    /// Whether the monitor thread stopped, so the state is no longer updated.
+    stopped: bool,
+}
+
+/// Query the details of an open process. Each detail is queried separately,
+/// so failing to get the command line (for example) still keeps the image path.
+#[cfg(windows)]
+fn capture_details(handle: &OwnedHandle) -> ProcessDetails {
+    ProcessDetails {
+        image_path: crate::process::image_path(handle.0).ok(),
+        command_line: crate::process::command_line(handle.0).ok(),
+    }
+}
+
+#[cfg(not(windows))]
+fn capture_details(_handle: &OwnedHandle) -> ProcessDetails {
+    ProcessDetails::default()
+}
+
+impl State {
+    fn track_process(&mut self, pid: Pid) {
+        if self.processes.contains_key(&pid) {
+            return;
+        }
+
+        // Keep a handle to the process, so its exit code is available after it exits.
+        match unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid.as_u32()) } {
+            Ok(handle) => {
+                let handle = OwnedHandle(handle);
+                // The identity is taken while the handle is open, so it can't be of a reused pid.
+                let identity = ProcessIdentity::of_handle(handle.0, pid.as_u32()).ok();
+
+                if self.capture_details {
+                    self.details.insert(pid, capture_details(&handle));
+                }
+
+                #[cfg(all(windows, feature = "minidump"))]
+                if let Some(local_dumps) = &mut self.local_dumps {
+                    if let Some(image_name) = crate::process::image_path(handle.0)
+                        .ok()
+                        .and_then(|path| path.file_name().map(ToOwned::to_owned))
+                    {
+                        local_dumps.track(pid.as_u32(), image_name);
+                    }
+                }
+
+                self.processes
+                    .insert(pid, TrackedProcess { handle, identity });
+            }
+            Err(err) => {
+                log_debug!("Failed to open job process {}: {}", pid, err);
+            }
+        }
+    }
+
+    fn process_exited(&mut self, pid: Pid, abnormal: bool) {
+        self.churn.exited += 1;
+        if abnormal {
+            self.churn.abnormal += 1;
+        }
+
+        let process = self.processes.remove(&pid);
+
+        #[cfg(feature = "minidump")]
+        if let Some(local_dumps) = &mut self.local_dumps {
+            match abnormal {
+                true => match local_dumps.crashed(pid.as_u32()) {
+                    Some(path) => {
+                        self.dumps.insert(pid, path);
+                    }
+                    None => {
+                        log_warn!("No minidump was written for process {}", pid);
+                    }
+                },
+                false => local_dumps.exited(pid.as_u32()),
+            }
+        }
+
+        let exit_code = process.as_ref().and_then(|process| {
+            let mut exit_code = 0;
+
+            unsafe { GetExitCodeProcess(process.handle.0, &mut exit_code) }
+                .ok()
+                .map(|_| exit_code)
+        });
+
+        let exit = ProcessExit {
+            exit_code,
+            abnormal,
+            identity: process.and_then(|process| process.identity),
+        };
+        self.exits.insert(pid, exit);
+
+        let failed = abnormal || exit_code.is_some_and(|exit_code| exit_code != 0);
+        if failed && self.abort_cause.is_none() {
+            self.abort(pid, exit);
+        }
+    }
+
+    /// Terminate the job (if `abort_on_failure` is enabled), because `pid` failed.
+    fn abort(&mut self, pid: Pid, exit: ProcessExit) {
+        let Some(job) = &self.abort_job else {
+            return;
+        };
+
+        log_warn!(
+            "Process {} failed with exit code {:?}, terminating the job",
+            pid,
+            exit.exit_code
+        );
+
+        // The processes terminated by the abort will also fail, but only the first one is the cause.
+        self.abort_cause = Some((pid, exit));
+
+        if let Err(err) = job.terminate(exit.exit_code.unwrap_or(1)) {
+            log_warn!("Failed to terminate the job: {}", err);
+        }
+    }
+
+    /// Return the identity of the tracked (or exited) process `pid`.
+    fn identity(&self, pid: Pid) -> Option<ProcessIdentity> {
+        match self.processes.get(&pid) {
+            Some(process) => process.identity,
+            None => self.exits.get(&pid).and_then(|exit| exit.identity),
+        }
+    }
+
+    fn handle(&mut self, notification: JobNotification) {
+        match notification {
+            JobNotification::NewProcess(pid) => {
+                self.empty = false;
+
+                // Processes assigned while the monitor started are both in the accounting totals
+                // (and tracked), and reported by a notification.
+                if !self.processes.contains_key(&pid) {
+                    self.churn.created += 1;
+                }
+                self.track_process(pid);
+            }
+            // The system terminates processes which exceed their time limit.
+            JobNotification::EndOfProcessTime(_) => self.churn.killed += 1,
+            JobNotification::ExitProcess(pid) => self.process_exited(pid, false),
+            JobNotification::AbnormalExitProcess(pid) => self.process_exited(pid, true),
+            JobNotification::ActiveProcessZero => {
+                self.empty = true;
+
+                for (_, waker) in self.wakers.drain() {
+                    waker.wake();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl JobMonitor {
+    /// Associate `job` with a new completion port, and start watching its notifications.
+    /// See `JobMonitor::builder` for naming the monitor thread and handling panics.
+    pub fn spawn(job: &Job) -> Result<Self, JobError> {
+        JobMonitorBuilder::new().spawn(job)
+    }
+
+    /// Return a builder for configuring the monitor thread.
+    pub fn builder() -> JobMonitorBuilder {
+        JobMonitorBuilder::new()
+    }
+
+    fn spawn_with(job: &Job, builder: &JobMonitorBuilder) -> Result<Self, JobError> {
+        let port = Arc::new(CompletionPort::create()?);
+        job.associate_completion_port(&port, JOB_KEY)?;
+
+        // Processes which were assigned before the association don't generate
+        // a `NewProcess` notification, so start tracking them here.
+        let info = job.query_basic_accounting_info()?;
+        let mut state = State {
+            empty: info.active_processes() == 0,
+            // Start from the accounting totals, so the counters cover the lifetime of the job.
+            churn: ProcessChurn::from_accounting(&info),
+            processes: HashMap::new(),
+            exits: HashMap::new(),
+            capture_details: builder.capture_process_details,
+            details: HashMap::new(),
+            abort_job: match builder.abort_on_failure {
+                true => Some(job.try_clone()?),
+                false => None,
+            },
+            abort_cause: None,
+            #[cfg(feature = "minidump")]
+            local_dumps: builder.minidumps.clone().map(LocalDumps::new),
+            #[cfg(feature = "minidump")]
+            dumps: HashMap::new(),
+            wakers: HashMap::new(),
+            next_waker_id: 0,
+            stopped: false,
+        };
+
+        for pid in job.query_process_id_list()? {
+            state.track_process(pid);
+        }
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(state),
+            changed: Condvar::new(),
+            subscribers: Mutex::new(vec![]),
+            stopped: Mutex::new(false),
+            stopped_changed: Condvar::new(),
+        });
+
+        let thread_shared = shared.clone();
+        let thread_port = port.clone();
+        let panic_policy = builder.panic_policy;
+        #[cfg(feature = "tracing")]
+        let job_handle = job.handle.0;
+
+        let thread = thread::Builder::new()
+            .name(builder.thread_name.clone())
+            .spawn(move || {
+                let _stopped = StoppedGuard(thread_shared.clone());
+                #[cfg(feature = "tracing")]
+                let _span = tracing::debug_span!("job_monitor", job = job_handle).entered();
+
+                loop {
+                    let mut timed = match thread_port.get_timed(None) {
+                        Ok(Some((JOB_KEY, timed))) => timed,
+                        Ok(_) => break,
+                        Err(err) => {
+                            #[cfg(feature = "tracing")]
+                            tracing::warn!(error = %err, "failed to dequeue job notification");
+                            log_warn!("Failed to dequeue job notification: {}", err);
+                            break;
+                        }
+                    };
+
+                    let notification = timed.notification;
+                    log_debug!("Job notification: {:?}", notification);
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        kind = notification.name(),
+                        message = notification.message(),
+                        pid = notification.pid().map(|pid| pid.as_u32()),
+                        "job notification"
+                    );
+                    #[cfg(feature = "tracing")]
+                    let _dispatch =
+                        tracing::debug_span!("dispatch", kind = notification.name()).entered();
+
+                    let mut state = thread_shared.state.lock().unwrap();
+                    state.handle(notification);
+                    timed.process = notification
+                        .pid()
+                        .and_then(|pid| state.identity(pid));
+                    drop(state);
+
+                    thread_shared.changed.notify_all();
+
+                    let mut panic_payload = None;
+
+                    // Callbacks may add subscribers, so they run without holding the lock.
+                    let mut subscribers =
+                        mem::take(&mut *thread_shared.subscribers.lock().unwrap());
+
+                    subscribers.retain_mut(|subscriber| {
+                        // Callbacks run on this thread, so their panics must not unwind it
+                        // (and drop the subscribers) before the policy is applied.
+                        match panic::catch_unwind(AssertUnwindSafe(|| subscriber.deliver(timed))) {
+                            Ok(keep) => keep,
+                            Err(payload) => {
+                                panic_payload.get_or_insert(payload);
+                                true
+                            }
+                        }
+                    });
+
+                    // Keep the subscribers which were added while delivering.
+                    let mut current = thread_shared.subscribers.lock().unwrap();
+                    subscribers.append(&mut current);
+                    *current = subscribers;
+                    drop(current);
+
+                    if let Some(payload) = panic_payload {
+                        let message = panic_message(payload.as_ref());
+
+                        match panic_policy {
+                            PanicPolicy::Abort => {
+                                #[cfg(feature = "tracing")]
+                                tracing::error!(%message, "job notification callback panicked, aborting");
+                                log_warn!(
+                                    "Job notification callback panicked, aborting: {}",
+                                    message
+                                );
+                                process::abort();
+                            }
+                            PanicPolicy::LogAndContinue => {
+                                #[cfg(feature = "tracing")]
+                                tracing::warn!(%message, "job notification callback panicked");
+                                log_warn!("Job notification callback panicked: {}", message);
+                            }
+                            PanicPolicy::Forward => {
+                                #[cfg(feature = "tracing")]
+                                tracing::warn!(
+                                    %message,
+                                    "job notification callback panicked, stopping the monitor"
+                                );
+                                return Some(message);
+                            }
+                        }
+                    }
+                }
+
+                None
+            })
+            .map_err(JobError::MonitorThreadFailed)?;
+
+        Ok(Self {
+            shared,
+            port,
+            thread: Some(thread),
+        })
+    }
+
+    /// Return a channel which receives the notifications of the job matched by `filter`.
+    /// Only notifications which arrive after the call are received.
+    pub fn subscribe(&self, filter: NotificationFilter) -> mpsc::Receiver<JobNotification> {
+        let (sender, receiver) = mpsc::channel();
+        self.add_subscriber(filter, Sink::Channel(sender));
+
+        receiver
+    }
+
+    /// Like `subscribe`, but each notification is stamped with the time it was
+    /// dequeued by the monitor thread.
+    pub fn subscribe_timed(&self, filter: NotificationFilter) -> mpsc::Receiver<TimedNotification> {
+        let (sender, receiver) = mpsc::channel();
+        self.add_subscriber(filter, Sink::Timed(sender));
+
+        receiver
+    }
+
+    /// Like `subscribe`, but the channel holds up to `capacity` notifications,
+    /// and `policy` decides what happens when a slow receiver lets it fill up.
+    pub fn subscribe_bounded(
+        &self,
+        filter: NotificationFilter,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> BoundedReceiver {
+        let (sender, receiver) = bounded::channel(capacity, policy);
+        self.add_subscriber(filter, Sink::Bounded(sender));
+
+        receiver
+    }
+
+    /// Call `callback` on the monitor thread for each notification of the job matched by `filter`.
+    /// Only notifications which arrive after the call are passed to the callback.
+    pub fn on_notification<F>(&self, filter: NotificationFilter, callback: F)
+    where
+        F: FnMut(JobNotification) + Send + 'static,
+    {
+        self.add_subscriber(filter, Sink::Callback(Box::new(callback)));
+    }
+
+    fn add_subscriber(&self, filter: NotificationFilter, sink: Sink) {
+        self.shared
+            .subscribers
+            .lock()
+            .unwrap()
+            .push(Subscriber { filter, sink });
+    }
+
+    /// Return `true` if the job has no active processes.
+    pub fn is_empty(&self) -> bool {
+        self.shared.state.lock().unwrap().empty
+    }
+
+    /// Block until the job has no active processes, or until `timeout` elapses.
+    /// Return `true` if the job is empty.
+    ///
+    /// If the monitor thread stops (for example, because a callback panicked), this returns
+    /// right away, since the job can no longer be observed to become empty.
+    pub fn wait_until_empty(&self, timeout: Option<Duration>) -> bool {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = self.shared.state.lock().unwrap();
+
+        while !state.empty && !state.stopped {
+            state = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+
+                    if remaining.is_zero() {
+                        break;
+                    }
+
+                    self.shared
+                        .changed
+                        .wait_timeout(state, remaining)
+                        .unwrap()
+                        .0
+                }
+                None => self.shared.changed.wait(state).unwrap(),
+            };
+        }
+
+        state.empty
+    }
+
+    /// Return a future which resolves once the job has no active processes
+    /// (immediately, if the job is already empty), or once the monitor thread stops
+    /// (use `is_empty` to tell the two apart).
+    /// The future does not depend on a specific async runtime.
+    pub fn until_empty(&self) -> UntilEmpty {
+        UntilEmpty {
+            shared: self.shared.clone(),
+            waker_id: None,
+        }
+    }
+
+    /// Wait until the job has no active processes, or until `timeout` elapses.
+    /// Return `true` if the job is empty. This is the async version of `wait_until_empty`.
+    #[cfg(feature = "tokio")]
+    pub async fn wait_until_empty_async(&self, timeout: Option<Duration>) -> bool {
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.until_empty())
+                .await
+                .is_ok(),
+            None => {
+                self.until_empty().await;
+                true
+            }
+        }
+    }
+
+    /// Return the process churn counters of the job, as seen by the monitor.
+    pub fn process_churn(&self) -> ProcessChurn {
+        ProcessChurn {
+            at: Instant::now(),
+            ..self.shared.state.lock().unwrap().churn
+        }
+    }
+
+    /// Return the path of the minidump which was written when the process `pid` crashed
+    /// (see `JobMonitorBuilder::write_minidumps`).
+    #[cfg(feature = "minidump")]
+    pub fn minidump_path(&self, pid: impl Into<Pid>) -> Option<PathBuf> {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .dumps
+            .get(&pid.into())
+            .cloned()
+    }
+
+    /// Return the details of the process `pid`, which were captured when it joined the job.
+    /// Returns `None` unless the monitor was spawned with `capture_process_details` enabled,
+    /// or if the process could not be opened.
+    pub fn process_details(&self, pid: impl Into<Pid>) -> Option<ProcessDetails> {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .details
+            .get(&pid.into())
+            .cloned()
+    }
+
+    /// Return the pid and exit status of the process which caused the job to be terminated,
+    /// if the monitor was spawned with `abort_on_failure` enabled and a process failed.
+    pub fn abort_cause(&self) -> Option<(Pid, ProcessExit)> {
+        self.shared.state.lock().unwrap().abort_cause
+    }
+
+    /// Return how each process of the job exited, keyed by pid,
+    /// or `None` if the job still has active processes.
+    pub fn exit_report(&self) -> Option<HashMap<Pid, ProcessExit>> {
+        let state = self.shared.state.lock().unwrap();
+
+        if state.empty {
+            Some(state.exits.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl JobMonitor {
+    /// Stop the monitor thread, waiting up to `timeout` for it to finish handling
+    /// the current notification, and return the notifications which were still queued
+    /// (and were not delivered to subscribers).
+    ///
+    /// If a callback panicked (with `PanicPolicy::Forward`), `JobError::MonitorPanicked`
+    /// is returned with the panic message.
+    ///
+    /// If the thread does not stop in time (for example, because a callback blocks),
+    /// `JobError::MonitorShutdownTimedOut` is returned and the thread is left running
+    /// until it handles the stop request.
+    pub fn shutdown(mut self, timeout: Duration) -> Result<Vec<JobNotification>, JobError> {
+        self.stop(timeout)
+    }
+
+    fn stop(&mut self, timeout: Duration) -> Result<Vec<JobNotification>, JobError> {
+        let Some(thread) = self.thread.take() else {
+            return Ok(vec![]);
+        };
+
+        self.port.post(WAKE_KEY)?;
+
+        let stopped = self
+            .shared
+            .stopped
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (stopped, _) = self
+            .shared
+            .stopped_changed
+            .wait_timeout_while(stopped, timeout, |stopped| !*stopped)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if !*stopped {
+            return Err(JobError::MonitorShutdownTimedOut);
+        }
+        drop(stopped);
+
+        let panicked = match thread.join() {
+            Ok(panicked) => panicked,
+            Err(payload) => Some(panic_message(payload.as_ref())),
+        };
+
+        if let Some(message) = panicked {
+            return Err(JobError::MonitorPanicked(message));
+        }
+
+        // The thread stops at the wake packet, so anything after it was never handled.
+        let mut pending = vec![];
+        while let Some((key, notification)) = self.port.get(Some(Duration::ZERO))? {
+            if key == JOB_KEY {
+                pending.push(notification);
+            }
+        }
+
+        Ok(pending)
+    }
+}
+
+/// A future which resolves once a monitored job has no active processes.
+/// See `monitor.until_empty`.
+#[derive(Debug)]
+pub struct UntilEmpty {
+    shared: Arc<Shared>,
+    waker_id: Option<u64>,
+}
+
+impl Future for UntilEmpty {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = &mut *self;
+
+        // The waker is registered while holding the lock, so the monitor thread
+        // can't mark the job as empty between the check and the registration.
+        let mut state = this.shared.state.lock().unwrap();
+
+        if state.empty || state.stopped {
+            if let Some(waker_id) = this.waker_id.take() {
+                state.wakers.remove(&waker_id);
+            }
+
+            return Poll::Ready(());
+        }
+
+        let waker_id = *this.waker_id.get_or_insert_with(|| {
+            state.next_waker_id += 1;
+            state.next_waker_id
+        });
+        state.wakers.insert(waker_id, cx.waker().clone());
+
+        Poll::Pending
+    }
+}
+
+impl Drop for UntilEmpty {
+    fn drop(&mut self) {
+        if let Some(waker_id) = self.waker_id.take() {
+            self.shared.state.lock().unwrap().wakers.remove(&waker_id);
+        }
+    }
+}
+
+impl Drop for JobMonitor {
+    fn drop(&mut self) {
+        match self.stop(DROP_TIMEOUT) {
+            Ok(pending) => {
+                if !pending.is_empty() {
+                    log_debug!("Dropped {} queued job notifications", pending.len());
+                }
+            }
+            // Leave the thread running rather than hang here.
+            Err(err) => {
+                log_warn!("Failed to stop the monitor thread: {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use std::{
+        future::Future,
+        pin::pin,
+        sync::{mpsc, Arc},
+        task::{Context, Poll, Wake},
+        thread::{self, Thread},
+        time::{Duration, Instant},
+    };
+
+    use crate::test_util::DisposableChild;
+    use crate::{
+        Job, JobError, JobMonitor, JobNotification, NotificationFilter, OverflowPolicy, Pid,
+    };
+
+    #[test]
+    fn exit_report() {
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::spawn(&job).unwrap();
+
+        assert!(monitor.is_empty());
+
+        let child = DisposableChild::spawn_exiting_in(&job, 3, Duration::from_secs(1)).unwrap();
+        let pid = Pid::new(child.id());
+        child.wait().unwrap();
+
+        assert!(monitor.wait_until_empty(Some(Duration::from_secs(10))));
+
+        let report = monitor.exit_report().unwrap();
+        let exit = report[&pid];
+        assert_eq!(exit.exit_code, Some(3));
+        assert!(!exit.abnormal);
+        assert_eq!(exit.identity.map(|identity| identity.pid), Some(pid));
+
+        let churn = monitor.process_churn();
+        // `cmd` and `ping`.
+        assert_eq!(churn.created, 2);
+        assert_eq!(churn.exited, churn.created);
+        assert_eq!(churn.killed, 0);
+        assert_eq!(churn.abnormal, 0);
+    }
+
+    #[test]
+    fn abort_on_failure() {
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::builder()
+            .abort_on_failure(true)
+            .spawn(&job)
+            .unwrap();
+
+        let long = DisposableChild::spawn_in(&job).unwrap();
+
+        let failing = DisposableChild::spawn_exiting_in(&job, 7, Duration::from_secs(1)).unwrap();
+        let failing_pid = Pid::new(failing.id());
+        failing.wait().unwrap();
+
+        assert!(monitor.wait_until_empty(Some(Duration::from_secs(10))));
+
+        let (pid, exit) = monitor.abort_cause().unwrap();
+        assert_eq!(pid, failing_pid);
+        assert_eq!(exit.exit_code, Some(7));
+
+        assert_eq!(long.wait().unwrap().code(), Some(7));
+    }
+
+    #[test]
+    fn process_details() {
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::builder()
+            .capture_process_details(true)
+            .spawn(&job)
+            .unwrap();
+
+        let child = DisposableChild::spawn_exiting_in(&job, 0, Duration::from_secs(1)).unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+
+        assert!(monitor.wait_until_empty(Some(Duration::from_secs(10))));
+
+        let details = monitor.process_details(pid).unwrap();
+        let image_path = details.image_path.unwrap();
+        assert!(image_path.ends_with("cmd.exe"));
+        let command_line = details.command_line.unwrap();
+        assert!(command_line.to_string_lossy().contains("ping -n 2"));
+    }
+
+    #[test]
+    fn filtered_subscription() {
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::spawn(&job).unwrap();
+
+        let events = monitor.subscribe(NotificationFilter::ACTIVE_PROCESS_ZERO);
+
+        let child = DisposableChild::spawn_exiting_in(&job, 0, Duration::from_secs(1)).unwrap();
+        child.wait().unwrap();
+
+        let event = events.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(event, JobNotification::ActiveProcessZero);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn subscribe_from_callback() {
+        let job = Job::create().unwrap();
+        let monitor = Arc::new(JobMonitor::spawn(&job).unwrap());
+
+        let (sender, receiver) = mpsc::channel();
+        let weak = Arc::downgrade(&monitor);
+        monitor.on_notification(NotificationFilter::NEW_PROCESS, move |_| {
+            if let Some(monitor) = weak.upgrade() {
+                sender
+                    .send(monitor.subscribe(NotificationFilter::ACTIVE_PROCESS_ZERO))
+                    .unwrap();
+            }
+        });
+
+        let child = DisposableChild::spawn_exiting_in(&job, 0, Duration::from_secs(1)).unwrap();
+        child.wait().unwrap();
+
+        // The subscriber added by the callback gets the following notifications.
+        let events = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        let event = events.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(event, JobNotification::ActiveProcessZero);
+    }
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn until_empty() {
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::spawn(&job).unwrap();
+
+        // Resolves immediately when the job is already empty.
+        block_on(monitor.until_empty());
+
+        let child = DisposableChild::spawn_exiting_in(&job, 0, Duration::from_secs(1)).unwrap();
+
+        block_on(monitor.until_empty());
+        assert!(monitor.is_empty());
+
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn waits_end_when_thread_stops() {
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::spawn(&job).unwrap();
+
+        let (started, events) = mpsc::channel();
+        let (release, released) = mpsc::channel::<()>();
+        monitor.on_notification(NotificationFilter::NEW_PROCESS, move |_| {
+            started.send(()).unwrap();
+            let _ = released.recv();
+            panic!("callback failed");
+        });
+
+        let _child = DisposableChild::spawn_in(&job).unwrap();
+        // The job is marked as not empty before the callback runs.
+        events.recv_timeout(Duration::from_secs(10)).unwrap();
+
+        thread::scope(|scope| {
+            let blocking = scope.spawn(|| monitor.wait_until_empty(None));
+            let future = scope.spawn(|| block_on(monitor.until_empty()));
+
+            thread::sleep(Duration::from_millis(100));
+            release.send(()).unwrap();
+
+            assert!(!blocking.join().unwrap());
+            future.join().unwrap();
+        });
+
+        assert!(!monitor.is_empty());
+    }
+
+    #[test]
+    fn shutdown() {
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::spawn(&job).unwrap();
+
+        let child = DisposableChild::spawn_exiting_in(&job, 0, Duration::ZERO).unwrap();
+        child.wait().unwrap();
+
+        assert!(monitor.wait_until_empty(Some(Duration::from_secs(10))));
+
+        let pending = monitor.shutdown(Duration::from_secs(5)).unwrap();
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn shutdown_timeout() {
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::spawn(&job).unwrap();
+
+        let (started, events) = std::sync::mpsc::channel();
+        monitor.on_notification(NotificationFilter::ALL, move |_| {
+            let _ = started.send(());
+            thread::sleep(Duration::from_secs(1));
+        });
+
+        let child = DisposableChild::spawn_exiting_in(&job, 0, Duration::ZERO).unwrap();
+        child.wait().unwrap();
+
+        events.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(matches!(
+            monitor.shutdown(Duration::from_millis(10)),
+            Err(JobError::MonitorShutdownTimedOut)
+        ));
+    }
+
+    #[test]
+    fn forward_panic() {
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::builder()
+            .thread_name("test-monitor")
+            .spawn(&job)
+            .unwrap();
+
+        monitor.on_notification(NotificationFilter::NEW_PROCESS, |_| {
+            assert_eq!(thread::current().name(), Some("test-monitor"));
+            panic!("callback failed");
+        });
+        let events = monitor.subscribe(NotificationFilter::NEW_PROCESS);
+
+        let child = DisposableChild::spawn_exiting_in(&job, 0, Duration::ZERO).unwrap();
+        child.wait().unwrap();
+
+        // Other subscribers still receive the notification.
+        events.recv_timeout(Duration::from_secs(10)).unwrap();
+
+        let err = monitor.shutdown(Duration::from_secs(5)).unwrap_err();
+        assert!(matches!(err, JobError::MonitorPanicked(message) if message == "callback failed"));
+    }
+
+    #[test]
+    fn bounded_subscription() {
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::spawn(&job).unwrap();
+
+        let events = monitor.subscribe_bounded(
+            NotificationFilter::ACTIVE_PROCESS_ZERO,
+            1,
+            OverflowPolicy::DropOldest,
+        );
+
+        let child = DisposableChild::spawn_exiting_in(&job, 0, Duration::ZERO).unwrap();
+        child.wait().unwrap();
+
+        let event = events.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(event, JobNotification::ActiveProcessZero);
+        assert_eq!(events.dropped(), 0);
+
+        drop(monitor);
+        assert!(events.recv().is_err());
+    }
+
+    #[test]
+    fn timed_subscription() {
+        let job = Job::create().unwrap();
+        let monitor = JobMonitor::spawn(&job).unwrap();
+
+        let events = monitor.subscribe_timed(NotificationFilter::ALL);
+        let before = Instant::now();
+
+        let child = DisposableChild::spawn_exiting_in(&job, 0, Duration::ZERO).unwrap();
+        child.wait().unwrap();
+
+        let first = events.recv_timeout(Duration::from_secs(10)).unwrap();
+        let second = events.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(first.received_at >= before);
+        assert!(second.received_at >= first.received_at);
+    }
+}
@@ -0,0 +1,80 @@
+use windows::Win32::System::JobObjects::{
+    JOBOBJECT_NET_RATE_CONTROL_INFORMATION, JOB_OBJECT_NET_RATE_CONTROL_DSCP_TAG,
+    JOB_OBJECT_NET_RATE_CONTROL_ENABLE, JOB_OBJECT_NET_RATE_CONTROL_FLAGS,
+    JOB_OBJECT_NET_RATE_CONTROL_MAX_BANDWIDTH,
+};
+
+use crate::{Job, JobError};
+
+/// Contains network rate control information for a job object, with helper methods for
+/// easy limit manipulation. To apply limits, pass the instance of this struct to
+/// `job.set_net_rate_control_info`.
+#[derive(Debug, Clone, Copy)]
+pub struct NetRateControlInfo(pub(crate) JOBOBJECT_NET_RATE_CONTROL_INFORMATION);
+
+impl_eq_hash_by_key!(NetRateControlInfo, |info| (
+    info.ControlFlags.0,
+    info.MaxBandwidth,
+    info.DscpTag
+));
+
+impl Default for NetRateControlInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NetRateControlInfo {
+    /// Return an empty network rate control info object, without any limits.
+    pub fn new() -> Self {
+        let inner = Default::default();
+        NetRateControlInfo(inner)
+    }
+
+    /// Limit the outgoing network traffic of the job to `bytes_per_sec`.
+    pub fn limit_max_bandwidth(&mut self, bytes_per_sec: u64) -> &mut Self {
+        self.0.MaxBandwidth = bytes_per_sec;
+        self.0.ControlFlags |=
+            JOB_OBJECT_NET_RATE_CONTROL_ENABLE | JOB_OBJECT_NET_RATE_CONTROL_MAX_BANDWIDTH;
+
+        self
+    }
+
+    /// Tag the outgoing network traffic of the job with the given DSCP value (0 to 63).
+    pub fn limit_dscp_tag(&mut self, dscp_tag: u8) -> &mut Self {
+        self.0.DscpTag = dscp_tag;
+        self.0.ControlFlags |=
+            JOB_OBJECT_NET_RATE_CONTROL_ENABLE | JOB_OBJECT_NET_RATE_CONTROL_DSCP_TAG;
+
+        self
+    }
+
+    /// Clear all limits.
+    pub fn clear_limits(&mut self) -> &mut Self {
+        self.0.ControlFlags = JOB_OBJECT_NET_RATE_CONTROL_FLAGS(0);
+
+        self
+    }
+}
+
+impl Job {
+    /// Return the network rate control information for a job object.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_net_rate_control_information).
+    pub fn query_net_rate_control_info(&self) -> Result<NetRateControlInfo, JobError> {
+        self.query_info()
+    }
+
+    /// Set the network rate control information for a job object.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(job = self.handle.0, flags = info.0.ControlFlags.0),
+            err
+        )
+    )]
+    pub fn set_net_rate_control_info(&self, info: &NetRateControlInfo) -> Result<(), JobError> {
+        self.set_info(info)
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    ops::{BitOr, BitOrAssign},
+    time::{Instant, SystemTime},
+};
+
+use windows::Win32::System::SystemServices::{
+    JOB_OBJECT_MSG_ABNORMAL_EXIT_PROCESS, JOB_OBJECT_MSG_ACTIVE_PROCESS_LIMIT,
+    JOB_OBJECT_MSG_ACTIVE_PROCESS_ZERO, JOB_OBJECT_MSG_END_OF_JOB_TIME,
+    JOB_OBJECT_MSG_END_OF_PROCESS_TIME, JOB_OBJECT_MSG_EXIT_PROCESS,
+    JOB_OBJECT_MSG_JOB_CYCLE_TIME_LIMIT, JOB_OBJECT_MSG_JOB_MEMORY_LIMIT,
+    JOB_OBJECT_MSG_NEW_PROCESS, JOB_OBJECT_MSG_NOTIFICATION_LIMIT,
+    JOB_OBJECT_MSG_PROCESS_MEMORY_LIMIT,
+};
+
+use crate::{Pid, ProcessIdentity};
+
+/// A notification posted by the system to the completion port associated with a job.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_associate_completion_port).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum JobNotification {
+    /// The end-of-job time limit has been reached.
+    EndOfJobTime,
+    /// The end-of-process time limit of the process with the given pid has been reached.
+    EndOfProcessTime(Pid),
+    /// The active process limit has been exceeded.
+    ActiveProcessLimit,
+    /// The active process count has been decremented to 0.
+    ActiveProcessZero,
+    /// A process with the given pid has been added to the job.
+    NewProcess(Pid),
+    /// The process with the given pid has exited.
+    ExitProcess(Pid),
+    /// The process with the given pid has exited due to an unhandled exception.
+    AbnormalExitProcess(Pid),
+    /// The process with the given pid has exceeded its memory limit.
+    ProcessMemoryLimit(Pid),
+    /// The process with the given pid caused the job to exceed its memory limit.
+    JobMemoryLimit(Pid),
+    /// One of the notification limits of the job has been exceeded.
+    NotificationLimit,
+    /// The job cycle time limit has been exceeded.
+    JobCycleTimeLimit,
+    /// A message which is not known to this crate.
+    Unknown { message: u32, value: usize },
+}
+
+impl JobNotification {
+    /// Decode a completion packet dequeued from a port associated with a job
+    /// (using `GetQueuedCompletionStatus`) into the completion key of the job and the notification.
+    pub fn from_packet(
+        completion_key: usize,
+        bytes_transferred: u32,
+        overlapped: usize,
+    ) -> (usize, Self) {
+        (
+            completion_key,
+            Self::from_raw(bytes_transferred, overlapped),
+        )
+    }
+
+    /// Return the `JOB_OBJECT_MSG_*` message number of the notification.
+    pub fn message(&self) -> u32 {
+        match self {
+            Self::EndOfJobTime => JOB_OBJECT_MSG_END_OF_JOB_TIME,
+            Self::EndOfProcessTime(_) => JOB_OBJECT_MSG_END_OF_PROCESS_TIME,
+            Self::ActiveProcessLimit => JOB_OBJECT_MSG_ACTIVE_PROCESS_LIMIT,
+            Self::ActiveProcessZero => JOB_OBJECT_MSG_ACTIVE_PROCESS_ZERO,
+            Self::NewProcess(_) => JOB_OBJECT_MSG_NEW_PROCESS,
+            Self::ExitProcess(_) => JOB_OBJECT_MSG_EXIT_PROCESS,
+            Self::AbnormalExitProcess(_) => JOB_OBJECT_MSG_ABNORMAL_EXIT_PROCESS,
+            Self::ProcessMemoryLimit(_) => JOB_OBJECT_MSG_PROCESS_MEMORY_LIMIT,
+            Self::JobMemoryLimit(_) => JOB_OBJECT_MSG_JOB_MEMORY_LIMIT,
+            Self::NotificationLimit => JOB_OBJECT_MSG_NOTIFICATION_LIMIT,
+            Self::JobCycleTimeLimit => JOB_OBJECT_MSG_JOB_CYCLE_TIME_LIMIT,
+            Self::Unknown { message, .. } => *message,
+        }
+    }
+
+    /// Return the name of the kind of the notification, like `NewProcess`
+    /// (as used for ETW events and `tracing` fields).
+    #[cfg(any(feature = "etw", feature = "tracing"))]
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::EndOfJobTime => "EndOfJobTime",
+            Self::EndOfProcessTime(_) => "EndOfProcessTime",
+            Self::ActiveProcessLimit => "ActiveProcessLimit",
+            Self::ActiveProcessZero => "ActiveProcessZero",
+            Self::NewProcess(_) => "NewProcess",
+            Self::ExitProcess(_) => "ExitProcess",
+            Self::AbnormalExitProcess(_) => "AbnormalExitProcess",
+            Self::ProcessMemoryLimit(_) => "ProcessMemoryLimit",
+            Self::JobMemoryLimit(_) => "JobMemoryLimit",
+            Self::NotificationLimit => "NotificationLimit",
+            Self::JobCycleTimeLimit => "JobCycleTimeLimit",
+            Self::Unknown { .. } => "Unknown",
+        }
+    }
+
+    /// Return the pid of the process the notification is about, if any.
+    pub fn pid(&self) -> Option<Pid> {
+        match self {
+            Self::EndOfProcessTime(pid)
+            | Self::NewProcess(pid)
+            | Self::ExitProcess(pid)
+            | Self::AbnormalExitProcess(pid)
+            | Self::ProcessMemoryLimit(pid)
+            | Self::JobMemoryLimit(pid) => Some(*pid),
+            _ => None,
+        }
+    }
+
+    /// Decode a notification from the message (the number of bytes transferred)
+    /// and the value (the overlapped pointer) of a dequeued completion packet.
+    pub(crate) fn from_raw(message: u32, value: usize) -> Self {
+        let pid = Pid::new(value as u32);
+
+        match message {
+            JOB_OBJECT_MSG_END_OF_JOB_TIME => Self::EndOfJobTime,
+            JOB_OBJECT_MSG_END_OF_PROCESS_TIME => Self::EndOfProcessTime(pid),
+            JOB_OBJECT_MSG_ACTIVE_PROCESS_LIMIT => Self::ActiveProcessLimit,
+            JOB_OBJECT_MSG_ACTIVE_PROCESS_ZERO => Self::ActiveProcessZero,
+            JOB_OBJECT_MSG_NEW_PROCESS => Self::NewProcess(pid),
+            JOB_OBJECT_MSG_EXIT_PROCESS => Self::ExitProcess(pid),
+            JOB_OBJECT_MSG_ABNORMAL_EXIT_PROCESS => Self::AbnormalExitProcess(pid),
+            JOB_OBJECT_MSG_PROCESS_MEMORY_LIMIT => Self::ProcessMemoryLimit(pid),
+            JOB_OBJECT_MSG_JOB_MEMORY_LIMIT => Self::JobMemoryLimit(pid),
+            JOB_OBJECT_MSG_NOTIFICATION_LIMIT => Self::NotificationLimit,
+            JOB_OBJECT_MSG_JOB_CYCLE_TIME_LIMIT => Self::JobCycleTimeLimit,
+            message => Self::Unknown { message, value },
+        }
+    }
+}
+
+/// A notification, stamped with the time it was dequeued from the completion port,
+/// so it can be ordered (and its latency computed) even if it is handled much later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedNotification {
+    /// The notification.
+    pub notification: JobNotification,
+    /// When the notification was dequeued, for computing latencies.
+    pub received_at: Instant,
+    /// When the notification was dequeued, as a wall-clock time.
+    pub received_time: SystemTime,
+    /// The identity of the process the notification is about, if it is tracked by the monitor
+    /// (always `None` for notifications returned by `port.get_timed`).
+    pub process: Option<ProcessIdentity>,
+}
+
+impl TimedNotification {
+    /// Stamp `notification` with the current time.
+    pub(crate) fn now(notification: JobNotification) -> Self {
+        Self {
+            notification,
+            received_at: Instant::now(),
+            received_time: SystemTime::now(),
+            process: None,
+        }
+    }
+}
+
+/// A set of notification kinds, used to subscribe to a subset of the notifications of a job.
+/// Filters can be combined using `|`, for example
+/// `NotificationFilter::NEW_PROCESS | NotificationFilter::ACTIVE_PROCESS_ZERO`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct NotificationFilter(u32);
+
+impl NotificationFilter {
+    /// Don't match any notification.
+    pub const NONE: Self = Self(0);
+    /// Match all notifications, including unknown ones.
+    pub const ALL: Self = Self(u32::MAX);
+    /// Match `JobNotification::EndOfJobTime`.
+    pub const END_OF_JOB_TIME: Self = Self::from_message(JOB_OBJECT_MSG_END_OF_JOB_TIME);
+    /// Match `JobNotification::EndOfProcessTime`.
+    pub const END_OF_PROCESS_TIME: Self = Self::from_message(JOB_OBJECT_MSG_END_OF_PROCESS_TIME);
+    /// Match `JobNotification::ActiveProcessLimit`.
+    pub const ACTIVE_PROCESS_LIMIT: Self = Self::from_message(JOB_OBJECT_MSG_ACTIVE_PROCESS_LIMIT);
+    /// Match `JobNotification::ActiveProcessZero`.
+    pub const ACTIVE_PROCESS_ZERO: Self = Self::from_message(JOB_OBJECT_MSG_ACTIVE_PROCESS_ZERO);
+    /// Match `JobNotification::NewProcess`.
+    pub const NEW_PROCESS: Self = Self::from_message(JOB_OBJECT_MSG_NEW_PROCESS);
+    /// Match `JobNotification::ExitProcess`.
+    pub const EXIT_PROCESS: Self = Self::from_message(JOB_OBJECT_MSG_EXIT_PROCESS);
+    /// Match `JobNotification::AbnormalExitProcess`.
+    pub const ABNORMAL_EXIT_PROCESS: Self =
+        Self::from_message(JOB_OBJECT_MSG_ABNORMAL_EXIT_PROCESS);
+    /// Match `JobNotification::ProcessMemoryLimit`.
+    pub const PROCESS_MEMORY_LIMIT: Self = Self::from_message(JOB_OBJECT_MSG_PROCESS_MEMORY_LIMIT);
+    /// Match `JobNotification::JobMemoryLimit`.
+    pub const JOB_MEMORY_LIMIT: Self = Self::from_message(JOB_OBJECT_MSG_JOB_MEMORY_LIMIT);
+    /// Match `JobNotification::NotificationLimit`.
+    pub const NOTIFICATION_LIMIT: Self = Self::from_message(JOB_OBJECT_MSG_NOTIFICATION_LIMIT);
+    /// Match `JobNotification::JobCycleTimeLimit`.
+    pub const JOB_CYCLE_TIME_LIMIT: Self = Self::from_message(JOB_OBJECT_MSG_JOB_CYCLE_TIME_LIMIT);
+    /// Match the notifications about processes entering and leaving the job,
+    /// and about the job becoming empty.
+    pub const LIFECYCLE: Self = Self(
+        Self::NEW_PROCESS.0
+            | Self::EXIT_PROCESS.0
+            | Self::ABNORMAL_EXIT_PROCESS.0
+            | Self::ACTIVE_PROCESS_ZERO.0,
+    );
+
+    const fn from_message(message: u32) -> Self {
+        Self(1 << message)
+    }
+
+    /// Return `true` if `notification` is matched by the filter.
+    pub fn matches(&self, notification: &JobNotification) -> bool {
+        if *self == Self::ALL {
+            return true;
+        }
+
+        match 1u32.checked_shl(notification.message()) {
+            Some(bit) => self.0 & bit != 0,
+            None => false,
+        }
+    }
+}
+
+impl Default for NotificationFilter {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl BitOr for NotificationFilter {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for NotificationFilter {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{JobNotification, NotificationFilter, Pid};
+
+    #[test]
+    fn decode_notifications() {
+        assert_eq!(
+            JobNotification::from_raw(6, 42),
+            JobNotification::NewProcess(Pid::new(42))
+        );
+        assert_eq!(
+            JobNotification::from_raw(4, 0),
+            JobNotification::ActiveProcessZero
+        );
+        assert_eq!(
+            JobNotification::from_raw(100, 7),
+            JobNotification::Unknown {
+                message: 100,
+                value: 7
+            }
+        );
+        assert_eq!(
+            JobNotification::from_packet(3, 7, 42),
+            (3, JobNotification::ExitProcess(Pid::new(42)))
+        );
+    }
+
+    #[test]
+    fn filter_notifications() {
+        let filter = NotificationFilter::NEW_PROCESS | NotificationFilter::ACTIVE_PROCESS_ZERO;
+
+        assert!(filter.matches(&JobNotification::NewProcess(Pid::new(42))));
+        assert!(filter.matches(&JobNotification::ActiveProcessZero));
+        assert!(!filter.matches(&JobNotification::ExitProcess(Pid::new(42))));
+
+        let unknown = JobNotification::Unknown {
+            message: 100,
+            value: 0,
+        };
+        assert!(!filter.matches(&unknown));
+        assert!(NotificationFilter::ALL.matches(&unknown));
+        assert!(!NotificationFilter::NONE.matches(&JobNotification::EndOfJobTime));
+    }
+}
@@ -0,0 +1,467 @@
+use std::{fmt, time::Duration};
+
+use windows::Win32::System::JobObjects::{
+    ToleranceHigh, ToleranceIntervalLong, ToleranceIntervalMedium, ToleranceIntervalShort,
+    ToleranceLow, ToleranceMedium, JOBOBJECT_NOTIFICATION_LIMIT_INFORMATION,
+    JOBOBJECT_NOTIFICATION_LIMIT_INFORMATION_2, JOBOBJECT_RATE_CONTROL_TOLERANCE,
+    JOBOBJECT_RATE_CONTROL_TOLERANCE_INTERVAL, JOB_OBJECT_LIMIT, JOB_OBJECT_LIMIT_CPU_RATE_CONTROL,
+    JOB_OBJECT_LIMIT_IO_RATE_CONTROL, JOB_OBJECT_LIMIT_JOB_MEMORY, JOB_OBJECT_LIMIT_JOB_MEMORY_LOW,
+    JOB_OBJECT_LIMIT_JOB_READ_BYTES, JOB_OBJECT_LIMIT_JOB_TIME, JOB_OBJECT_LIMIT_JOB_WRITE_BYTES,
+    JOB_OBJECT_LIMIT_NET_RATE_CONTROL,
+};
+
+use crate::utils::{duration_from_100ns, duration_to_100ns};
+use crate::{Job, JobError};
+
+/// Contains notification limits for a job object, with helper methods for
+/// easy limit manipulation. Unlike the limits in `ExtendedLimitInfo`, exceeding a
+/// notification limit does not affect the job's processes, and only posts a
+/// `JOB_OBJECT_MSG_NOTIFICATION_LIMIT` notification.
+/// To apply limits, pass the instance of this struct to `job.set_notification_limit_info`.
+#[derive(Debug, Clone, Copy)]
+pub struct NotificationLimitInfo(pub(crate) JOBOBJECT_NOTIFICATION_LIMIT_INFORMATION);
+
+impl_eq_hash_by_key!(NotificationLimitInfo, |info| (
+    info.LimitFlags.0,
+    info.IoReadBytesLimit,
+    info.IoWriteBytesLimit,
+    info.PerJobUserTimeLimit,
+    info.JobMemoryLimit,
+    info.RateControlTolerance.0,
+    info.RateControlToleranceInterval.0,
+));
+
+impl Default for NotificationLimitInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotificationLimitInfo {
+    /// Return an empty notification limit info object, without any limits.
+    pub fn new() -> Self {
+        let inner = Default::default();
+        NotificationLimitInfo(inner)
+    }
+
+    /// Notify when the processes in the job read more than `bytes` in total.
+    pub fn limit_io_read_bytes(&mut self, bytes: u64) -> &mut Self {
+        self.0.IoReadBytesLimit = bytes;
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_JOB_READ_BYTES;
+
+        self
+    }
+
+    /// Notify when the processes in the job write more than `bytes` in total.
+    pub fn limit_io_write_bytes(&mut self, bytes: u64) -> &mut Self {
+        self.0.IoWriteBytesLimit = bytes;
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_JOB_WRITE_BYTES;
+
+        self
+    }
+
+    /// Notify when the processes in the job use more than `time` of user-mode execution time.
+    pub fn limit_job_time(&mut self, time: Duration) -> &mut Self {
+        self.0.PerJobUserTimeLimit = duration_to_100ns(time);
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_JOB_TIME;
+
+        self
+    }
+
+    /// Notify when the processes in the job commit more than `bytes` of memory in total.
+    pub fn limit_job_memory(&mut self, bytes: u64) -> &mut Self {
+        self.0.JobMemoryLimit = bytes;
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+
+        self
+    }
+
+    /// Return the user-mode execution time limit of the job, if set.
+    pub fn job_time_limit(&self) -> Option<Duration> {
+        self.0
+            .LimitFlags
+            .contains(JOB_OBJECT_LIMIT_JOB_TIME)
+            .then(|| duration_from_100ns(self.0.PerJobUserTimeLimit))
+    }
+
+    /// Clear all limits.
+    pub fn clear_limits(&mut self) -> &mut Self {
+        self.0.LimitFlags = JOB_OBJECT_LIMIT(0);
+
+        self
+    }
+}
+
+/// How much of a `ToleranceInterval` a rate control limit can be exceeded for,
+/// before a `JOB_OBJECT_MSG_NOTIFICATION_LIMIT` notification is posted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RateControlTolerance {
+    /// The limit can be exceeded for 20% of the interval.
+    Low,
+    /// The limit can be exceeded for 40% of the interval.
+    Medium,
+    /// The limit can be exceeded for 60% of the interval.
+    High,
+}
+
+impl RateControlTolerance {
+    fn as_raw(self) -> JOBOBJECT_RATE_CONTROL_TOLERANCE {
+        match self {
+            RateControlTolerance::Low => ToleranceLow,
+            RateControlTolerance::Medium => ToleranceMedium,
+            RateControlTolerance::High => ToleranceHigh,
+        }
+    }
+
+    fn from_raw(raw: JOBOBJECT_RATE_CONTROL_TOLERANCE) -> Option<Self> {
+        [Self::Low, Self::Medium, Self::High]
+            .into_iter()
+            .find(|tolerance| tolerance.as_raw() == raw)
+    }
+}
+
+/// The window over which a `RateControlTolerance` is measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ToleranceInterval {
+    /// 10 seconds.
+    Short,
+    /// One minute.
+    Medium,
+    /// 10 minutes.
+    Long,
+}
+
+impl ToleranceInterval {
+    /// Return the length of the interval.
+    pub fn duration(&self) -> Duration {
+        match self {
+            ToleranceInterval::Short => Duration::from_secs(10),
+            ToleranceInterval::Medium => Duration::from_secs(60),
+            ToleranceInterval::Long => Duration::from_secs(10 * 60),
+        }
+    }
+
+    fn as_raw(self) -> JOBOBJECT_RATE_CONTROL_TOLERANCE_INTERVAL {
+        match self {
+            ToleranceInterval::Short => ToleranceIntervalShort,
+            ToleranceInterval::Medium => ToleranceIntervalMedium,
+            ToleranceInterval::Long => ToleranceIntervalLong,
+        }
+    }
+
+    fn from_raw(raw: JOBOBJECT_RATE_CONTROL_TOLERANCE_INTERVAL) -> Option<Self> {
+        [Self::Short, Self::Medium, Self::Long]
+            .into_iter()
+            .find(|interval| interval.as_raw() == raw)
+    }
+}
+
+/// Contains the extended notification limits for a job object (Windows 10 and later), which add
+/// rate control tolerances and a low memory limit to those of `NotificationLimitInfo`.
+///
+/// A rate control tolerance lets the job exceed its CPU, I/O or network rate limit for part of
+/// an interval before a notification is posted. For example, to be notified when the job
+/// exceeds its CPU rate for more than 20% of a 10-second window:
+///
+/// ```edition2021
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let job = Job::create()?;
+///
+/// let mut info = NotificationLimitInfo2::new();
+/// info.limit_cpu_rate_tolerance(RateControlTolerance::Low, ToleranceInterval::Short);
+///
+/// job.set_notification_limit_info_2(&info)?;
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct NotificationLimitInfo2(pub(crate) JOBOBJECT_NOTIFICATION_LIMIT_INFORMATION_2);
+
+impl_eq_hash_by_key!(NotificationLimitInfo2, |info| (
+    info.LimitFlags.0,
+    info.IoReadBytesLimit,
+    info.IoWriteBytesLimit,
+    info.PerJobUserTimeLimit,
+    unsafe { info.Anonymous1.JobHighMemoryLimit },
+    unsafe { info.Anonymous2.CpuRateControlTolerance.0 },
+    unsafe { info.Anonymous3.CpuRateControlToleranceInterval.0 },
+    info.IoRateControlTolerance.0,
+    info.JobLowMemoryLimit,
+    info.IoRateControlToleranceInterval.0,
+    info.NetRateControlTolerance.0,
+    info.NetRateControlToleranceInterval.0,
+));
+
+impl fmt::Debug for NotificationLimitInfo2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = &self.0;
+
+        f.debug_struct("NotificationLimitInfo2")
+            .field("LimitFlags", &info.LimitFlags)
+            .field("IoReadBytesLimit", &info.IoReadBytesLimit)
+            .field("IoWriteBytesLimit", &info.IoWriteBytesLimit)
+            .field("PerJobUserTimeLimit", &info.PerJobUserTimeLimit)
+            .field("JobHighMemoryLimit", &unsafe {
+                info.Anonymous1.JobHighMemoryLimit
+            })
+            .field("JobLowMemoryLimit", &info.JobLowMemoryLimit)
+            .field("CpuRateTolerance", &self.cpu_rate_tolerance())
+            .field("IoRateTolerance", &self.io_rate_tolerance())
+            .field("NetRateTolerance", &self.net_rate_tolerance())
+            .finish()
+    }
+}
+
+impl Default for NotificationLimitInfo2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotificationLimitInfo2 {
+    /// Return an empty notification limit info object, without any limits.
+    pub fn new() -> Self {
+        let inner = Default::default();
+        NotificationLimitInfo2(inner)
+    }
+
+    /// Notify when the processes in the job read more than `bytes` in total.
+    pub fn limit_io_read_bytes(&mut self, bytes: u64) -> &mut Self {
+        self.0.IoReadBytesLimit = bytes;
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_JOB_READ_BYTES;
+
+        self
+    }
+
+    /// Notify when the processes in the job write more than `bytes` in total.
+    pub fn limit_io_write_bytes(&mut self, bytes: u64) -> &mut Self {
+        self.0.IoWriteBytesLimit = bytes;
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_JOB_WRITE_BYTES;
+
+        self
+    }
+
+    /// Notify when the processes in the job use more than `time` of user-mode execution time.
+    pub fn limit_job_time(&mut self, time: Duration) -> &mut Self {
+        self.0.PerJobUserTimeLimit = duration_to_100ns(time);
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_JOB_TIME;
+
+        self
+    }
+
+    /// Notify when the processes in the job commit more than `bytes` of memory in total.
+    pub fn limit_job_memory(&mut self, bytes: u64) -> &mut Self {
+        self.0.Anonymous1.JobHighMemoryLimit = bytes;
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+
+        self
+    }
+
+    /// Notify when the processes in the job commit less than `bytes` of memory in total.
+    pub fn limit_job_low_memory(&mut self, bytes: u64) -> &mut Self {
+        self.0.JobLowMemoryLimit = bytes;
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY_LOW;
+
+        self
+    }
+
+    /// Notify when the job exceeds its CPU rate limit (see `CpuRateControlInfo`)
+    /// for longer than `tolerance` of `interval`.
+    pub fn limit_cpu_rate_tolerance(
+        &mut self,
+        tolerance: RateControlTolerance,
+        interval: ToleranceInterval,
+    ) -> &mut Self {
+        self.0.Anonymous2.CpuRateControlTolerance = tolerance.as_raw();
+        self.0.Anonymous3.CpuRateControlToleranceInterval = interval.as_raw();
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_CPU_RATE_CONTROL;
+
+        self
+    }
+
+    /// Notify when the job exceeds its I/O rate limit (see `IoRateControlInfo`)
+    /// for longer than `tolerance` of `interval`.
+    pub fn limit_io_rate_tolerance(
+        &mut self,
+        tolerance: RateControlTolerance,
+        interval: ToleranceInterval,
+    ) -> &mut Self {
+        self.0.IoRateControlTolerance = tolerance.as_raw();
+        self.0.IoRateControlToleranceInterval = interval.as_raw();
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_IO_RATE_CONTROL;
+
+        self
+    }
+
+    /// Notify when the job exceeds its network rate limit (see `NetRateControlInfo`)
+    /// for longer than `tolerance` of `interval`.
+    pub fn limit_net_rate_tolerance(
+        &mut self,
+        tolerance: RateControlTolerance,
+        interval: ToleranceInterval,
+    ) -> &mut Self {
+        self.0.NetRateControlTolerance = tolerance.as_raw();
+        self.0.NetRateControlToleranceInterval = interval.as_raw();
+        self.0.LimitFlags |= JOB_OBJECT_LIMIT_NET_RATE_CONTROL;
+
+        self
+    }
+
+    /// Return the CPU rate control tolerance and interval, if set.
+    pub fn cpu_rate_tolerance(&self) -> Option<(RateControlTolerance, ToleranceInterval)> {
+        if !self
+            .0
+            .LimitFlags
+            .contains(JOB_OBJECT_LIMIT_CPU_RATE_CONTROL)
+        {
+            return None;
+        }
+
+        let (tolerance, interval) = unsafe {
+            (
+                self.0.Anonymous2.CpuRateControlTolerance,
+                self.0.Anonymous3.CpuRateControlToleranceInterval,
+            )
+        };
+
+        Some((
+            RateControlTolerance::from_raw(tolerance)?,
+            ToleranceInterval::from_raw(interval)?,
+        ))
+    }
+
+    /// Return the I/O rate control tolerance and interval, if set.
+    pub fn io_rate_tolerance(&self) -> Option<(RateControlTolerance, ToleranceInterval)> {
+        if !self.0.LimitFlags.contains(JOB_OBJECT_LIMIT_IO_RATE_CONTROL) {
+            return None;
+        }
+
+        Some((
+            RateControlTolerance::from_raw(self.0.IoRateControlTolerance)?,
+            ToleranceInterval::from_raw(self.0.IoRateControlToleranceInterval)?,
+        ))
+    }
+
+    /// Return the network rate control tolerance and interval, if set.
+    pub fn net_rate_tolerance(&self) -> Option<(RateControlTolerance, ToleranceInterval)> {
+        if !self
+            .0
+            .LimitFlags
+            .contains(JOB_OBJECT_LIMIT_NET_RATE_CONTROL)
+        {
+            return None;
+        }
+
+        Some((
+            RateControlTolerance::from_raw(self.0.NetRateControlTolerance)?,
+            ToleranceInterval::from_raw(self.0.NetRateControlToleranceInterval)?,
+        ))
+    }
+
+    /// Return the user-mode execution time limit of the job, if set.
+    pub fn job_time_limit(&self) -> Option<Duration> {
+        self.0
+            .LimitFlags
+            .contains(JOB_OBJECT_LIMIT_JOB_TIME)
+            .then(|| duration_from_100ns(self.0.PerJobUserTimeLimit))
+    }
+
+    /// Clear all limits.
+    pub fn clear_limits(&mut self) -> &mut Self {
+        self.0.LimitFlags = JOB_OBJECT_LIMIT(0);
+
+        self
+    }
+}
+
+impl Job {
+    /// Return the notification limits for a job object.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_notification_limit_information).
+    pub fn query_notification_limit_info(&self) -> Result<NotificationLimitInfo, JobError> {
+        self.query_info()
+    }
+
+    /// Set the notification limits for a job object.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(job = self.handle.0, flags = info.0.LimitFlags.0),
+            err
+        )
+    )]
+    pub fn set_notification_limit_info(
+        &self,
+        info: &NotificationLimitInfo,
+    ) -> Result<(), JobError> {
+        self.set_info(info)
+    }
+
+    /// Return the extended notification limits (including rate control tolerances) for a job object.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/ns-jobapi2-jobobject_notification_limit_information_2).
+    pub fn query_notification_limit_info_2(&self) -> Result<NotificationLimitInfo2, JobError> {
+        self.query_info()
+    }
+
+    /// Set the extended notification limits (including rate control tolerances) for a job object.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(job = self.handle.0, flags = info.0.LimitFlags.0),
+            err
+        )
+    )]
+    pub fn set_notification_limit_info_2(
+        &self,
+        info: &NotificationLimitInfo2,
+    ) -> Result<(), JobError> {
+        self.set_info(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Job, NotificationLimitInfo, NotificationLimitInfo2, RateControlTolerance, ToleranceInterval,
+    };
+
+    #[test]
+    fn notification_limits() {
+        let job = Job::create().unwrap();
+
+        let mut info = NotificationLimitInfo::new();
+        info.limit_job_memory(64 * 1024 * 1024);
+
+        job.set_notification_limit_info(&info).unwrap();
+
+        let queried = job.query_notification_limit_info().unwrap();
+        assert_eq!(queried.0.JobMemoryLimit, 64 * 1024 * 1024);
+        assert_eq!(queried.0.LimitFlags, info.0.LimitFlags);
+    }
+
+    #[test]
+    fn rate_control_tolerance() {
+        let job = Job::create().unwrap();
+
+        let mut info = NotificationLimitInfo2::new();
+        info.limit_cpu_rate_tolerance(RateControlTolerance::Low, ToleranceInterval::Short)
+            .limit_job_low_memory(16 * 1024 * 1024);
+
+        job.set_notification_limit_info_2(&info).unwrap();
+
+        let queried = job.query_notification_limit_info_2().unwrap();
+        assert_eq!(
+            queried.cpu_rate_tolerance(),
+            Some((RateControlTolerance::Low, ToleranceInterval::Short))
+        );
+        assert_eq!(queried.io_rate_tolerance(), None);
+        assert_eq!(queried.0.JobLowMemoryLimit, 16 * 1024 * 1024);
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use crate::{BasicAccountingInfo, ExtendedLimitInfo, Job, JobError, Pid};
+
+/// The operations of a job object, as a trait.
+///
+/// Supervision logic which is written against `JobOps` (instead of `Job`) can be unit-tested
+/// without creating real job objects, by passing it a `MockJob` (with the `mock` feature)
+/// or another implementation.
+///
+/// ```edition2021
+/// use win32job::*;
+///
+/// fn enforce_quota(job: &impl JobOps, max_processes: u32) -> Result<bool, JobError> {
+///     let info = job.query_basic_accounting_info()?;
+///
+///     if info.active_processes() > max_processes {
+///         job.terminate(1)?;
+///         return Ok(true);
+///     }
+///
+///     Ok(false)
+/// }
+///
+/// # fn main() -> Result<(), JobError> {
+/// let job = Job::create()?;
+/// assert!(!enforce_quota(&job, 0)?);
+/// #   Ok(())
+/// # }
+/// ```
+pub trait JobOps {
+    /// Assign the process with the handle `proc_handle` to the job (see `Job::assign_process`).
+    fn assign_process(&self, proc_handle: isize) -> Result<(), JobError>;
+
+    /// Assign the process with the id `pid` to the job (see `Job::assign_process_by_pid`).
+    fn assign_process_by_pid(&self, pid: Pid) -> Result<(), JobError>;
+
+    /// Terminate all the processes of the job (see `Job::terminate`).
+    fn terminate(&self, exit_code: u32) -> Result<(), JobError>;
+
+    /// Terminate a single process of the job (see `Job::kill_process`).
+    fn kill_process(&self, pid: Pid, exit_code: u32) -> Result<(), JobError>;
+
+    /// Return the extended limit information of the job (see `Job::query_extended_limit_info`).
+    fn query_extended_limit_info(&self) -> Result<ExtendedLimitInfo, JobError>;
+
+    /// Set the extended limit information of the job (see `Job::set_extended_limit_info`).
+    fn set_extended_limit_info(&self, info: &ExtendedLimitInfo) -> Result<(), JobError>;
+
+    /// Return the basic accounting information of the job (see `Job::query_basic_accounting_info`).
+    fn query_basic_accounting_info(&self) -> Result<BasicAccountingInfo, JobError>;
+
+    /// Return the ids of the processes of the job (see `Job::query_process_id_list`).
+    fn query_process_id_list(&self) -> Result<Vec<Pid>, JobError>;
+
+    /// Return `true` if there are no active processes in the job.
+    fn is_empty(&self) -> Result<bool, JobError> {
+        Ok(self.query_basic_accounting_info()?.active_processes() == 0)
+    }
+
+    /// Block until the job has no active processes, or until `timeout` elapses
+    /// (see `Job::wait_until_empty`).
+    fn wait_until_empty(&self, timeout: Option<Duration>) -> Result<bool, JobError>;
+}
+
+impl JobOps for Job {
+    fn assign_process(&self, proc_handle: isize) -> Result<(), JobError> {
+        Job::assign_process(self, proc_handle)
+    }
+
+    fn assign_process_by_pid(&self, pid: Pid) -> Result<(), JobError> {
+        Job::assign_process_by_pid(self, pid)
+    }
+
+    fn terminate(&self, exit_code: u32) -> Result<(), JobError> {
+        Job::terminate(self, exit_code)
+    }
+
+    fn kill_process(&self, pid: Pid, exit_code: u32) -> Result<(), JobError> {
+        Job::kill_process(self, pid, exit_code)
+    }
+
+    fn query_extended_limit_info(&self) -> Result<ExtendedLimitInfo, JobError> {
+        Job::query_extended_limit_info(self)
+    }
+
+    fn set_extended_limit_info(&self, info: &ExtendedLimitInfo) -> Result<(), JobError> {
+        Job::set_extended_limit_info(self, info)
+    }
+
+    fn query_basic_accounting_info(&self) -> Result<BasicAccountingInfo, JobError> {
+        Job::query_basic_accounting_info(self)
+    }
+
+    fn query_process_id_list(&self) -> Result<Vec<Pid>, JobError> {
+        Job::query_process_id_list(self)
+    }
+
+    fn is_empty(&self) -> Result<bool, JobError> {
+        Job::is_empty(self)
+    }
+
+    fn wait_until_empty(&self, timeout: Option<Duration>) -> Result<bool, JobError> {
+        Job::wait_until_empty(self, timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ExtendedLimitInfo, Job, JobOps};
+
+    fn limit_processes(job: &impl JobOps) -> Result<(), crate::JobError> {
+        let mut info = ExtendedLimitInfo::new();
+        info.limit_active_processes(2);
+
+        job.set_extended_limit_info(&info)
+    }
+
+    #[test]
+    fn job_implements_ops() {
+        let job = Job::create().unwrap();
+
+        limit_processes(&job).unwrap();
+
+        let info = JobOps::query_extended_limit_info(&job).unwrap();
+        assert_eq!(info.0.BasicLimitInformation.ActiveProcessLimit, 2);
+        assert!(JobOps::is_empty(&job).unwrap());
+        assert!(JobOps::wait_until_empty(&job, None).unwrap());
+    }
+}
@@ -0,0 +1,96 @@
+use std::{fmt, num::TryFromIntError};
+
+/// The identifier of a process.
+///
+/// Process ids are `u32`s, but the system reports the processes of a job as `usize` words,
+/// which are easy to confuse with handles and masks. This type is used for the process ids
+/// of the API (like `job.query_process_id_list` and `JobNotification`), and converts
+/// to and from `u32` and `usize`.
+///
+/// ```edition2021
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let job = Job::create()?;
+/// job.assign_current_process()?;
+///
+/// assert_eq!(job.query_process_id_list()?, [Pid::current()]);
+/// assert!(job.contains_process(std::process::id())?);
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+#[repr(transparent)]
+pub struct Pid(u32);
+
+impl Pid {
+    /// Return the process id `pid`.
+    pub const fn new(pid: u32) -> Self {
+        Pid(pid)
+    }
+
+    /// Return the process id of the current process.
+    pub fn current() -> Self {
+        Pid(std::process::id())
+    }
+
+    /// Return the process id as a `u32`.
+    pub const fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for Pid {
+    fn from(pid: u32) -> Self {
+        Pid(pid)
+    }
+}
+
+impl From<Pid> for u32 {
+    fn from(pid: Pid) -> Self {
+        pid.0
+    }
+}
+
+impl From<Pid> for usize {
+    fn from(pid: Pid) -> Self {
+        pid.0 as usize
+    }
+}
+
+impl TryFrom<usize> for Pid {
+    type Error = TryFromIntError;
+
+    /// Convert a process id as reported by the system (like in `JOBOBJECT_BASIC_PROCESS_ID_LIST`).
+    fn try_from(pid: usize) -> Result<Self, Self::Error> {
+        u32::try_from(pid).map(Pid)
+    }
+}
+
+impl fmt::Display for Pid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Pid;
+
+    #[test]
+    fn conversions() {
+        let pid = Pid::new(42);
+
+        assert_eq!(u32::from(pid), 42);
+        assert_eq!(usize::from(pid), 42);
+        assert_eq!(Pid::from(42u32), pid);
+        assert_eq!(Pid::try_from(42usize), Ok(pid));
+        if usize::BITS > u32::BITS {
+            assert!(Pid::try_from(usize::MAX).is_err());
+        }
+        assert_eq!(pid.to_string(), "42");
+        assert_eq!(Pid::current().as_u32(), std::process::id());
+    }
+}
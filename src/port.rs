@@ -0,0 +1,212 @@
+use std::{ffi::c_void, mem, ptr, time::Duration};
+
+use windows::Win32::{
+    Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE, WAIT_TIMEOUT, WIN32_ERROR},
+    System::{
+        JobObjects::{
+            JobObjectAssociateCompletionPortInformation, SetInformationJobObject,
+            JOBOBJECT_ASSOCIATE_COMPLETION_PORT,
+        },
+        Threading::INFINITE,
+        IO::{CreateIoCompletionPort, GetQueuedCompletionStatus, PostQueuedCompletionStatus},
+    },
+};
+
+use crate::{Job, JobError, JobNotification, TimedNotification};
+
+/// An I/O completion port which receives the notifications of one or more jobs.
+///
+/// Each job is associated with the port using a distinct completion key,
+/// so a single port (and a single thread) can serve many jobs.
+///
+/// ```edition2021
+/// use std::time::Duration;
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let port = CompletionPort::create()?;
+///
+/// let first = Job::create()?;
+/// let second = Job::create()?;
+/// first.associate_completion_port(&port, 1)?;
+/// second.associate_completion_port(&port, 2)?;
+///
+/// while let Some((key, notification)) = port.get(Some(Duration::from_millis(10)))? {
+///     println!("job {}: {:?}", key, notification);
+/// }
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CompletionPort {
+    handle: HANDLE,
+}
+
+unsafe impl Send for CompletionPort {}
+unsafe impl Sync for CompletionPort {}
+
+impl CompletionPort {
+    /// Create a new completion port.
+    pub fn create() -> Result<Self, JobError> {
+        unsafe { CreateIoCompletionPort(INVALID_HANDLE_VALUE, None, 0, 1) }
+            .map_err(|e| JobError::CompletionPortFailed(e.into()))
+            .map(|handle| Self { handle })
+    }
+
+    /// Return the underlying handle to the port.
+    /// Note that this handle will be closed once the `CompletionPort` object is dropped.
+    pub fn handle(&self) -> isize {
+        self.handle.0
+    }
+
+    /// Dequeue the next packet from the port, waiting up to `timeout` (or forever, if `None`).
+    /// Return the completion key of the job which posted it and the decoded notification,
+    /// or `None` if the timeout elapsed.
+    pub fn get(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<Option<(usize, JobNotification)>, JobError> {
+        let timeout = timeout.map_or(INFINITE, |timeout| {
+            timeout.as_millis().min(INFINITE as u128 - 1) as u32
+        });
+
+        let mut bytes_transferred = 0u32;
+        let mut key = 0usize;
+        let mut overlapped = ptr::null_mut();
+
+        let res = unsafe {
+            GetQueuedCompletionStatus(
+                self.handle,
+                &mut bytes_transferred,
+                &mut key,
+                &mut overlapped,
+                timeout,
+            )
+        };
+
+        match res {
+            Ok(()) => Ok(Some(JobNotification::from_packet(
+                key,
+                bytes_transferred,
+                overlapped as usize,
+            ))),
+            Err(e) if e.code() == WIN32_ERROR(WAIT_TIMEOUT.0).to_hresult() => Ok(None),
+            Err(e) => Err(JobError::CompletionPortFailed(e.into())),
+        }
+    }
+
+    /// Like `get`, but stamp the notification with the time it was dequeued.
+    pub fn get_timed(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<Option<(usize, TimedNotification)>, JobError> {
+        Ok(self
+            .get(timeout)?
+            .map(|(key, notification)| (key, TimedNotification::now(notification))))
+    }
+
+    /// Post an empty packet with the given completion key to the port.
+    pub(crate) fn post(&self, key: usize) -> Result<(), JobError> {
+        unsafe { PostQueuedCompletionStatus(self.handle, 0, key, None) }
+            .map_err(|e| JobError::CompletionPortFailed(e.into()))
+    }
+}
+
+impl Drop for CompletionPort {
+    fn drop(&mut self) {
+        if let Err(err) = unsafe { CloseHandle(self.handle) } {
+            log_warn!("Failed to close completion port {:?}: {}", self.handle, err);
+        }
+    }
+}
+
+impl Job {
+    /// Associate the job with `port`. Notifications of the job are posted to the port
+    /// with the given completion `key`, which is returned by `port.get`.
+    /// Note that a job can only be associated with a single completion port.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_associate_completion_port).
+    pub fn associate_completion_port(
+        &self,
+        port: &CompletionPort,
+        key: usize,
+    ) -> Result<(), JobError> {
+        self.associate_completion_port_handle(port.handle.0, key)
+    }
+
+    /// Associate the job with an existing completion port, owned by the caller,
+    /// so job notifications can be handled by an application's existing completion port loop.
+    /// Use `JobNotification::from_packet` to decode the packets dequeued from the port.
+    /// Note that the port must stay open for as long as the job is associated with it.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), err)
+    )]
+    pub fn associate_completion_port_handle(
+        &self,
+        port_handle: isize,
+        key: usize,
+    ) -> Result<(), JobError> {
+        let info = JOBOBJECT_ASSOCIATE_COMPLETION_PORT {
+            CompletionKey: key as *mut c_void,
+            CompletionPort: HANDLE(port_handle),
+        };
+
+        unsafe {
+            SetInformationJobObject(
+                self.handle,
+                JobObjectAssociateCompletionPortInformation,
+                &info as *const _ as *const c_void,
+                mem::size_of_val(&info) as u32,
+            )
+        }
+        .map_err(|e| JobError::SetInfoFailed {
+            class: "JobObjectAssociateCompletionPortInformation",
+            source: e.into(),
+        })
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use std::time::Duration;
+
+    use crate::test_util::DisposableChild;
+    use crate::{CompletionPort, Job, JobNotification, Pid};
+
+    #[test]
+    fn multiplex_jobs() {
+        let port = CompletionPort::create().unwrap();
+
+        let first = Job::create().unwrap();
+        let second = Job::create().unwrap();
+        first.associate_completion_port(&port, 1).unwrap();
+        second.associate_completion_port(&port, 2).unwrap();
+
+        let child = DisposableChild::spawn_exiting_in(&second, 0, Duration::from_secs(1)).unwrap();
+        let pid = Pid::new(child.id());
+        child.wait().unwrap();
+
+        let mut packets = vec![];
+        while let Some(packet) = port.get(Some(Duration::from_secs(1))).unwrap() {
+            packets.push(packet);
+        }
+
+        assert!(packets.contains(&(2, JobNotification::NewProcess(pid))));
+        assert!(packets.contains(&(2, JobNotification::ActiveProcessZero)));
+        assert!(packets.iter().all(|(key, _)| *key == 2));
+    }
+
+    #[test]
+    fn user_owned_port() {
+        let port = CompletionPort::create().unwrap();
+
+        let job = Job::create().unwrap();
+        job.associate_completion_port_handle(port.handle(), 42)
+            .unwrap();
+
+        job.assign_current_process().unwrap();
+
+        let (key, _) = port.get(Some(Duration::from_secs(1))).unwrap().unwrap();
+        assert_eq!(key, 42);
+    }
+}
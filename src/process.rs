@@ -0,0 +1,255 @@
+use std::io;
+#[cfg(windows)]
+use std::{ffi::OsString, mem, os::windows::ffi::OsStringExt, path::PathBuf};
+
+use windows::Win32::{
+    Foundation::{BOOL, ERROR_ACCESS_DENIED},
+    System::{
+        JobObjects::IsProcessInJob,
+        Threading::{
+            OpenProcess, TerminateProcess, PROCESS_ACCESS_RIGHTS,
+            PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SYNCHRONIZE, PROCESS_TERMINATE,
+        },
+    },
+};
+#[cfg(windows)]
+use windows::{
+    core::PWSTR,
+    Wdk::System::Threading::{NtQueryInformationProcess, ProcessCommandLineInformation},
+    Win32::{
+        Foundation::{HANDLE, STATUS_INFO_LENGTH_MISMATCH, UNICODE_STRING},
+        System::Threading::{QueryFullProcessImageNameW, PROCESS_NAME_WIN32},
+    },
+};
+
+use crate::handle::OwnedHandle;
+use crate::utils::{self, ProcessMemoryCounters, ProcessTimes};
+use crate::{Job, JobError, ProcessIdentity};
+
+/// An open handle to a process, which is closed when dropped.
+///
+/// The process is opened with `PROCESS_QUERY_LIMITED_INFORMATION`, `SYNCHRONIZE` and,
+/// if allowed, `PROCESS_TERMINATE`. Since the handle keeps the pid from being reused,
+/// the accessors always refer to the same process, even after it exits.
+#[derive(Debug)]
+pub struct OpenedProcess {
+    handle: OwnedHandle,
+    pid: u32,
+}
+
+impl OpenedProcess {
+    /// Open the process `pid`.
+    pub(crate) fn open(pid: u32) -> Result<Self, io::Error> {
+        let access = PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_SYNCHRONIZE;
+
+        let handle = match Self::open_with(pid, access | PROCESS_TERMINATE) {
+            Err(err) if err.raw_os_error() == Some(ERROR_ACCESS_DENIED.0 as i32) => {
+                Self::open_with(pid, access)
+            }
+            res => res,
+        }?;
+
+        Ok(Self { handle, pid })
+    }
+
+    /// Open the process `pid` with `access`, in addition to `PROCESS_QUERY_LIMITED_INFORMATION`
+    /// and `SYNCHRONIZE` (but without `PROCESS_TERMINATE`).
+    #[cfg(feature = "full")]
+    pub(crate) fn open_with_access(
+        pid: u32,
+        access: PROCESS_ACCESS_RIGHTS,
+    ) -> Result<Self, io::Error> {
+        let handle = Self::open_with(
+            pid,
+            PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_SYNCHRONIZE | access,
+        )?;
+
+        Ok(Self { handle, pid })
+    }
+
+    fn open_with(pid: u32, access: PROCESS_ACCESS_RIGHTS) -> Result<OwnedHandle, io::Error> {
+        Ok(OwnedHandle(unsafe { OpenProcess(access, false, pid) }?))
+    }
+
+    /// Return the pid of the process.
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Return the underlying handle to the process.
+    /// Note that this handle will be closed once the `OpenedProcess` object is dropped.
+    pub fn handle(&self) -> isize {
+        self.handle.0 .0
+    }
+
+    /// Return the identity (pid and creation time) of the process.
+    pub fn identity(&self) -> Result<ProcessIdentity, io::Error> {
+        ProcessIdentity::of_handle(self.handle.0, self.pid)
+    }
+
+    /// Return the creation, exit, kernel and user times of the process.
+    pub fn times(&self) -> Result<ProcessTimes, io::Error> {
+        utils::get_process_times(self.handle())
+    }
+
+    /// Return the memory usage of the process.
+    pub fn memory_info(&self) -> Result<ProcessMemoryCounters, io::Error> {
+        utils::get_process_memory_info(self.handle())
+    }
+
+    /// Return the full path of the executable of the process.
+    #[cfg(windows)]
+    pub fn image_path(&self) -> Result<PathBuf, io::Error> {
+        image_path(self.handle.0)
+    }
+
+    /// Return the command line of the process.
+    /// Requires Windows 8.1 or later, and fails once the process has exited.
+    #[cfg(windows)]
+    pub fn command_line(&self) -> Result<OsString, io::Error> {
+        command_line(self.handle.0)
+    }
+
+    /// Terminate the process with the given exit code.
+    /// Fails if the process could not be opened with `PROCESS_TERMINATE`.
+    pub fn terminate(&self, exit_code: u32) -> Result<(), JobError> {
+        unsafe { TerminateProcess(self.handle.0, exit_code) }
+            .map_err(|e| JobError::TerminateProcessFailed(e.into()))
+    }
+
+    pub(crate) fn is_in_job(&self, job: &Job) -> Result<bool, JobError> {
+        let mut in_job = BOOL::default();
+
+        unsafe { IsProcessInJob(self.handle.0, job.handle, &mut in_job) }.map_err(|e| {
+            JobError::GetInfoFailed {
+                class: "IsProcessInJob",
+                source: e.into(),
+            }
+        })?;
+
+        Ok(in_job.as_bool())
+    }
+}
+
+/// Return the full path of the executable of the process `handle`,
+/// which needs `PROCESS_QUERY_LIMITED_INFORMATION` access.
+#[cfg(windows)]
+pub(crate) fn image_path(handle: HANDLE) -> Result<PathBuf, io::Error> {
+    let mut buf = vec![0u16; 1024];
+
+    loop {
+        let mut len = buf.len() as u32;
+
+        match unsafe {
+            QueryFullProcessImageNameW(
+                handle,
+                PROCESS_NAME_WIN32,
+                PWSTR(buf.as_mut_ptr()),
+                &mut len,
+            )
+        } {
+            Ok(()) => return Ok(OsString::from_wide(&buf[..len as usize]).into()),
+            // Paths can be longer than `MAX_PATH`, up to 32K characters.
+            Err(_) if buf.len() < 32 * 1024 => buf.resize(buf.len() * 2, 0),
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Return the command line of the process `handle`,
+/// which needs `PROCESS_QUERY_LIMITED_INFORMATION` access.
+#[cfg(windows)]
+pub(crate) fn command_line(handle: HANDLE) -> Result<OsString, io::Error> {
+    let mut len = 0;
+
+    // The first call only returns the size of the `UNICODE_STRING` and the string after it.
+    let status = unsafe {
+        NtQueryInformationProcess(
+            handle,
+            ProcessCommandLineInformation,
+            std::ptr::null_mut(),
+            0,
+            &mut len,
+        )
+    };
+    if status != STATUS_INFO_LENGTH_MISMATCH {
+        status.ok()?;
+    }
+
+    // Use `u64`s, so the buffer is aligned for the `UNICODE_STRING`.
+    let mut buf = vec![0u64; (len as usize).div_ceil(mem::size_of::<u64>())];
+
+    unsafe {
+        NtQueryInformationProcess(
+            handle,
+            ProcessCommandLineInformation,
+            buf.as_mut_ptr() as *mut _,
+            mem::size_of_val(buf.as_slice()) as u32,
+            &mut len,
+        )
+    }
+    .ok()?;
+
+    let string = unsafe { &*(buf.as_ptr() as *const UNICODE_STRING) };
+    if string.Buffer.is_null() {
+        return Ok(OsString::new());
+    }
+
+    // `Length` is in bytes, and the buffer points into `buf`.
+    let wide = unsafe {
+        std::slice::from_raw_parts(
+            string.Buffer.0,
+            string.Length as usize / mem::size_of::<u16>(),
+        )
+    };
+
+    Ok(OsString::from_wide(wide))
+}
+
+impl Job {
+    /// Open all the processes of the job (and its child jobs).
+    /// Processes which exit before they can be opened are skipped.
+    pub fn processes(&self) -> Result<Vec<OpenedProcess>, JobError> {
+        let mut processes = vec![];
+
+        for pid in self.query_process_id_list()? {
+            let Ok(process) = OpenedProcess::open(pid.as_u32()) else {
+                continue;
+            };
+
+            // The pid might have been reused by a process outside of the job.
+            if process.is_in_job(self)? {
+                processes.push(process);
+            }
+        }
+
+        Ok(processes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{utils, Job};
+
+    #[test]
+    fn processes() {
+        let job = Job::create().unwrap();
+        job.assign_current_process().unwrap();
+
+        let processes = job.processes().unwrap();
+        let current = processes
+            .iter()
+            .find(|process| process.pid() == std::process::id())
+            .unwrap();
+
+        assert!(current.memory_info().unwrap().working_set_size > 0);
+        assert!(current.times().unwrap().exit_time.is_none());
+        assert_eq!(
+            current.identity().unwrap(),
+            utils::open_process(std::process::id())
+                .unwrap()
+                .identity()
+                .unwrap()
+        );
+    }
+}
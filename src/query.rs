@@ -1,58 +1,254 @@
 use std::{ffi::c_void, mem};
-use windows::Win32::System::JobObjects::{
-    JobObjectBasicProcessIdList, QueryInformationJobObject, JOBOBJECT_BASIC_PROCESS_ID_LIST,
+use windows::Win32::{
+    Foundation::{ERROR_INSUFFICIENT_BUFFER, ERROR_MORE_DATA},
+    System::JobObjects::{
+        JobObjectBasicProcessIdList, JobObjectGroupInformation, QueryInformationJobObject,
+        JOBOBJECT_BASIC_PROCESS_ID_LIST,
+    },
 };
 
-use crate::{Job, JobError};
+use crate::{utils, Job, JobError, Pid};
 
-#[repr(C)]
-#[derive(Debug)]
-struct ProcessIdList {
-    header: JOBOBJECT_BASIC_PROCESS_ID_LIST,
-    list: [usize; 1024],
+/// The number of `usize` words taken by the header of `JOBOBJECT_BASIC_PROCESS_ID_LIST`.
+const HEADER_LEN: usize =
+    mem::size_of::<JOBOBJECT_BASIC_PROCESS_ID_LIST>() / mem::size_of::<usize>() - 1;
+
+/// The number of process ids a new buffer has room for.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// The number of process ids `query_process_id_list_extend` has room for on the stack.
+const INLINE_CAPACITY: usize = 32;
+
+/// A caller owned buffer for variable-length queries (like `job.query_process_id_list_into`).
+///
+/// Reusing the same buffer across calls avoids allocating (and zeroing) a new buffer
+/// for every query. The buffer grows as needed, and never shrinks.
+#[derive(Debug, Clone, Default)]
+pub struct QueryBuffer {
+    buf: Vec<usize>,
+    /// The process ids of the last query, converted from the raw words of `buf`.
+    pids: Vec<Pid>,
+}
+
+impl QueryBuffer {
+    /// Create an empty buffer. Memory is allocated on first use.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a buffer with room for `capacity` process ids.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut buffer = Self::new();
+        buffer.reserve(capacity);
+
+        buffer
+    }
+
+    /// Make sure the buffer has room for at least `capacity` process ids.
+    fn reserve(&mut self, capacity: usize) {
+        let len = HEADER_LEN + capacity.max(1);
+
+        if self.buf.len() < len {
+            self.buf.resize(len, 0);
+            self.pids.reserve(len - HEADER_LEN);
+        }
+    }
+
+    /// Convert the first `count` process ids of the last query, and return them.
+    fn pids(&mut self, count: usize) -> &[Pid] {
+        let words = &self.buf[HEADER_LEN..HEADER_LEN + count];
+
+        self.pids.clear();
+        self.pids
+            .extend(words.iter().map(|&pid| Pid::new(pid as u32)));
+
+        &self.pids
+    }
 }
 
 impl Job {
     /// Return all the process identifiers for a job object.
     /// If the job is nested, the process identifier list consists of all processes
     /// associated with the job and its child jobs.
-    pub fn query_process_id_list(&self) -> Result<Vec<usize>, JobError> {
-        // TODO: We will get an error if there are more than 1024 processes in the job.
-        // This can be fixed by calling `QueryInformationJobObject` a second time,
-        // with a bigger list with the correct size (as returned from the first call).
-        let mut proc_id_list = ProcessIdList {
-            header: Default::default(),
-            list: [0usize; 1024],
-        };
+    pub fn query_process_id_list(&self) -> Result<Vec<Pid>, JobError> {
+        let mut pids = Vec::new();
+        self.query_process_id_list_extend(&mut pids)?;
+
+        Ok(pids)
+    }
+
+    /// Like `query_process_id_list`, but add the process identifiers to `target`
+    /// (for example, a `SmallVec` or an `ArrayVec`), and return their number.
+    /// Up to 32 processes are queried without allocating.
+    pub fn query_process_id_list_extend<E: Extend<Pid>>(
+        &self,
+        target: &mut E,
+    ) -> Result<usize, JobError> {
+        let mut inline = [0usize; HEADER_LEN + INLINE_CAPACITY];
+
+        match self.query_process_id_list_raw(&mut inline) {
+            Ok(count) => {
+                let pids = &inline[HEADER_LEN..HEADER_LEN + count];
+                target.extend(pids.iter().map(|&pid| Pid::new(pid as u32)));
+
+                Ok(count)
+            }
+            Err(JobError::BufferTooSmall { required, .. }) => {
+                let mut buffer = QueryBuffer::with_capacity(required);
+                let pids = self.query_process_id_list_into(&mut buffer)?;
+                target.extend(pids.iter().copied());
+
+                Ok(pids.len())
+            }
+            Err(err) => Err(err),
+        }
+    }
 
-        unsafe {
+    /// Like `query_process_id_list`, but store the process identifiers in a caller owned `buffer`,
+    /// which can be reused across calls.
+    pub fn query_process_id_list_into<'a>(
+        &self,
+        buffer: &'a mut QueryBuffer,
+    ) -> Result<&'a [Pid], JobError> {
+        if buffer.buf.is_empty() {
+            buffer.reserve(DEFAULT_CAPACITY);
+        }
+
+        loop {
+            match self.query_process_id_list_raw(&mut buffer.buf) {
+                Ok(count) => return Ok(buffer.pids(count)),
+                // Grow the buffer to the reported size
+                // (with some room for new processes) and try again.
+                Err(JobError::BufferTooSmall { required, .. }) => {
+                    let capacity = buffer.buf.len() - HEADER_LEN;
+
+                    buffer.reserve(required.max(capacity) * 2);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Like `query_process_id_list_into`, but never grow `buffer`.
+    ///
+    /// If the buffer is too small, return `JobError::BufferTooSmall` with the number of
+    /// processes in the job, which can be used to create a large enough buffer
+    /// (using `QueryBuffer::with_capacity`).
+    /// An empty buffer (like `QueryBuffer::new()`) only fits a job without processes.
+    pub fn try_query_process_id_list_into<'a>(
+        &self,
+        buffer: &'a mut QueryBuffer,
+    ) -> Result<&'a [Pid], JobError> {
+        if buffer.buf.is_empty() {
+            // Only query the number of processes, without allocating.
+            let mut header = [0usize; HEADER_LEN + 1];
+
+            return match self.query_process_id_list_raw(&mut header)? {
+                0 => Ok(&[]),
+                required => Err(JobError::BufferTooSmall {
+                    class: "JobObjectBasicProcessIdList",
+                    required,
+                }),
+            };
+        }
+
+        let count = self.query_process_id_list_raw(&mut buffer.buf)?;
+
+        Ok(buffer.pids(count))
+    }
+
+    /// Query the process identifiers into `buf`, which starts with the list header,
+    /// and return their number.
+    fn query_process_id_list_raw(&self, buf: &mut [usize]) -> Result<usize, JobError> {
+        let res = unsafe {
             QueryInformationJobObject(
                 self.handle,
                 JobObjectBasicProcessIdList,
-                &mut proc_id_list as *mut _ as *mut c_void,
-                mem::size_of_val(&proc_id_list) as u32,
+                buf.as_mut_ptr() as *mut c_void,
+                mem::size_of_val(buf) as u32,
                 None,
             )
+        };
+
+        let header = unsafe { &*(buf.as_ptr() as *const JOBOBJECT_BASIC_PROCESS_ID_LIST) };
+
+        match res {
+            Ok(()) => Ok(header.NumberOfProcessIdsInList as usize),
+            Err(e) if e.code() == ERROR_MORE_DATA.to_hresult() => Err(JobError::BufferTooSmall {
+                class: "JobObjectBasicProcessIdList",
+                required: header.NumberOfAssignedProcesses as usize,
+            }),
+            Err(e) => Err(JobError::GetInfoFailed {
+                class: "JobObjectBasicProcessIdList",
+                source: e.into(),
+            }),
         }
-        .map_err(|e| JobError::GetInfoFailed(e.into()))?;
+    }
 
-        let list = proc_id_list
-            .header
-            .ProcessIdList
-            .into_iter()
-            .chain(proc_id_list.list)
-            .take(proc_id_list.header.NumberOfProcessIdsInList as usize)
-            .collect();
+    /// Return the processor groups the job is assigned to.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/jobapi2/nf-jobapi2-queryinformationjobobject).
+    pub fn query_processor_groups(&self) -> Result<Vec<u16>, JobError> {
+        let mut groups = vec![0; usize::from(utils::get_maximum_processor_group_count()).max(1)];
 
-        Ok(list)
+        loop {
+            match self.try_query_processor_groups_into(&mut groups) {
+                Ok(count) => {
+                    groups.truncate(count);
+
+                    return Ok(groups);
+                }
+                Err(JobError::BufferTooSmall { required, .. }) => groups.resize(required, 0),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Like `query_processor_groups`, but store the processor groups in `groups`,
+    /// and return their number.
+    ///
+    /// If `groups` is too small, return `JobError::BufferTooSmall` with the number of groups.
+    pub fn try_query_processor_groups_into(&self, groups: &mut [u16]) -> Result<usize, JobError> {
+        let mut len = 0;
+
+        let res = unsafe {
+            QueryInformationJobObject(
+                self.handle,
+                JobObjectGroupInformation,
+                groups.as_mut_ptr() as *mut c_void,
+                mem::size_of_val(groups) as u32,
+                Some(&mut len),
+            )
+        };
+
+        let count = len as usize / mem::size_of::<u16>();
+
+        match res {
+            Ok(()) => Ok(count),
+            Err(e)
+                if e.code() == ERROR_INSUFFICIENT_BUFFER.to_hresult()
+                    || e.code() == ERROR_MORE_DATA.to_hresult() =>
+            {
+                Err(JobError::BufferTooSmall {
+                    class: "JobObjectGroupInformation",
+                    required: count.max(groups.len() + 1),
+                })
+            }
+            Err(e) => Err(JobError::GetInfoFailed {
+                class: "JobObjectGroupInformation",
+                source: e.into(),
+            }),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Job;
+    use std::collections::HashSet;
+
+    use crate::{utils, Job, JobError, Pid, QueryBuffer};
 
     #[test]
+    #[allow(clippy::len_zero)]
     fn query_proc_id() {
         let job = Job::create().unwrap();
 
@@ -63,11 +259,108 @@ mod tests {
 
         let pids = job.query_process_id_list().unwrap();
 
-        let current_process_id = std::process::id() as usize;
+        let current_process_id = Pid::current();
 
         // It's not equal to 1 because sometime we "catch" `rusty_fork_test` sub procs.
         assert!(pids.len() >= 1);
 
         assert!(pids.contains(&current_process_id));
     }
+
+    #[test]
+    fn query_proc_id_extend() {
+        let job = Job::create().unwrap();
+        let mut pids = HashSet::new();
+
+        assert_eq!(job.query_process_id_list_extend(&mut pids).unwrap(), 0);
+
+        job.assign_current_process().unwrap();
+
+        let count = job.query_process_id_list_extend(&mut pids).unwrap();
+        assert_eq!(count, pids.len());
+        assert!(pids.contains(&Pid::current()));
+    }
+
+    #[test]
+    fn query_proc_id_into_buffer() {
+        let job = Job::create().unwrap();
+        let mut buffer = QueryBuffer::with_capacity(1);
+
+        assert_eq!(
+            job.query_process_id_list_into(&mut buffer).unwrap(),
+            [] as [Pid; 0]
+        );
+
+        job.assign_current_process().unwrap();
+
+        for _ in 0..2 {
+            let pids = job.query_process_id_list_into(&mut buffer).unwrap();
+            assert!(pids.contains(&Pid::current()));
+        }
+    }
+
+    #[cfg(all(windows, feature = "full"))]
+    #[test]
+    fn query_proc_id_buffer_too_small() {
+        use crate::test_util::DisposableChild;
+
+        let job = Job::create().unwrap();
+        job.assign_current_process().unwrap();
+
+        // The child is created inside the job of the current process.
+        let child = DisposableChild::spawn().unwrap();
+
+        let mut buffer = QueryBuffer::with_capacity(1);
+        let err = job.try_query_process_id_list_into(&mut buffer).unwrap_err();
+        let JobError::BufferTooSmall { required, .. } = err else {
+            panic!("unexpected error: {}", err);
+        };
+        assert!(required >= 2);
+
+        // With some room for processes spawned meanwhile by other tests.
+        let mut buffer = QueryBuffer::with_capacity(required * 2);
+        let pids = job.try_query_process_id_list_into(&mut buffer).unwrap();
+        assert!(pids.contains(&Pid::new(child.id())));
+
+        child.kill().unwrap();
+    }
+
+    #[test]
+    fn try_query_proc_id_empty_buffer() {
+        let job = Job::create().unwrap();
+
+        let mut buffer = QueryBuffer::new();
+        assert_eq!(job.try_query_process_id_list_into(&mut buffer).unwrap(), []);
+
+        job.assign_current_process().unwrap();
+
+        let err = job.try_query_process_id_list_into(&mut buffer).unwrap_err();
+        let JobError::BufferTooSmall { required, .. } = err else {
+            panic!("unexpected error: {}", err);
+        };
+        assert!(required >= 1);
+        assert!(buffer.buf.is_empty());
+    }
+
+    #[test]
+    fn query_processor_groups() {
+        let job = Job::create().unwrap();
+        job.assign_current_process().unwrap();
+
+        let groups = job.query_processor_groups().unwrap();
+        assert!(!groups.is_empty());
+        assert!(groups
+            .iter()
+            .all(|&group| group < utils::get_active_processor_group_count()));
+
+        let err = job.try_query_processor_groups_into(&mut []).unwrap_err();
+        let JobError::BufferTooSmall { required, .. } = err else {
+            panic!("unexpected error: {}", err);
+        };
+        assert!(required >= 1);
+
+        let mut buffer = vec![0; required.max(groups.len())];
+        let count = job.try_query_processor_groups_into(&mut buffer).unwrap();
+        assert_eq!(&buffer[..count], groups);
+    }
 }
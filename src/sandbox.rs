@@ -0,0 +1,618 @@
+use std::{
+    ffi::{OsStr, OsString},
+    io, iter, mem,
+    os::windows::{
+        ffi::OsStrExt,
+        io::{AsRawHandle, RawHandle},
+    },
+    path::{Path, PathBuf},
+};
+
+use windows::{
+    core::{PCWSTR, PWSTR},
+    Win32::{
+        Foundation::{HANDLE, PSID, STILL_ACTIVE, WAIT_FAILED},
+        Security::{
+            AllocateAndInitializeSid, CreateRestrictedToken, CreateWellKnownSid, FreeSid,
+            GetLengthSid, SetTokenInformation, TokenIntegrityLevel, WinBuiltinAdministratorsSid,
+            WinLocalAccountAndAdministratorSid, CREATE_RESTRICTED_TOKEN_FLAGS,
+            DISABLE_MAX_PRIVILEGE, LUA_TOKEN, SECURITY_MANDATORY_LABEL_AUTHORITY,
+            SID_AND_ATTRIBUTES, TOKEN_ADJUST_DEFAULT, TOKEN_ASSIGN_PRIMARY, TOKEN_DUPLICATE,
+            TOKEN_MANDATORY_LABEL, TOKEN_QUERY, WELL_KNOWN_SID_TYPE,
+        },
+        System::{
+            SystemServices::{SECURITY_MANDATORY_LOW_RID, SE_GROUP_INTEGRITY},
+            Threading::{
+                CreateProcessAsUserW, DeleteProcThreadAttributeList, GetCurrentProcess,
+                GetExitCodeProcess, InitializeProcThreadAttributeList, OpenProcessToken,
+                ResumeThread, TerminateProcess, UpdateProcThreadAttribute, WaitForSingleObject,
+                CREATE_SUSPENDED, EXTENDED_STARTUPINFO_PRESENT, INFINITE,
+                LPPROC_THREAD_ATTRIBUTE_LIST, PROCESS_INFORMATION, PROC_THREAD_ATTRIBUTE_JOB_LIST,
+                STARTUPINFOEXW, STARTUPINFOW,
+            },
+        },
+    },
+};
+
+use crate::handle::OwnedHandle;
+use crate::{IsolatedDesktop, Job, JobError, UiRestrictions};
+
+/// The maximum size of a SID, in bytes (`SECURITY_MAX_SID_SIZE`).
+const MAX_SID_SIZE: usize = 68;
+
+/// A process builder which spawns the child process inside a job, with a restricted
+/// version of the token of the current process.
+///
+/// By default, the token is created with `DISABLE_MAX_PRIVILEGE`, which removes all the
+/// privileges except `SeChangeNotifyPrivilege`, and without administrator rights: it is
+/// a `LUA_TOKEN` (like the filtered token of UAC) in which the Administrators and local
+/// administrator account groups are deny-only. Use `low_integrity` to also run the child at
+/// the low integrity level (like a sandboxed browser renderer), and `ui_restrictions` to limit
+/// the job's access to the user interface before the child is spawned.
+///
+/// On Windows 10 and later, the child is created directly inside the job
+/// (using `PROC_THREAD_ATTRIBUTE_JOB_LIST`). On older versions, the child is created suspended,
+/// assigned to the job, and only then resumed.
+///
+/// The child inherits the console of the current process, but no other handles.
+///
+/// ```edition2021,no_run
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let job = Job::create()?;
+///
+/// let mut ui_restrictions = UiRestrictions::new();
+/// ui_restrictions.limit_read_clipboard().limit_write_clipboard();
+///
+/// let exit_code = SandboxCommand::new(&job, "cmd")
+///     .args(["/C", "exit 3"])
+///     .low_integrity(true)
+///     .ui_restrictions(ui_restrictions)
+///     .status()?;
+///
+/// assert_eq!(exit_code, 3);
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SandboxCommand<'a> {
+    job: &'a Job,
+    program: OsString,
+    args: Vec<OsString>,
+    current_dir: Option<PathBuf>,
+    disable_max_privilege: bool,
+    deny_administrators: bool,
+    low_integrity: bool,
+    ui_restrictions: Option<UiRestrictions>,
+    desktop: Option<&'a IsolatedDesktop>,
+}
+
+/// A process spawned by `SandboxCommand`. Unlike `std::process::Child`, dropping it
+/// only closes the handles to the process.
+#[derive(Debug)]
+pub struct SandboxChild {
+    process: OwnedHandle,
+    pid: u32,
+}
+
+impl<'a> SandboxCommand<'a> {
+    /// Create a new builder for launching `program` inside `job`.
+    pub fn new<S: AsRef<OsStr>>(job: &'a Job, program: S) -> Self {
+        SandboxCommand {
+            job,
+            program: program.as_ref().to_owned(),
+            args: vec![],
+            current_dir: None,
+            disable_max_privilege: true,
+            deny_administrators: true,
+            low_integrity: false,
+            ui_restrictions: None,
+            desktop: None,
+        }
+    }
+
+    /// Add an argument to pass to the program.
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.args.push(arg.as_ref().to_owned());
+
+        self
+    }
+
+    /// Add multiple arguments to pass to the program.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_owned()));
+
+        self
+    }
+
+    /// Set the working directory of the child process.
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.current_dir = Some(dir.as_ref().to_owned());
+
+        self
+    }
+
+    /// Remove all the privileges of the token, except `SeChangeNotifyPrivilege`. Enabled by default.
+    pub fn disable_max_privilege(&mut self, disable: bool) -> &mut Self {
+        self.disable_max_privilege = disable;
+
+        self
+    }
+
+    /// Create the token as a `LUA_TOKEN`, with the Administrators and local administrator account
+    /// groups set to deny-only, so an elevated parent doesn't spawn an administrator child.
+    /// Enabled by default.
+    pub fn deny_administrators(&mut self, deny: bool) -> &mut Self {
+        self.deny_administrators = deny;
+
+        self
+    }
+
+    /// Run the child at the low integrity level, so it cannot write to most of the
+    /// file system, registry and other processes. Disabled by default.
+    pub fn low_integrity(&mut self, low: bool) -> &mut Self {
+        self.low_integrity = low;
+
+        self
+    }
+
+    /// Set the UI restrictions of the job to `restrictions` before spawning the child.
+    pub fn ui_restrictions(&mut self, restrictions: UiRestrictions) -> &mut Self {
+        self.ui_restrictions = Some(restrictions);
+
+        self
+    }
+
+    /// Spawn the child onto `desktop`, instead of the desktop of the current process.
+    pub fn desktop(&mut self, desktop: &'a IsolatedDesktop) -> &mut Self {
+        self.desktop = Some(desktop);
+
+        self
+    }
+
+    /// Spawn the child process inside the job.
+    /// If the child cannot be assigned to the job, it is killed and an error is returned.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job = self.job.handle.0), err)
+    )]
+    pub fn spawn(&mut self) -> Result<SandboxChild, JobError> {
+        if let Some(restrictions) = &self.ui_restrictions {
+            self.job.set_ui_restrictions(restrictions)?;
+        }
+
+        let token = self.restricted_token()?;
+
+        let mut command_line = command_line(&self.program, &self.args);
+        let current_dir = self
+            .current_dir
+            .as_ref()
+            .map(|dir| to_wide(dir.as_os_str()));
+
+        let mut desktop = self.desktop.map(|desktop| to_wide(desktop.name().as_ref()));
+
+        let attributes = JobListAttribute::new(self.job);
+
+        let mut startup_info = STARTUPINFOEXW {
+            StartupInfo: STARTUPINFOW {
+                cb: mem::size_of::<STARTUPINFOW>() as u32,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        if let Some(desktop) = &mut desktop {
+            startup_info.StartupInfo.lpDesktop = PWSTR(desktop.as_mut_ptr());
+        }
+
+        let mut flags = CREATE_SUSPENDED;
+        if let Some(attributes) = &attributes {
+            startup_info.StartupInfo.cb = mem::size_of::<STARTUPINFOEXW>() as u32;
+            startup_info.lpAttributeList = attributes.list();
+            flags |= EXTENDED_STARTUPINFO_PRESENT;
+        }
+
+        let mut process_info = PROCESS_INFORMATION::default();
+
+        unsafe {
+            CreateProcessAsUserW(
+                token.0,
+                PCWSTR::null(),
+                PWSTR(command_line.as_mut_ptr()),
+                None,
+                None,
+                false,
+                flags,
+                None,
+                current_dir
+                    .as_ref()
+                    .map_or(PCWSTR::null(), |dir| PCWSTR(dir.as_ptr())),
+                &startup_info.StartupInfo,
+                &mut process_info,
+            )
+        }
+        .map_err(|e| JobError::SpawnFailed(e.into()))?;
+
+        let process = OwnedHandle(process_info.hProcess);
+        let thread = OwnedHandle(process_info.hThread);
+
+        // Without the job list attribute, the child is only assigned once it was created.
+        let res = match &attributes {
+            Some(_) => Ok(()),
+            None => self.job.assign_process_checked(process.0),
+        }
+        .and_then(|_| {
+            if unsafe { ResumeThread(thread.0) } == u32::MAX {
+                return Err(JobError::ResumeFailed(io::Error::last_os_error()));
+            }
+
+            Ok(())
+        });
+
+        if let Err(err) = res {
+            let _ = unsafe { TerminateProcess(process.0, 1) };
+
+            return Err(err);
+        }
+
+        Ok(SandboxChild {
+            process,
+            pid: process_info.dwProcessId,
+        })
+    }
+
+    /// Spawn the child process inside the job, wait for it to exit and return its exit code.
+    pub fn status(&mut self) -> Result<u32, JobError> {
+        self.spawn()?.wait()
+    }
+
+    /// Create the restricted primary token of the child.
+    fn restricted_token(&self) -> Result<OwnedHandle, JobError> {
+        let mut token = HANDLE::default();
+
+        unsafe {
+            OpenProcessToken(
+                GetCurrentProcess(),
+                TOKEN_DUPLICATE | TOKEN_QUERY | TOKEN_ASSIGN_PRIMARY | TOKEN_ADJUST_DEFAULT,
+                &mut token,
+            )
+        }
+        .map_err(|e| JobError::RestrictedTokenFailed(e.into()))?;
+        let token = OwnedHandle(token);
+
+        let mut flags = CREATE_RESTRICTED_TOKEN_FLAGS(0);
+        if self.disable_max_privilege {
+            flags |= DISABLE_MAX_PRIVILEGE;
+        }
+
+        let mut admin_sids = vec![];
+        if self.deny_administrators {
+            flags |= LUA_TOKEN;
+
+            for sid_type in [
+                WinBuiltinAdministratorsSid,
+                WinLocalAccountAndAdministratorSid,
+            ] {
+                admin_sids.push(well_known_sid(sid_type).map_err(JobError::RestrictedTokenFailed)?);
+            }
+        }
+
+        // The SIDs are in `admin_sids`, which outlives the call.
+        let sids_to_disable: Vec<_> = admin_sids
+            .iter_mut()
+            .map(|sid| SID_AND_ATTRIBUTES {
+                Sid: PSID(sid.as_mut_ptr() as *mut _),
+                Attributes: 0,
+            })
+            .collect();
+
+        let mut restricted = HANDLE::default();
+
+        unsafe {
+            CreateRestrictedToken(
+                token.0,
+                flags,
+                (!sids_to_disable.is_empty()).then_some(sids_to_disable.as_slice()),
+                None,
+                None,
+                &mut restricted,
+            )
+        }
+        .map_err(|e| JobError::RestrictedTokenFailed(e.into()))?;
+        let restricted = OwnedHandle(restricted);
+
+        if self.low_integrity {
+            set_low_integrity(&restricted).map_err(JobError::RestrictedTokenFailed)?;
+        }
+
+        Ok(restricted)
+    }
+}
+
+/// Create the well-known SID `sid_type` (of the local machine).
+fn well_known_sid(sid_type: WELL_KNOWN_SID_TYPE) -> Result<Vec<u64>, io::Error> {
+    // Use `u64`s, so the SID is suitably aligned.
+    let mut sid = vec![0u64; MAX_SID_SIZE.div_ceil(mem::size_of::<u64>())];
+    let mut size = MAX_SID_SIZE as u32;
+
+    unsafe {
+        CreateWellKnownSid(
+            sid_type,
+            PSID::default(),
+            PSID(sid.as_mut_ptr() as *mut _),
+            &mut size,
+        )
+    }?;
+
+    Ok(sid)
+}
+
+/// Set the integrity level of `token` to low.
+fn set_low_integrity(token: &OwnedHandle) -> Result<(), io::Error> {
+    let mut sid = PSID::default();
+
+    unsafe {
+        AllocateAndInitializeSid(
+            &SECURITY_MANDATORY_LABEL_AUTHORITY,
+            1,
+            SECURITY_MANDATORY_LOW_RID as u32,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            &mut sid,
+        )
+    }?;
+
+    let label = TOKEN_MANDATORY_LABEL {
+        Label: SID_AND_ATTRIBUTES {
+            Sid: sid,
+            Attributes: SE_GROUP_INTEGRITY as u32,
+        },
+    };
+
+    let res = unsafe {
+        SetTokenInformation(
+            token.0,
+            TokenIntegrityLevel,
+            &label as *const _ as *const _,
+            mem::size_of_val(&label) as u32 + GetLengthSid(sid),
+        )
+    };
+
+    unsafe { FreeSid(sid) };
+
+    Ok(res?)
+}
+
+/// A `PROC_THREAD_ATTRIBUTE_JOB_LIST` attribute list, which creates the child inside a job.
+struct JobListAttribute {
+    // Use `u64`s, so the list is suitably aligned.
+    buf: Vec<u64>,
+    // The attribute points to the job handle, which must outlive the list.
+    _job: Box<HANDLE>,
+}
+
+impl JobListAttribute {
+    /// Create the attribute list, or return `None` if it is not supported (before Windows 10).
+    fn new(job: &Job) -> Option<Self> {
+        let mut size = 0;
+
+        // The first call only returns the size of the list.
+        let _ = unsafe {
+            InitializeProcThreadAttributeList(
+                LPPROC_THREAD_ATTRIBUTE_LIST::default(),
+                1,
+                0,
+                &mut size,
+            )
+        };
+
+        let mut buf = vec![0u64; size.div_ceil(mem::size_of::<u64>())];
+        let list = LPPROC_THREAD_ATTRIBUTE_LIST(buf.as_mut_ptr() as *mut _);
+
+        unsafe { InitializeProcThreadAttributeList(list, 1, 0, &mut size) }.ok()?;
+
+        let job = Box::new(job.handle);
+        let attribute = JobListAttribute { buf, _job: job };
+
+        unsafe {
+            UpdateProcThreadAttribute(
+                list,
+                0,
+                PROC_THREAD_ATTRIBUTE_JOB_LIST as usize,
+                Some(&*attribute._job as *const HANDLE as *const _),
+                mem::size_of::<HANDLE>(),
+                None,
+                None,
+            )
+        }
+        .ok()?;
+
+        Some(attribute)
+    }
+
+    fn list(&self) -> LPPROC_THREAD_ATTRIBUTE_LIST {
+        LPPROC_THREAD_ATTRIBUTE_LIST(self.buf.as_ptr() as *mut _)
+    }
+}
+
+impl Drop for JobListAttribute {
+    fn drop(&mut self) {
+        unsafe { DeleteProcThreadAttributeList(self.list()) };
+    }
+}
+
+fn to_wide(s: &OsStr) -> Vec<u16> {
+    s.encode_wide().chain(iter::once(0)).collect()
+}
+
+/// Build a command line which `CommandLineToArgvW` (and the MSVC runtime) parses back
+/// into `program` and `args`.
+fn command_line(program: &OsStr, args: &[OsString]) -> Vec<u16> {
+    let mut line = vec![];
+
+    for (index, arg) in iter::once(program)
+        .chain(args.iter().map(|arg| arg.as_os_str()))
+        .enumerate()
+    {
+        if index > 0 {
+            line.push(b' ' as u16);
+        }
+
+        append_quoted(&mut line, arg);
+    }
+
+    line.push(0);
+
+    line
+}
+
+fn append_quoted(line: &mut Vec<u16>, arg: &OsStr) {
+    let arg: Vec<u16> = arg.encode_wide().collect();
+
+    let needs_quotes = arg.is_empty()
+        || arg
+            .iter()
+            .any(|&c| c == b' ' as u16 || c == b'\t' as u16 || c == b'"' as u16);
+
+    if !needs_quotes {
+        line.extend_from_slice(&arg);
+        return;
+    }
+
+    line.push(b'"' as u16);
+
+    let mut backslashes = 0;
+
+    for &c in &arg {
+        if c == b'\\' as u16 {
+            backslashes += 1;
+        } else {
+            // Backslashes are only special before a quote, where they must be doubled.
+            if c == b'"' as u16 {
+                line.extend(iter::repeat_n(b'\\' as u16, backslashes + 1));
+            }
+            backslashes = 0;
+        }
+
+        line.push(c);
+    }
+
+    // Double the trailing backslashes, so they don't escape the closing quote.
+    line.extend(iter::repeat_n(b'\\' as u16, backslashes));
+    line.push(b'"' as u16);
+}
+
+impl SandboxChild {
+    /// Return the pid of the child.
+    pub fn id(&self) -> u32 {
+        self.pid
+    }
+
+    /// Return the underlying handle to the child process.
+    /// Note that this handle will be closed once the `SandboxChild` object is dropped.
+    pub fn handle(&self) -> isize {
+        self.process.0 .0
+    }
+
+    /// Wait for the child to exit, and return its exit code.
+    pub fn wait(&self) -> Result<u32, JobError> {
+        if unsafe { WaitForSingleObject(self.process.0, INFINITE) } == WAIT_FAILED {
+            return Err(JobError::SpawnFailed(io::Error::last_os_error()));
+        }
+
+        self.exit_code()?.ok_or(JobError::ProcessExited)
+    }
+
+    /// Return the exit code of the child if it exited, without blocking.
+    pub fn try_wait(&self) -> Result<Option<u32>, JobError> {
+        self.exit_code()
+    }
+
+    /// Terminate the child with exit code 1.
+    pub fn kill(&self) -> Result<(), JobError> {
+        unsafe { TerminateProcess(self.process.0, 1) }
+            .map_err(|e| JobError::TerminateProcessFailed(e.into()))
+    }
+
+    fn exit_code(&self) -> Result<Option<u32>, JobError> {
+        let mut exit_code = 0;
+
+        unsafe { GetExitCodeProcess(self.process.0, &mut exit_code) }
+            .map_err(|e| JobError::SpawnFailed(e.into()))?;
+
+        Ok((exit_code != STILL_ACTIVE.0 as u32).then_some(exit_code))
+    }
+}
+
+impl AsRawHandle for SandboxChild {
+    fn as_raw_handle(&self) -> RawHandle {
+        self.process.0 .0 as RawHandle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use super::command_line;
+    use crate::{Job, Pid, SandboxCommand};
+
+    #[test]
+    fn quotes_command_line() {
+        let line = command_line(
+            "cmd".as_ref(),
+            &[
+                OsString::from("/C"),
+                OsString::from("echo a b"),
+                OsString::from(r#"say "hi""#),
+                OsString::from(r"C:\dir with spaces\"),
+                OsString::from(""),
+            ],
+        );
+
+        assert_eq!(
+            String::from_utf16(&line[..line.len() - 1]).unwrap(),
+            r#"cmd /C "echo a b" "say \"hi\"" "C:\dir with spaces\\" """#
+        );
+    }
+
+    #[test]
+    fn spawns_sandboxed_inside_job() {
+        let job = Job::create().unwrap();
+
+        let child = SandboxCommand::new(&job, "cmd")
+            .args(["/C", "ping -n 2 127.0.0.1 > NUL & exit 5"])
+            .low_integrity(true)
+            .spawn()
+            .unwrap();
+
+        let pids = job.query_process_id_list().unwrap();
+        assert!(pids.contains(&Pid::new(child.id())));
+
+        assert_eq!(child.wait().unwrap(), 5);
+    }
+
+    #[test]
+    fn denies_administrators() {
+        let job = Job::create().unwrap();
+
+        // `net session` requires administrator rights, even if the current process is elevated.
+        let exit_code = SandboxCommand::new(&job, "cmd")
+            .args(["/C", "net session > NUL 2>&1"])
+            .status()
+            .unwrap();
+
+        assert_ne!(exit_code, 0);
+    }
+}
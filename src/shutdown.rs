@@ -0,0 +1,88 @@
+use std::time::{Duration, Instant};
+
+use crate::console::send_ctrl_break;
+use crate::window::close_windows;
+use crate::{Job, JobError, Pid};
+
+impl Job {
+    /// Ask the processes of the job to exit, by sending `CTRL_BREAK` to the console process groups
+    /// led by `groups` (see `send_ctrl_break`) and `WM_CLOSE` to their top-level windows.
+    /// Return the number of process groups and windows which were signaled.
+    pub(crate) fn signal_processes(&self, groups: &[Pid]) -> Result<usize, JobError> {
+        let pids: Vec<u32> = self
+            .query_process_id_list()?
+            .into_iter()
+            .map(Pid::as_u32)
+            .collect();
+        let groups: Vec<u32> = groups.iter().copied().map(Pid::as_u32).collect();
+
+        Ok(send_ctrl_break(&groups, &pids, false) + close_windows(&pids))
+    }
+
+    /// Gracefully shut down the job: ask its processes to exit by sending a `WM_CLOSE` message
+    /// to their top-level windows, wait up to `grace` for the job to become empty,
+    /// and then terminate any remaining processes with exit code 1.
+    ///
+    /// Console processes have no windows: use `shutdown_with_ctrl_break` to also signal them.
+    ///
+    /// Return `true` if the job became empty before the grace period elapsed.
+    pub async fn shutdown(&self, grace: Duration) -> Result<bool, JobError> {
+        self.shutdown_with_ctrl_break(grace, &[]).await
+    }
+
+    /// Like `shutdown`, but also send a `CTRL_BREAK` event to the console process groups
+    /// led by `groups`, which must have been created with `CREATE_NEW_PROCESS_GROUP`
+    /// (see `send_ctrl_break`).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), ret, err)
+    )]
+    pub async fn shutdown_with_ctrl_break(
+        &self,
+        grace: Duration,
+        groups: &[Pid],
+    ) -> Result<bool, JobError> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+        let deadline = Instant::now() + grace;
+        let signaled = self.signal_processes(groups)?;
+        log_debug!("Signaled {} process groups and windows", signaled);
+
+        loop {
+            if self.is_empty()? {
+                return Ok(true);
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+
+            tokio::time::sleep(remaining.min(POLL_INTERVAL)).await;
+        }
+
+        self.terminate(1)?;
+
+        Ok(false)
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use crate::test_util::DisposableChild;
+    use crate::{Job, Pid};
+
+    #[test]
+    fn signal_processes() {
+        let job = Job::create().unwrap();
+
+        let child = DisposableChild::spawn_process_group_in(&job).unwrap();
+
+        // Without known group leaders, console processes are not signaled.
+        assert_eq!(job.signal_processes(&[]).unwrap(), 0);
+        assert_eq!(job.signal_processes(&[Pid::new(child.id())]).unwrap(), 1);
+
+        job.terminate(1).unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+use windows::Win32::Foundation::{ERROR_INVALID_PARAMETER, ERROR_NOT_SUPPORTED};
+
+use crate::{
+    BasicAndIoAccountingInfo, CpuRateControlInfo, ExtendedLimitInfo, IoRateControlInfo, Job,
+    JobError, NetRateControlInfo, NotificationLimitInfo, Pid, UiRestrictions,
+};
+
+/// A snapshot of all the queryable information of a job, as returned by `job.query_all`.
+///
+/// Information classes which are not supported on the current version of Windows are `None`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct JobSnapshot {
+    /// Basic and extended limits.
+    pub extended_limits: ExtendedLimitInfo,
+    /// Basic and I/O accounting information.
+    pub accounting: BasicAndIoAccountingInfo,
+    /// The process identifiers of the job (and its child jobs).
+    pub process_ids: Vec<Pid>,
+    /// Basic user-interface restrictions.
+    pub ui_restrictions: UiRestrictions,
+    /// CPU rate control.
+    pub cpu_rate_control: Option<CpuRateControlInfo>,
+    /// Network rate control.
+    pub net_rate_control: Option<NetRateControlInfo>,
+    /// I/O rate control, one entry per volume.
+    pub io_rate_control: Option<Vec<IoRateControlInfo>>,
+    /// Notification limits.
+    pub notification_limits: Option<NotificationLimitInfo>,
+}
+
+/// Map a failure to query an information class which is not supported by the OS to `None`.
+fn supported<T>(res: Result<T, JobError>) -> Result<Option<T>, JobError> {
+    match res {
+        Ok(info) => Ok(Some(info)),
+        Err(JobError::GetInfoFailed { source: err, .. })
+            if err.raw_os_error() == Some(ERROR_INVALID_PARAMETER.0 as i32)
+                || err.raw_os_error() == Some(ERROR_NOT_SUPPORTED.0 as i32) =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+impl Job {
+    /// Query all the information of the job in one call, for example for debug dumps.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), err)
+    )]
+    pub fn query_all(&self) -> Result<JobSnapshot, JobError> {
+        Ok(JobSnapshot {
+            extended_limits: self.query_extended_limit_info()?,
+            accounting: self.query_basic_and_io_accounting_info()?,
+            process_ids: self.query_process_id_list()?,
+            ui_restrictions: self.query_ui_restrictions()?,
+            cpu_rate_control: supported(self.query_cpu_rate_control_info())?,
+            net_rate_control: supported(self.query_net_rate_control_info())?,
+            io_rate_control: supported(self.query_io_rate_control_info(None))?,
+            notification_limits: supported(self.query_notification_limit_info())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Job, Pid};
+
+    #[test]
+    fn query_all() {
+        let job = Job::create().unwrap();
+        job.assign_current_process().unwrap();
+
+        let snapshot = job.query_all().unwrap();
+        assert!(snapshot.process_ids.contains(&Pid::current()));
+        assert!(snapshot.accounting.basic().active_processes() >= 1);
+        assert!(snapshot.cpu_rate_control.is_some());
+    }
+}
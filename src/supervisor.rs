@@ -0,0 +1,296 @@
+use std::{
+    fmt,
+    process::{Child, ExitStatus},
+    sync::{mpsc::RecvTimeoutError, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    ExtendedLimitInfo, Job, JobCommand, JobError, JobMonitor, JobNotification, NotificationFilter,
+};
+
+/// How a `JobSupervisor` restarts processes which exit abnormally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// The number of times each command is restarted before giving up.
+    pub max_restarts: u32,
+    /// The delay before the first restart, which is doubled for every following restart.
+    pub backoff: Duration,
+    /// The maximal delay before a restart.
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 3,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Return the delay before restarting a command which was already restarted `restarts` times.
+    fn delay(&self, restarts: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(restarts))
+            .min(self.max_backoff)
+    }
+}
+
+type GiveUpCallback = Box<dyn FnMut(usize, ExitStatus) + Send>;
+
+/// Runs a set of commands inside a kill-on-close job, restarting the processes which exit
+/// abnormally (due to an unhandled exception, see `JobNotification::AbnormalExitProcess`)
+/// according to a `RestartPolicy`.
+///
+/// ```edition2021,no_run
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let mut supervisor = JobSupervisor::new(RestartPolicy::default())?;
+/// supervisor.on_give_up(|index, status| eprintln!("command {} keeps crashing: {}", index, status));
+///
+/// let mut worker = JobCommand::new(supervisor.job(), "worker.exe");
+/// worker.arg("--serve");
+///
+/// let statuses = supervisor.run(&mut [worker])?;
+/// #   Ok(())
+/// # }
+/// ```
+pub struct JobSupervisor {
+    job: Job,
+    monitor: JobMonitor,
+    policy: RestartPolicy,
+    on_give_up: Mutex<Option<GiveUpCallback>>,
+}
+
+impl fmt::Debug for JobSupervisor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JobSupervisor")
+            .field("job", &self.job)
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A supervised command.
+#[derive(Debug, Default)]
+struct Slot {
+    child: Option<Child>,
+    restarts: u32,
+    restart_at: Option<Instant>,
+    status: Option<ExitStatus>,
+}
+
+impl JobSupervisor {
+    /// Create a new kill-on-close job (and a monitor for it) for supervising commands.
+    pub fn new(policy: RestartPolicy) -> Result<Self, JobError> {
+        let mut info = ExtendedLimitInfo::new();
+        info.limit_kill_on_job_close();
+
+        let job = Job::create_with_limit_info(&info)?;
+        let monitor = JobMonitor::spawn(&job)?;
+
+        Ok(Self {
+            job,
+            monitor,
+            policy,
+            on_give_up: Mutex::new(None),
+        })
+    }
+
+    /// Return the supervised job, which the commands passed to `run` must be created with.
+    pub fn job(&self) -> &Job {
+        &self.job
+    }
+
+    /// Call `callback` with the index of the command and its exit status when a command
+    /// exits abnormally after it was already restarted `policy.max_restarts` times.
+    pub fn on_give_up<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: FnMut(usize, ExitStatus) + Send + 'static,
+    {
+        *self
+            .on_give_up
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Box::new(callback));
+
+        self
+    }
+
+    /// Spawn all the `commands`, restart them when they exit abnormally, and block until
+    /// all of them exited for good. Return the last exit status of each command.
+    ///
+    /// Note that only the processes spawned by the commands are supervised,
+    /// and not the processes they create.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(job = self.job.handle.0), err)
+    )]
+    pub fn run(&self, commands: &mut [JobCommand<'_>]) -> Result<Vec<ExitStatus>, JobError> {
+        // Subscribe before spawning, so no exit can be missed.
+        let events = self.monitor.subscribe(
+            NotificationFilter::EXIT_PROCESS | NotificationFilter::ABNORMAL_EXIT_PROCESS,
+        );
+
+        let mut slots: Vec<Slot> = commands.iter().map(|_| Slot::default()).collect();
+
+        for (slot, command) in slots.iter_mut().zip(commands.iter_mut()) {
+            slot.child = Some(command.spawn()?);
+        }
+
+        loop {
+            let now = Instant::now();
+
+            for (slot, command) in slots.iter_mut().zip(commands.iter_mut()) {
+                if slot.restart_at.is_some_and(|at| at <= now) {
+                    slot.restart_at = None;
+                    slot.child = Some(command.spawn()?);
+                }
+            }
+
+            if slots
+                .iter()
+                .all(|slot| slot.child.is_none() && slot.restart_at.is_none())
+            {
+                break;
+            }
+
+            let next_restart = slots.iter().filter_map(|slot| slot.restart_at).min();
+
+            let event = match next_restart {
+                Some(at) => match events.recv_timeout(at.saturating_duration_since(now)) {
+                    Ok(event) => event,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                },
+                None => match events.recv() {
+                    Ok(event) => event,
+                    Err(_) => break,
+                },
+            };
+
+            let (pid, abnormal) = match event {
+                JobNotification::ExitProcess(pid) => (pid.as_u32(), false),
+                JobNotification::AbnormalExitProcess(pid) => (pid.as_u32(), true),
+                _ => continue,
+            };
+
+            let Some(index) = slots
+                .iter()
+                .position(|slot| slot.child.as_ref().is_some_and(|child| child.id() == pid))
+            else {
+                continue;
+            };
+
+            let slot = &mut slots[index];
+            let status = slot
+                .child
+                .take()
+                .expect("the slot has a child")
+                .wait()
+                .map_err(JobError::SpawnFailed)?;
+            slot.status = Some(status);
+
+            if !abnormal {
+                continue;
+            }
+
+            if slot.restarts < self.policy.max_restarts {
+                let delay = self.policy.delay(slot.restarts);
+                log_debug!(
+                    "Command {} exited abnormally ({}), restarting in {:?}",
+                    index,
+                    status,
+                    delay
+                );
+
+                slot.restarts += 1;
+                slot.restart_at = Some(Instant::now() + delay);
+            } else if let Some(on_give_up) = self
+                .on_give_up
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .as_mut()
+            {
+                on_give_up(index, status);
+            }
+        }
+
+        // The monitor stopped, so wait for the remaining children directly.
+        for slot in &mut slots {
+            if let Some(mut child) = slot.child.take() {
+                slot.status = Some(child.wait().map_err(JobError::SpawnFailed)?);
+            }
+        }
+
+        Ok(slots
+            .into_iter()
+            .map(|slot| slot.status.expect("every command was spawned"))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use super::RestartPolicy;
+    use crate::{JobCommand, JobSupervisor};
+
+    /// `STATUS_ACCESS_VIOLATION`, which the OS reports as an abnormal exit.
+    const ACCESS_VIOLATION: i32 = 0xC0000005_u32 as i32;
+
+    #[test]
+    fn restart_policy_delay() {
+        let policy = RestartPolicy {
+            max_restarts: 10,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(400));
+        assert_eq!(policy.delay(3), Duration::from_millis(500));
+        assert_eq!(policy.delay(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn restarts_abnormal_exits() {
+        let mut supervisor = JobSupervisor::new(RestartPolicy {
+            max_restarts: 2,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+        })
+        .unwrap();
+
+        let gave_up = Arc::new(AtomicUsize::new(0));
+        let thread_gave_up = gave_up.clone();
+        supervisor.on_give_up(move |index, _| {
+            assert_eq!(index, 0);
+            thread_gave_up.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let mut crashing = JobCommand::new(supervisor.job(), "cmd");
+        crashing.args(["/C", &format!("exit {}", ACCESS_VIOLATION)]);
+        let mut normal = JobCommand::new(supervisor.job(), "cmd");
+        normal.args(["/C", "exit 3"]);
+
+        let statuses = supervisor.run(&mut [crashing, normal]).unwrap();
+
+        assert_eq!(statuses[0].code(), Some(ACCESS_VIOLATION));
+        assert_eq!(statuses[1].code(), Some(3));
+        assert_eq!(gave_up.load(Ordering::SeqCst), 1);
+
+        let churn = supervisor.monitor.process_churn();
+        assert_eq!(churn.created, 4);
+    }
+}
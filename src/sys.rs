@@ -0,0 +1,29 @@
+//! The low-level job object bindings this crate is built against
+//! (from version 0.52 of the `windows` crate).
+//!
+//! These can be used with the raw information of a job (like `ExtendedLimitInfo::raw_mut`),
+//! without depending on a matching version of the `windows` crate.
+//!
+//! ```edition2021
+//! use win32job::{sys, *};
+//! # fn main() -> Result<(), JobError> {
+//!
+//! let job = Job::create()?;
+//! let mut info = job.query_extended_limit_info()?;
+//!
+//! info.raw_mut().BasicLimitInformation.LimitFlags |= sys::JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION;
+//! job.set_extended_limit_info(&info)?;
+//! #   Ok(())
+//! # }
+//! ```
+
+pub use windows::Win32::Foundation::{BOOL, HANDLE};
+pub use windows::Win32::System::JobObjects::*;
+pub use windows::Win32::System::SystemServices::{
+    JOB_OBJECT_MSG_ABNORMAL_EXIT_PROCESS, JOB_OBJECT_MSG_ACTIVE_PROCESS_LIMIT,
+    JOB_OBJECT_MSG_ACTIVE_PROCESS_ZERO, JOB_OBJECT_MSG_END_OF_JOB_TIME,
+    JOB_OBJECT_MSG_END_OF_PROCESS_TIME, JOB_OBJECT_MSG_EXIT_PROCESS,
+    JOB_OBJECT_MSG_JOB_CYCLE_TIME_LIMIT, JOB_OBJECT_MSG_JOB_MEMORY_LIMIT,
+    JOB_OBJECT_MSG_NEW_PROCESS, JOB_OBJECT_MSG_NOTIFICATION_LIMIT,
+    JOB_OBJECT_MSG_PROCESS_MEMORY_LIMIT,
+};
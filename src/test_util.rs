@@ -0,0 +1,231 @@
+//! Helpers for tests which need real processes to assign to, and terminate in, jobs.
+//!
+//! The processes spawned here are inert: they do not read their input, write any output or
+//! use CPU, and are killed (and waited for) when dropped, so a failing test does not leak them.
+//!
+//! ```edition2021
+//! use win32job::*;
+//! use win32job::test_util::DisposableChild;
+//! # fn main() -> Result<(), JobError> {
+//!
+//! let job = Job::create()?;
+//! let child = DisposableChild::spawn_in(&job)?;
+//!
+//! assert_eq!(job.query_process_id_list()?, [Pid::new(child.id())]);
+//!
+//! job.terminate(5)?;
+//! assert_eq!(child.wait()?.code(), Some(5));
+//! #   Ok(())
+//! # }
+//! ```
+use std::{
+    os::windows::io::{AsRawHandle, RawHandle},
+    process::{Child, Command, ExitStatus, Stdio},
+    time::Duration,
+};
+
+use windows::Win32::System::Threading::CREATE_NEW_PROCESS_GROUP;
+
+use crate::command::spawn_in_job;
+use crate::{Job, JobError};
+
+/// How long an idle child runs for if it is never killed, in seconds.
+const IDLE_SECONDS: u64 = 60 * 60;
+
+/// An inert child process, which is killed and waited for when dropped.
+#[derive(Debug)]
+pub struct DisposableChild {
+    child: Option<Child>,
+}
+
+impl DisposableChild {
+    /// Spawn a process which idles until it is killed (or for an hour).
+    pub fn spawn() -> Result<Self, JobError> {
+        Self::from_command(idle_command())
+    }
+
+    /// Spawn a process which idles until it is killed, directly in `job`.
+    /// The process is assigned to the job before it starts running, so it never runs outside of it.
+    pub fn spawn_in(job: &Job) -> Result<Self, JobError> {
+        let child = spawn_in_job(job, &mut idle_command(), 0)?;
+
+        Ok(DisposableChild { child: Some(child) })
+    }
+
+    /// Spawn a process which idles until it is killed, directly in `job`, as the root of a new
+    /// console process group (like `JobCommand::new_process_group`).
+    pub fn spawn_process_group_in(job: &Job) -> Result<Self, JobError> {
+        let child = spawn_in_job(job, &mut idle_command(), CREATE_NEW_PROCESS_GROUP.0)?;
+
+        Ok(DisposableChild { child: Some(child) })
+    }
+
+    /// Spawn a process which exits with `exit_code` after about `delay`
+    /// (rounded up to whole seconds).
+    ///
+    /// Note that a process with a delay runs a (short-lived) child process of its own,
+    /// which is not killed if this process is killed before the delay elapses.
+    pub fn spawn_exiting(exit_code: u32, delay: Duration) -> Result<Self, JobError> {
+        Self::from_command(exiting_command(exit_code, delay))
+    }
+
+    /// Spawn a process which exits with `exit_code` after about `delay`, directly in `job`.
+    pub fn spawn_exiting_in(job: &Job, exit_code: u32, delay: Duration) -> Result<Self, JobError> {
+        let child = spawn_in_job(job, &mut exiting_command(exit_code, delay), 0)?;
+
+        Ok(DisposableChild { child: Some(child) })
+    }
+
+    /// Spawn `count` idle processes.
+    pub fn spawn_many(count: usize) -> Result<Vec<Self>, JobError> {
+        (0..count).map(|_| Self::spawn()).collect()
+    }
+
+    fn from_command(mut command: Command) -> Result<Self, JobError> {
+        let child = command.spawn().map_err(JobError::SpawnFailed)?;
+
+        Ok(DisposableChild { child: Some(child) })
+    }
+
+    /// Return the process id of the child.
+    pub fn id(&self) -> u32 {
+        self.inner().id()
+    }
+
+    /// Return the underlying handle to the process, to be used with `Job::assign_process`.
+    /// Note that this handle will be closed once the `DisposableChild` object is dropped.
+    pub fn handle(&self) -> isize {
+        self.inner().as_raw_handle() as isize
+    }
+
+    /// Return the underlying `Child`.
+    pub fn inner(&self) -> &Child {
+        self.child
+            .as_ref()
+            .expect("the child is only taken on drop")
+    }
+
+    /// Return the underlying `Child`, mutably.
+    pub fn inner_mut(&mut self) -> &mut Child {
+        self.child
+            .as_mut()
+            .expect("the child is only taken on drop")
+    }
+
+    /// Return the exit status of the child if it has exited, without blocking.
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>, JobError> {
+        self.inner_mut().try_wait().map_err(JobError::SpawnFailed)
+    }
+
+    /// Wait for the child to exit (for example, after terminating its job), and return its exit status.
+    pub fn wait(mut self) -> Result<ExitStatus, JobError> {
+        let mut child = self.child.take().expect("the child is only taken on drop");
+
+        child.wait().map_err(JobError::SpawnFailed)
+    }
+
+    /// Kill the child and wait for it to exit.
+    pub fn kill(mut self) -> Result<ExitStatus, JobError> {
+        let mut child = self.child.take().expect("the child is only taken on drop");
+
+        // Killing a process which already exited fails, but waiting for it succeeds.
+        let _ = child.kill();
+        child.wait().map_err(JobError::SpawnFailed)
+    }
+}
+
+impl AsRawHandle for DisposableChild {
+    fn as_raw_handle(&self) -> RawHandle {
+        self.inner().as_raw_handle()
+    }
+}
+
+impl Drop for DisposableChild {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// A single process (without a `cmd` parent) which idles, using a ping to the loopback
+/// address once a second.
+fn idle_command() -> Command {
+    let mut command = Command::new("ping");
+    command
+        .args(["-n", &(IDLE_SECONDS + 1).to_string(), "127.0.0.1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    command
+}
+
+fn exiting_command(exit_code: u32, delay: Duration) -> Command {
+    let seconds = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+
+    let script = match seconds {
+        0 => format!("exit {exit_code}"),
+        _ => format!("ping -n {} 127.0.0.1 > NUL & exit {exit_code}", seconds + 1),
+    };
+
+    let mut command = Command::new("cmd");
+    command
+        .args(["/C", &script])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::DisposableChild;
+    use crate::{Job, Pid};
+
+    #[test]
+    fn spawn_in_job() {
+        let job = Job::create().unwrap();
+
+        let children = [
+            DisposableChild::spawn_in(&job).unwrap(),
+            DisposableChild::spawn_in(&job).unwrap(),
+        ];
+
+        let mut pids = job.query_process_id_list().unwrap();
+        pids.sort();
+        let mut expected: Vec<_> = children.iter().map(|c| Pid::new(c.id())).collect();
+        expected.sort();
+        assert_eq!(pids, expected);
+
+        job.terminate(9).unwrap();
+        for child in children {
+            assert_eq!(child.wait().unwrap().code(), Some(9));
+        }
+    }
+
+    #[test]
+    fn assign_and_drop() {
+        let job = Job::create().unwrap();
+
+        let child = DisposableChild::spawn().unwrap();
+        job.assign_process(child.handle()).unwrap();
+        assert!(!job.is_empty().unwrap());
+
+        drop(child);
+        assert!(job.wait_until_empty(Some(Duration::from_secs(5))).unwrap());
+    }
+
+    #[test]
+    fn exits_with_code() {
+        let child = DisposableChild::spawn_exiting(3, Duration::ZERO).unwrap();
+        assert_eq!(child.wait().unwrap().code(), Some(3));
+
+        let child = DisposableChild::spawn_exiting(4, Duration::from_millis(100)).unwrap();
+        assert_eq!(child.wait().unwrap().code(), Some(4));
+    }
+}
@@ -0,0 +1,251 @@
+use std::{collections::HashMap, ffi::OsString, io, mem, os::windows::ffi::OsStringExt};
+
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+};
+
+use crate::handle::OwnedHandle;
+use crate::{Job, JobError, Pid, ProcessIdentity};
+
+/// A process of a job, with the processes of the job it created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessNode {
+    /// The identity of the process.
+    pub identity: ProcessIdentity,
+    /// The file name of the executable of the process (like `cmd.exe`),
+    /// if the process was still running when the processes of the system were listed.
+    pub image_name: Option<String>,
+    /// The pid of the process which created this process, as reported by the system.
+    /// For the roots of the tree, this process is either outside of the job, or already exited.
+    pub parent_pid: Option<Pid>,
+    /// The processes of the job which were created by this process, ordered by their creation time.
+    pub children: Vec<ProcessNode>,
+}
+
+impl ProcessNode {
+    /// Return the pid of the process.
+    pub fn pid(&self) -> Pid {
+        self.identity.pid
+    }
+}
+
+/// The processes of a job, arranged by which process created which (see `job.process_tree`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessTree {
+    /// The processes whose parent is not in the job (including orphans, whose parent exited),
+    /// ordered by their creation time.
+    pub roots: Vec<ProcessNode>,
+}
+
+impl ProcessTree {
+    /// Iterate over all the processes of the tree, depth first (parents before their children),
+    /// with the depth of each process (0 for the roots). This is the order for rendering the tree.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &ProcessNode)> {
+        let mut stack: Vec<_> = self.roots.iter().rev().map(|node| (0, node)).collect();
+
+        std::iter::from_fn(move || {
+            let (depth, node) = stack.pop()?;
+            stack.extend(node.children.iter().rev().map(|child| (depth + 1, child)));
+
+            Some((depth, node))
+        })
+    }
+
+    /// Return the process `pid`, if it is in the tree.
+    pub fn find(&self, pid: impl Into<Pid>) -> Option<&ProcessNode> {
+        let pid = pid.into();
+
+        self.iter()
+            .map(|(_, node)| node)
+            .find(|node| node.pid() == pid)
+    }
+
+    /// Return the number of processes in the tree.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Return `true` if the tree has no processes.
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+}
+
+/// A process, as listed by a Toolhelp32 snapshot.
+struct SnapshotEntry {
+    parent_pid: u32,
+    image_name: String,
+}
+
+/// Return the parent pid and image name of every process in the system.
+fn snapshot_processes() -> Result<HashMap<u32, SnapshotEntry>, io::Error> {
+    let snapshot = OwnedHandle(unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) }?);
+
+    let mut entry = PROCESSENTRY32W {
+        dwSize: mem::size_of::<PROCESSENTRY32W>() as u32,
+        ..Default::default()
+    };
+
+    let mut processes = HashMap::new();
+    let mut res = unsafe { Process32FirstW(snapshot.0, &mut entry) };
+
+    while res.is_ok() {
+        let len = entry
+            .szExeFile
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(entry.szExeFile.len());
+
+        processes.insert(
+            entry.th32ProcessID,
+            SnapshotEntry {
+                parent_pid: entry.th32ParentProcessID,
+                image_name: OsString::from_wide(&entry.szExeFile[..len])
+                    .to_string_lossy()
+                    .into_owned(),
+            },
+        );
+
+        res = unsafe { Process32NextW(snapshot.0, &mut entry) };
+    }
+
+    Ok(processes)
+}
+
+impl Job {
+    /// Return the processes of the job (and its child jobs), arranged by which process
+    /// created which, for example for rendering them like `pstree`.
+    ///
+    /// A process is a child of another process of the job only if that process is its reported
+    /// parent, and was created before it: pids are reused, so a parent pid may refer to a newer,
+    /// unrelated process. Processes whose parent is outside of the job, or already exited,
+    /// are roots of the tree. Processes which exit while the tree is built are skipped.
+    ///
+    /// ```edition2021
+    /// use win32job::*;
+    /// # fn main() -> Result<(), JobError> {
+    ///
+    /// let job = Job::create()?;
+    /// job.assign_current_process()?;
+    ///
+    /// for (depth, process) in job.process_tree()?.iter() {
+    ///     println!("{:indent$}{} {:?}", "", process.pid(), process.image_name, indent = depth * 2);
+    /// }
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn process_tree(&self) -> Result<ProcessTree, JobError> {
+        let mut members: Vec<_> = self
+            .processes()?
+            .iter()
+            .filter_map(|process| process.identity().ok())
+            .collect();
+        members.sort_by_key(|identity| identity.creation_time);
+
+        let mut snapshot = snapshot_processes().map_err(JobError::ProcessSnapshotFailed)?;
+
+        let indices: HashMap<_, _> = members
+            .iter()
+            .enumerate()
+            .map(|(index, identity)| (identity.pid, index))
+            .collect();
+
+        let mut nodes: Vec<_> = members
+            .iter()
+            .map(|identity| {
+                let entry = snapshot.remove(&identity.pid.as_u32());
+
+                Some(ProcessNode {
+                    identity: *identity,
+                    parent_pid: entry.as_ref().map(|entry| Pid::new(entry.parent_pid)),
+                    image_name: entry.map(|entry| entry.image_name),
+                    children: vec![],
+                })
+            })
+            .collect();
+
+        // A parent must be created strictly before its child (so it has a lower index),
+        // which also rules out cycles.
+        let parents: Vec<_> = nodes
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(index, node)| {
+                let parent = *indices.get(&node.parent_pid?)?;
+
+                (members[parent].creation_time < members[index].creation_time).then_some(parent)
+            })
+            .collect();
+
+        // Attach the children to their parents starting from the newest process,
+        // so every node is complete before it is moved into its parent.
+        let mut roots = vec![];
+
+        for index in (0..nodes.len()).rev() {
+            let mut node = nodes[index].take().expect("nodes are taken once");
+            node.children.reverse();
+
+            match parents[index] {
+                Some(parent) => nodes[parent]
+                    .as_mut()
+                    .expect("parents are older than their children")
+                    .children
+                    .push(node),
+                None => roots.push(node),
+            }
+        }
+        roots.reverse();
+
+        Ok(ProcessTree { roots })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use crate::{Job, JobCommand, Pid};
+
+    #[test]
+    fn process_tree() {
+        let job = Job::create().unwrap();
+
+        // `cmd` creates a `ping` process, which is also in the job.
+        let mut child = JobCommand::new(&job, "cmd")
+            .args(["/C", "ping -n 30 127.0.0.1 > NUL"])
+            .spawn()
+            .unwrap();
+        let pid = Pid::new(child.id());
+
+        let tree = loop {
+            let tree = job.process_tree().unwrap();
+            if tree.len() >= 2 {
+                break tree;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        assert_eq!(tree.roots.len(), 1);
+        let root = &tree.roots[0];
+        assert_eq!(root.pid(), pid);
+        assert!(root
+            .image_name
+            .as_deref()
+            .is_some_and(|name| name.eq_ignore_ascii_case("cmd.exe")));
+
+        assert_eq!(root.children.len(), 1);
+        let ping = &root.children[0];
+        assert_eq!(ping.parent_pid, Some(pid));
+        assert!(ping
+            .image_name
+            .as_deref()
+            .is_some_and(|name| name.eq_ignore_ascii_case("ping.exe")));
+
+        let depths: Vec<_> = tree.iter().map(|(depth, _)| depth).collect();
+        assert_eq!(depths, [0, 1]);
+        assert_eq!(tree.find(ping.pid()), Some(ping));
+
+        job.terminate(1).unwrap();
+        child.wait().unwrap();
+    }
+}
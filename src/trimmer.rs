@@ -0,0 +1,316 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use windows::Win32::System::Threading::PROCESS_SET_QUOTA;
+
+use crate::monitor::panic_message;
+use crate::process::OpenedProcess;
+use crate::utils::{self, ProcessMemoryCounters};
+use crate::{Job, JobError, JobMonitor, NotificationFilter, Pid};
+
+/// What a `WorkingSetTrimmer` does with a process of the job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrimAction {
+    /// Leave the process as is.
+    Keep,
+    /// Remove as many pages as possible from the working set of the process (see `utils::empty_working_set`).
+    /// The pages are moved to the standby list, so they are cheap to fault back in.
+    Empty,
+    /// Set the minimum and maximum working set sizes of the process, as a hint to the memory manager
+    /// (see `utils::set_process_working_set_size`). Unlike a job memory limit, the process is never
+    /// killed for exceeding the maximum.
+    Limit { min: usize, max: usize },
+}
+
+/// Trims the working sets of a job's processes according to a user-provided policy,
+/// to keep idle processes from holding on to physical memory without killing them.
+///
+/// On every pass, the policy is called with the pid and memory counters of each process of the job,
+/// and returns the `TrimAction` to apply to it. A pass runs every `interval` (if given), when
+/// `trigger` is called, and for each notification matched by `trigger_on` (for example,
+/// when a soft memory limit set with `NotificationLimitInfo::limit_job_memory` is exceeded).
+///
+/// The trimmer stops when `stop` is called, or when it is dropped. If the policy panics,
+/// the trimmer stops, and `stop` returns the panic message as `JobError::MonitorPanicked`.
+///
+/// ```edition2021
+/// use std::time::Duration;
+/// use win32job::*;
+/// # fn main() -> Result<(), JobError> {
+///
+/// let job = Job::create()?;
+///
+/// let trimmer = WorkingSetTrimmer::spawn(&job, Some(Duration::from_secs(30)), |_pid, counters| {
+///     if counters.working_set_size > 256 * 1024 * 1024 {
+///         TrimAction::Empty
+///     } else {
+///         TrimAction::Keep
+///     }
+/// })?;
+///
+/// trimmer.stop()?;
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct WorkingSetTrimmer {
+    shared: Arc<Shared>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    stopped: bool,
+    triggered: bool,
+    trimmed: u64,
+    last_error: Option<JobError>,
+    panicked: Option<String>,
+}
+
+impl WorkingSetTrimmer {
+    /// Start trimming the processes of `job`, running a pass now and then every `interval`
+    /// (or only when triggered, if `interval` is `None`).
+    pub fn spawn<F>(job: &Job, interval: Option<Duration>, mut policy: F) -> Result<Self, JobError>
+    where
+        F: FnMut(Pid, &ProcessMemoryCounters) -> TrimAction + Send + 'static,
+    {
+        let job = job.try_clone()?;
+
+        let shared = Arc::new(Shared::default());
+        let thread_shared = shared.clone();
+
+        let thread = thread::Builder::new()
+            .name("win32job-trimmer".to_owned())
+            .spawn(move || loop {
+                let pass =
+                    panic::catch_unwind(AssertUnwindSafe(|| trim_processes(&job, &mut policy)));
+
+                let mut state = thread_shared.state.lock().unwrap();
+
+                // The policy might be left in an inconsistent state, so it is not called again.
+                let (trimmed, result) = match pass {
+                    Ok(pass) => pass,
+                    Err(payload) => {
+                        let message = panic_message(payload.as_ref());
+                        log_warn!("Working set trimmer policy panicked: {}", message);
+
+                        state.panicked = Some(message);
+                        state.stopped = true;
+                        break;
+                    }
+                };
+
+                state.trimmed += trimmed;
+
+                if let Err(err) = result {
+                    log_warn!("Failed to trim working sets: {}", err);
+                    state.last_error = Some(err);
+                }
+
+                let wake_up = |state: &mut State| !state.stopped && !state.triggered;
+                let mut state = match interval {
+                    Some(interval) => {
+                        thread_shared
+                            .wake
+                            .wait_timeout_while(state, interval, wake_up)
+                            .unwrap()
+                            .0
+                    }
+                    None => thread_shared.wake.wait_while(state, wake_up).unwrap(),
+                };
+
+                if state.stopped {
+                    break;
+                }
+
+                state.triggered = false;
+            })
+            .map_err(JobError::MonitorThreadFailed)?;
+
+        Ok(Self {
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// Run a pass as soon as possible (once the current pass, if any, is done).
+    pub fn trigger(&self) {
+        trigger(&self.shared);
+    }
+
+    /// Run a pass for each notification of `monitor` matched by `filter`, for example
+    /// `NotificationFilter::NOTIFICATION_LIMIT | NotificationFilter::JOB_MEMORY_LIMIT`.
+    pub fn trigger_on(&self, monitor: &JobMonitor, filter: NotificationFilter) {
+        let shared = self.shared.clone();
+
+        monitor.on_notification(filter, move |_notification| trigger(&shared));
+    }
+
+    /// Return the number of times an action (other than `TrimAction::Keep`) was applied to a process.
+    pub fn trimmed(&self) -> u64 {
+        self.shared.state.lock().unwrap().trimmed
+    }
+
+    /// Stop the trimmer, leaving the working set sizes of the processes as they are.
+    /// Return `JobError::MonitorPanicked` if the policy panicked, or else the last error
+    /// which occurred while trimming, if any.
+    pub fn stop(mut self) -> Result<(), JobError> {
+        self.join()?;
+
+        let mut state = self.shared.state.lock().unwrap();
+
+        if let Some(message) = state.panicked.take() {
+            return Err(JobError::MonitorPanicked(message));
+        }
+
+        match state.last_error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn join(&mut self) -> Result<(), JobError> {
+        // The lock is poisoned only if the thread panicked outside of the policy,
+        // which is reported by `join`.
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .stopped = true;
+        self.shared.wake.notify_all();
+
+        match self.thread.take().map(thread::JoinHandle::join) {
+            Some(Err(payload)) => Err(JobError::MonitorPanicked(panic_message(payload.as_ref()))),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Drop for WorkingSetTrimmer {
+    fn drop(&mut self) {
+        let _ = self.join();
+    }
+}
+
+fn trigger(shared: &Shared) {
+    shared.state.lock().unwrap().triggered = true;
+    shared.wake.notify_all();
+}
+
+/// Apply `policy` to the processes of `job`, returning the number of processes which were trimmed,
+/// and the last error. Processes which exit during the pass are skipped.
+fn trim_processes<F>(job: &Job, policy: &mut F) -> (u64, Result<(), JobError>)
+where
+    F: FnMut(Pid, &ProcessMemoryCounters) -> TrimAction,
+{
+    let pids = match job.query_process_id_list() {
+        Ok(pids) => pids,
+        Err(err) => return (0, Err(err)),
+    };
+
+    let mut trimmed = 0;
+    let mut result = Ok(());
+
+    for pid in pids {
+        let Ok(process) = OpenedProcess::open_with_access(pid.as_u32(), PROCESS_SET_QUOTA) else {
+            continue;
+        };
+
+        // The pid might have been reused by a process outside of the job.
+        match process.is_in_job(job) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(err) => {
+                result = Err(err);
+                continue;
+            }
+        }
+
+        let Ok(counters) = process.memory_info() else {
+            continue;
+        };
+
+        let res = match policy(pid, &counters) {
+            TrimAction::Keep => continue,
+            TrimAction::Empty => utils::empty_working_set(process.handle()),
+            TrimAction::Limit { min, max } => {
+                utils::set_process_working_set_size(process.handle(), min, max, false, false)
+            }
+        };
+
+        match res {
+            Ok(()) => {
+                log_debug!("Trimmed the working set of process {}", pid);
+                trimmed += 1;
+            }
+            Err(source) => result = Err(JobError::TrimFailed { pid, source }),
+        }
+    }
+
+    (trimmed, result)
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use crate::test_util::DisposableChild;
+    use crate::{Job, JobError, Pid, TrimAction, WorkingSetTrimmer};
+
+    #[test]
+    fn trims_processes() {
+        let job = Job::create().unwrap();
+        let child = DisposableChild::spawn_in(&job).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let trimmer = WorkingSetTrimmer::spawn(&job, None, move |pid, counters| {
+            sender.send((pid, counters.working_set_size)).unwrap();
+            TrimAction::Empty
+        })
+        .unwrap();
+
+        let (pid, working_set_size) = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(pid, Pid::new(child.id()));
+        assert!(working_set_size > 0);
+
+        // Without an interval, passes only run when triggered.
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+        trimmer.trigger();
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        trimmer.stop().unwrap();
+    }
+
+    #[test]
+    fn policy_panic() {
+        let job = Job::create().unwrap();
+        let _child = DisposableChild::spawn_in(&job).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let trimmer = WorkingSetTrimmer::spawn(&job, None, move |_pid, _counters| {
+            sender.send(()).unwrap();
+            panic!("policy failed");
+        })
+        .unwrap();
+
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        // The policy is not called again after it panicked.
+        trimmer.trigger();
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+
+        let err = trimmer.stop().unwrap_err();
+        assert!(matches!(err, JobError::MonitorPanicked(message) if message == "policy failed"));
+    }
+}
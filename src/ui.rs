@@ -0,0 +1,139 @@
+use windows::Win32::System::JobObjects::{
+    JOBOBJECT_BASIC_UI_RESTRICTIONS, JOB_OBJECT_UILIMIT_DESKTOP,
+    JOB_OBJECT_UILIMIT_DISPLAYSETTINGS, JOB_OBJECT_UILIMIT_EXITWINDOWS,
+    JOB_OBJECT_UILIMIT_GLOBALATOMS, JOB_OBJECT_UILIMIT_HANDLES, JOB_OBJECT_UILIMIT_NONE,
+    JOB_OBJECT_UILIMIT_READCLIPBOARD, JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS,
+    JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+};
+
+use crate::{Job, JobError};
+
+/// Contains basic user-interface restrictions for a job object, with helper methods for
+/// easy limit manipulation. To apply limits, pass the instance of this struct to
+/// `job.set_ui_restrictions`.
+#[derive(Debug, Clone, Copy)]
+pub struct UiRestrictions(pub(crate) JOBOBJECT_BASIC_UI_RESTRICTIONS);
+
+impl_eq_hash_by_key!(UiRestrictions, |info| info.UIRestrictionsClass.0);
+
+impl Default for UiRestrictions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UiRestrictions {
+    /// Return an empty UI restrictions object, without any limits.
+    pub fn new() -> Self {
+        let inner = Default::default();
+        UiRestrictions(inner)
+    }
+
+    /// Prevents processes associated with the job from creating desktops
+    /// and switching desktops.
+    pub fn limit_desktop(&mut self) -> &mut Self {
+        self.0.UIRestrictionsClass |= JOB_OBJECT_UILIMIT_DESKTOP;
+
+        self
+    }
+
+    /// Prevents processes associated with the job from changing the display settings.
+    pub fn limit_display_settings(&mut self) -> &mut Self {
+        self.0.UIRestrictionsClass |= JOB_OBJECT_UILIMIT_DISPLAYSETTINGS;
+
+        self
+    }
+
+    /// Prevents processes associated with the job from logging off, shutting down
+    /// or restarting the system.
+    pub fn limit_exit_windows(&mut self) -> &mut Self {
+        self.0.UIRestrictionsClass |= JOB_OBJECT_UILIMIT_EXITWINDOWS;
+
+        self
+    }
+
+    /// Prevents processes associated with the job from accessing global atoms.
+    /// When this flag is used, each job has its own atom table.
+    pub fn limit_global_atoms(&mut self) -> &mut Self {
+        self.0.UIRestrictionsClass |= JOB_OBJECT_UILIMIT_GLOBALATOMS;
+
+        self
+    }
+
+    /// Prevents processes associated with the job from using USER handles
+    /// owned by processes not associated with the same job.
+    pub fn limit_handles(&mut self) -> &mut Self {
+        self.0.UIRestrictionsClass |= JOB_OBJECT_UILIMIT_HANDLES;
+
+        self
+    }
+
+    /// Prevents processes associated with the job from reading data from the clipboard.
+    pub fn limit_read_clipboard(&mut self) -> &mut Self {
+        self.0.UIRestrictionsClass |= JOB_OBJECT_UILIMIT_READCLIPBOARD;
+
+        self
+    }
+
+    /// Prevents processes associated with the job from writing data to the clipboard.
+    pub fn limit_write_clipboard(&mut self) -> &mut Self {
+        self.0.UIRestrictionsClass |= JOB_OBJECT_UILIMIT_WRITECLIPBOARD;
+
+        self
+    }
+
+    /// Prevents processes associated with the job from changing system parameters
+    /// by using the `SystemParametersInfo` function.
+    pub fn limit_system_parameters(&mut self) -> &mut Self {
+        self.0.UIRestrictionsClass |= JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS;
+
+        self
+    }
+
+    /// Clear all limits.
+    pub fn clear_limits(&mut self) -> &mut Self {
+        self.0.UIRestrictionsClass = JOB_OBJECT_UILIMIT_NONE;
+
+        self
+    }
+}
+
+impl Job {
+    /// Return the basic UI restrictions for a job object.
+    /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-jobobject_basic_ui_restrictions).
+    pub fn query_ui_restrictions(&self) -> Result<UiRestrictions, JobError> {
+        self.query_info()
+    }
+
+    /// Set the basic UI restrictions for a job object.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(job = self.handle.0, flags = info.0.UIRestrictionsClass.0),
+            err
+        )
+    )]
+    pub fn set_ui_restrictions(&self, info: &UiRestrictions) -> Result<(), JobError> {
+        self.set_info(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Job, UiRestrictions};
+
+    #[test]
+    fn ui_restrictions() {
+        let job = Job::create().unwrap();
+
+        let mut info = UiRestrictions::new();
+        info.limit_read_clipboard().limit_write_clipboard();
+
+        job.set_ui_restrictions(&info).unwrap();
+
+        let queried = job.query_ui_restrictions().unwrap();
+        assert_eq!(queried.0, info.0);
+    }
+}
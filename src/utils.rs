@@ -1,19 +1,93 @@
-use std::{io, mem};
+use std::{
+    ffi::c_void,
+    io, mem, ptr,
+    time::{Duration, SystemTime},
+};
 
+use windows::Wdk::Foundation::OBJECT_ATTRIBUTES;
 use windows::Win32::{
-    Foundation::HANDLE,
+    Foundation::{
+        BOOLEAN, ERROR_PROCESS_MODE_ALREADY_BACKGROUND, ERROR_PROCESS_MODE_NOT_BACKGROUND,
+        FILETIME, HANDLE, NTSTATUS, STATUS_MORE_ENTRIES, STATUS_NO_MORE_ENTRIES, UNICODE_STRING,
+    },
     System::{
-        ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS_EX},
-        Threading::{GetCurrentProcess, GetProcessAffinityMask},
+        Memory::{
+            GetProcessWorkingSetSizeEx, SetProcessWorkingSetSizeEx,
+            QUOTA_LIMITS_HARDWS_MAX_DISABLE, QUOTA_LIMITS_HARDWS_MAX_ENABLE,
+            QUOTA_LIMITS_HARDWS_MIN_DISABLE, QUOTA_LIMITS_HARDWS_MIN_ENABLE,
+        },
+        ProcessStatus::{GetProcessMemoryInfo, K32EmptyWorkingSet, PROCESS_MEMORY_COUNTERS_EX},
+        SystemInformation::{
+            GetSystemInfo, GlobalMemoryStatusEx, GROUP_AFFINITY, MEMORYSTATUSEX, SYSTEM_INFO,
+        },
+        Threading::{
+            GetActiveProcessorCount, GetActiveProcessorGroupCount, GetCurrentProcess,
+            GetCurrentThread, GetMaximumProcessorCount, GetMaximumProcessorGroupCount,
+            GetPriorityClass, GetProcessAffinityMask, GetProcessTimes, GetThreadGroupAffinity,
+            GetThreadTimes, SetPriorityClass, SetThreadGroupAffinity, ALL_PROCESSOR_GROUPS,
+            PROCESS_CREATION_FLAGS, PROCESS_MODE_BACKGROUND_BEGIN, PROCESS_MODE_BACKGROUND_END,
+        },
     },
 };
 
+#[cfg(feature = "io-priority")]
+use windows::Wdk::System::Threading::{
+    NtQueryInformationProcess, ProcessIoPriority, PROCESSINFOCLASS,
+};
+
+use crate::handle::OwnedHandle;
+use crate::{OpenedProcess, PriorityClass};
+
+/// The access right needed to list the objects of an object directory.
+const DIRECTORY_QUERY: u32 = 0x0001;
+
+/// An entry of an object directory, as returned by `NtQueryDirectoryObject`.
+#[repr(C)]
+struct ObjectDirectoryInformation {
+    name: UNICODE_STRING,
+    type_name: UNICODE_STRING,
+}
+
+// These are not part of the `windows` crate.
+#[cfg_attr(windows, link(name = "ntdll"))]
+extern "system" {
+    #[cfg(feature = "io-priority")]
+    fn NtSetInformationProcess(
+        process_handle: HANDLE,
+        process_information_class: PROCESSINFOCLASS,
+        process_information: *const c_void,
+        process_information_length: u32,
+    ) -> NTSTATUS;
+
+    fn NtOpenDirectoryObject(
+        directory_handle: *mut HANDLE,
+        desired_access: u32,
+        object_attributes: *const OBJECT_ATTRIBUTES,
+    ) -> NTSTATUS;
+
+    fn NtQueryDirectoryObject(
+        directory_handle: HANDLE,
+        buffer: *mut c_void,
+        length: u32,
+        return_single_entry: BOOLEAN,
+        restart_scan: BOOLEAN,
+        context: *mut u32,
+        return_length: *mut u32,
+    ) -> NTSTATUS;
+}
+
 /// Return a pseudo handle to the current process.
 /// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getcurrentprocess) for this function.
 pub fn get_current_process() -> isize {
     unsafe { GetCurrentProcess() }.0
 }
 
+/// Return a pseudo handle to the current thread.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getcurrentthread) for this function.
+pub fn get_current_thread() -> isize {
+    unsafe { GetCurrentThread() }.0
+}
+
 #[derive(Debug, Clone)]
 pub struct ProcessMemoryCounters {
     pub page_fault_count: u32,
@@ -70,3 +144,566 @@ pub fn get_process_affinity_mask(process_handle: isize) -> Result<(usize, usize)
     .map_err(|e| e.into())
     .map(|_| (process_affinity_mask, system_affinity_mask))
 }
+
+/// Retrieves the priority class of the specified process, which must be opened with
+/// `PROCESS_QUERY_LIMITED_INFORMATION` access (for example, to verify that the priority class
+/// limit of a job took effect on its processes).
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getpriorityclass) for this function.
+pub fn get_priority_class(process_handle: isize) -> Result<PriorityClass, io::Error> {
+    let value = unsafe { GetPriorityClass(HANDLE(process_handle)) };
+
+    if value == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    PriorityClass::try_from(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Return the number of active logical processors in the system, across all processor groups.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getactiveprocessorcount) for this function.
+pub fn get_active_processor_count() -> u32 {
+    unsafe { GetActiveProcessorCount(ALL_PROCESSOR_GROUPS) }
+}
+
+/// Return the number of active logical processors in the processor group `group`,
+/// which is the number of valid bits in an affinity mask for this group.
+/// Returns 0 if the group does not exist.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getactiveprocessorcount) for this function.
+pub fn get_group_active_processor_count(group: u16) -> u32 {
+    unsafe { GetActiveProcessorCount(group) }
+}
+
+/// Return the maximum number of logical processors the system can have, across all
+/// processor groups (including processors which can be hot-added).
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getmaximumprocessorcount) for this function.
+pub fn get_maximum_processor_count() -> u32 {
+    unsafe { GetMaximumProcessorCount(ALL_PROCESSOR_GROUPS) }
+}
+
+/// Return the maximum number of logical processors the processor group `group` can have.
+/// Returns 0 if the group does not exist.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getmaximumprocessorcount) for this function.
+pub fn get_group_maximum_processor_count(group: u16) -> u32 {
+    unsafe { GetMaximumProcessorCount(group) }
+}
+
+/// Return the number of active processor groups in the system.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getactiveprocessorgroupcount) for this function.
+pub fn get_active_processor_group_count() -> u16 {
+    unsafe { GetActiveProcessorGroupCount() }
+}
+
+/// Return the maximum number of processor groups the system can have.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-getmaximumprocessorgroupcount) for this function.
+pub fn get_maximum_processor_group_count() -> u16 {
+    unsafe { GetMaximumProcessorGroupCount() }
+}
+
+/// Return the page size of the system, in bytes.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/sysinfoapi/nf-sysinfoapi-getsysteminfo) for this function.
+pub fn get_page_size() -> usize {
+    let mut info = SYSTEM_INFO::default();
+    unsafe { GetSystemInfo(&mut info) };
+
+    info.dwPageSize as usize
+}
+
+/// Retrieves the processor group affinity of the specified thread, as an affinity mask and a group number.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/processtopologyapi/nf-processtopologyapi-getthreadgroupaffinity) for this function.
+pub fn get_thread_group_affinity(thread_handle: isize) -> Result<(usize, u16), io::Error> {
+    let mut affinity = GROUP_AFFINITY::default();
+
+    unsafe { GetThreadGroupAffinity(HANDLE(thread_handle), &mut affinity) }.ok()?;
+
+    Ok((affinity.Mask, affinity.Group))
+}
+
+/// Sets the processor group affinity of the specified thread to the processors in `mask` of `group`,
+/// and return the previous affinity mask and group number.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/processtopologyapi/nf-processtopologyapi-setthreadgroupaffinity) for this function.
+pub fn set_thread_group_affinity(
+    thread_handle: isize,
+    mask: usize,
+    group: u16,
+) -> Result<(usize, u16), io::Error> {
+    let affinity = GROUP_AFFINITY {
+        Mask: mask,
+        Group: group,
+        ..Default::default()
+    };
+    let mut previous = GROUP_AFFINITY::default();
+
+    unsafe { SetThreadGroupAffinity(HANDLE(thread_handle), &affinity, Some(&mut previous)) }
+        .ok()?;
+
+    Ok((previous.Mask, previous.Group))
+}
+
+#[derive(Debug, Clone)]
+pub struct ThreadTimes {
+    pub creation_time: SystemTime,
+    /// `None` if the thread has not exited.
+    pub exit_time: Option<SystemTime>,
+    pub kernel_time: Duration,
+    pub user_time: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessTimes {
+    pub creation_time: SystemTime,
+    /// `None` if the process has not exited.
+    pub exit_time: Option<SystemTime>,
+    pub kernel_time: Duration,
+    pub user_time: Duration,
+}
+
+/// The number of 100ns intervals between 1601-01-01 (the `FILETIME` epoch) and the Unix epoch.
+const FILETIME_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+/// Convert a time in 100ns intervals (as used by job objects and `FILETIME`s) to a `Duration`.
+/// Negative values are treated as zero.
+pub(crate) fn duration_from_100ns(value: i64) -> Duration {
+    Duration::from_nanos(value.max(0) as u64 * 100)
+}
+
+/// Convert a `Duration` to a time in 100ns intervals, saturating at `i64::MAX`.
+pub(crate) fn duration_to_100ns(duration: Duration) -> i64 {
+    i64::try_from(duration.as_nanos() / 100).unwrap_or(i64::MAX)
+}
+
+fn filetime_to_u64(time: FILETIME) -> u64 {
+    (time.dwHighDateTime as u64) << 32 | time.dwLowDateTime as u64
+}
+
+fn filetime_to_system_time(time: FILETIME) -> SystemTime {
+    let intervals = filetime_to_u64(time);
+
+    if intervals >= FILETIME_UNIX_EPOCH {
+        SystemTime::UNIX_EPOCH + Duration::from_nanos((intervals - FILETIME_UNIX_EPOCH) * 100)
+    } else {
+        SystemTime::UNIX_EPOCH - Duration::from_nanos((FILETIME_UNIX_EPOCH - intervals) * 100)
+    }
+}
+
+/// Retrieves timing information for the specified thread.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getthreadtimes) for this function.
+pub fn get_thread_times(thread_handle: isize) -> Result<ThreadTimes, io::Error> {
+    let mut creation_time = FILETIME::default();
+    let mut exit_time = FILETIME::default();
+    let mut kernel_time = FILETIME::default();
+    let mut user_time = FILETIME::default();
+
+    unsafe {
+        GetThreadTimes(
+            HANDLE(thread_handle),
+            &mut creation_time,
+            &mut exit_time,
+            &mut kernel_time,
+            &mut user_time,
+        )
+    }?;
+
+    Ok(ThreadTimes {
+        creation_time: filetime_to_system_time(creation_time),
+        exit_time: (filetime_to_u64(exit_time) != 0).then(|| filetime_to_system_time(exit_time)),
+        kernel_time: duration_from_100ns(filetime_to_u64(kernel_time) as i64),
+        user_time: duration_from_100ns(filetime_to_u64(user_time) as i64),
+    })
+}
+
+/// Retrieves timing information for the specified process.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getprocesstimes) for this function.
+pub fn get_process_times(process_handle: isize) -> Result<ProcessTimes, io::Error> {
+    let mut creation_time = FILETIME::default();
+    let mut exit_time = FILETIME::default();
+    let mut kernel_time = FILETIME::default();
+    let mut user_time = FILETIME::default();
+
+    unsafe {
+        GetProcessTimes(
+            HANDLE(process_handle),
+            &mut creation_time,
+            &mut exit_time,
+            &mut kernel_time,
+            &mut user_time,
+        )
+    }?;
+
+    Ok(ProcessTimes {
+        creation_time: filetime_to_system_time(creation_time),
+        exit_time: (filetime_to_u64(exit_time) != 0).then(|| filetime_to_system_time(exit_time)),
+        kernel_time: duration_from_100ns(filetime_to_u64(kernel_time) as i64),
+        user_time: duration_from_100ns(filetime_to_u64(user_time) as i64),
+    })
+}
+
+/// Open the process with the given pid, for querying it (and, if allowed, terminating it).
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-openprocess) for this function.
+pub fn open_process(pid: u32) -> Result<OpenedProcess, io::Error> {
+    OpenedProcess::open(pid)
+}
+
+#[derive(Debug, Clone)]
+pub struct SystemMemoryInfo {
+    /// The approximate percentage of physical memory that is in use.
+    pub memory_load: u32,
+    pub total_physical: u64,
+    pub available_physical: u64,
+    /// The current committed memory limit of the system.
+    pub total_commit: u64,
+    /// The amount of memory which the current process can commit.
+    pub available_commit: u64,
+}
+
+/// Retrieves information about the current usage of physical and committed memory of the system.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/sysinfoapi/nf-sysinfoapi-globalmemorystatusex) for this function.
+pub fn get_system_memory_info() -> Result<SystemMemoryInfo, io::Error> {
+    let mut status = MEMORYSTATUSEX {
+        dwLength: mem::size_of::<MEMORYSTATUSEX>() as u32,
+        ..Default::default()
+    };
+
+    unsafe { GlobalMemoryStatusEx(&mut status) }?;
+
+    Ok(SystemMemoryInfo {
+        memory_load: status.dwMemoryLoad,
+        total_physical: status.ullTotalPhys,
+        available_physical: status.ullAvailPhys,
+        total_commit: status.ullTotalPageFile,
+        available_commit: status.ullAvailPageFile,
+    })
+}
+
+/// Sets the minimum and maximum working set sizes of the specified process.
+/// If `hard_min` (or `hard_max`) is `true`, the working set of the process never goes below the minimum
+/// (or above the maximum) size, otherwise the sizes are only used as a hint by the memory manager.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-setprocessworkingsetsizeex) for this function.
+pub fn set_process_working_set_size(
+    process_handle: isize,
+    min: usize,
+    max: usize,
+    hard_min: bool,
+    hard_max: bool,
+) -> Result<(), io::Error> {
+    let flags = if hard_min {
+        QUOTA_LIMITS_HARDWS_MIN_ENABLE
+    } else {
+        QUOTA_LIMITS_HARDWS_MIN_DISABLE
+    } | if hard_max {
+        QUOTA_LIMITS_HARDWS_MAX_ENABLE
+    } else {
+        QUOTA_LIMITS_HARDWS_MAX_DISABLE
+    };
+
+    unsafe { SetProcessWorkingSetSizeEx(HANDLE(process_handle), min, max, flags) }
+        .map_err(|e| e.into())
+}
+
+/// Retrieves the minimum and maximum working set sizes of the specified process,
+/// and whether each of them is a hard limit (see `set_process_working_set_size`).
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-getprocessworkingsetsizeex) for this function.
+pub fn get_process_working_set_size(
+    process_handle: isize,
+) -> Result<(usize, usize, bool, bool), io::Error> {
+    let mut min = 0usize;
+    let mut max = 0usize;
+    let mut flags = 0u32;
+
+    unsafe { GetProcessWorkingSetSizeEx(HANDLE(process_handle), &mut min, &mut max, &mut flags) }
+        .ok()?;
+
+    Ok((
+        min,
+        max,
+        flags & QUOTA_LIMITS_HARDWS_MIN_ENABLE.0 != 0,
+        flags & QUOTA_LIMITS_HARDWS_MAX_ENABLE.0 != 0,
+    ))
+}
+
+/// Removes as many pages as possible from the working set of the specified process,
+/// which must be opened with `PROCESS_QUERY_LIMITED_INFORMATION` and `PROCESS_SET_QUOTA` access.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-emptyworkingset) for this function.
+pub fn empty_working_set(process_handle: isize) -> Result<(), io::Error> {
+    unsafe { K32EmptyWorkingSet(HANDLE(process_handle)) }
+        .ok()
+        .map_err(|e| e.into())
+}
+
+/// Begin background processing mode for the current process, lowering its I/O and memory priority
+/// (and its scheduling priority) while it does background work, like indexing or backups.
+/// Background mode can only be set for the current process. Does nothing if the process is
+/// already in background mode.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setpriorityclass) for this function.
+pub fn enter_background_mode() -> Result<(), io::Error> {
+    set_background_mode(PROCESS_MODE_BACKGROUND_BEGIN)
+}
+
+/// End background processing mode for the current process (see `enter_background_mode`),
+/// restoring its previous priorities. Does nothing if the process is not in background mode.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-setpriorityclass) for this function.
+pub fn leave_background_mode() -> Result<(), io::Error> {
+    set_background_mode(PROCESS_MODE_BACKGROUND_END)
+}
+
+fn set_background_mode(mode: PROCESS_CREATION_FLAGS) -> Result<(), io::Error> {
+    match unsafe { SetPriorityClass(GetCurrentProcess(), mode) } {
+        Err(err)
+            if err.code() == ERROR_PROCESS_MODE_ALREADY_BACKGROUND.to_hresult()
+                || err.code() == ERROR_PROCESS_MODE_NOT_BACKGROUND.to_hresult() =>
+        {
+            Ok(())
+        }
+        res => res.map_err(|e| e.into()),
+    }
+}
+
+/// The I/O priority of a process, which applies to the I/O requests of all of its threads
+/// (see `set_process_io_priority`).
+#[cfg(feature = "io-priority")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum IoPriority {
+    /// Used for background work, like the I/O of processes in background mode.
+    VeryLow,
+    Low,
+    /// The default I/O priority.
+    Normal,
+    /// Requires the `SE_INC_BASE_PRIORITY_NAME` privilege.
+    High,
+}
+
+#[cfg(feature = "io-priority")]
+impl TryFrom<u32> for IoPriority {
+    type Error = u32;
+
+    /// Convert a raw I/O priority hint (as returned by `NtQueryInformationProcess`).
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(IoPriority::VeryLow),
+            1 => Ok(IoPriority::Low),
+            2 => Ok(IoPriority::Normal),
+            3 => Ok(IoPriority::High),
+            _ => Err(value),
+        }
+    }
+}
+
+/// Sets the I/O priority of the specified process, which must be opened with `PROCESS_SET_INFORMATION` access.
+/// Unlike the I/O rate controls of a job, this only changes the order in which the I/O requests
+/// of the process are served, without limiting them.
+/// This uses `ProcessIoPriority`, which is not documented.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winternl/nf-winternl-ntqueryinformationprocess) for the related query function.
+#[cfg(feature = "io-priority")]
+pub fn set_process_io_priority(
+    process_handle: isize,
+    priority: IoPriority,
+) -> Result<(), io::Error> {
+    let value = priority as u32;
+
+    unsafe {
+        NtSetInformationProcess(
+            HANDLE(process_handle),
+            ProcessIoPriority,
+            &value as *const _ as *const c_void,
+            mem::size_of_val(&value) as u32,
+        )
+    }
+    .ok()
+    .map_err(|e| e.into())
+}
+
+/// Retrieves the I/O priority of the specified process (see `set_process_io_priority`),
+/// which must be opened with `PROCESS_QUERY_INFORMATION` or `PROCESS_QUERY_LIMITED_INFORMATION` access.
+/// This uses `ProcessIoPriority`, which is not documented.
+/// See also [Microsoft Docs](https://docs.microsoft.com/en-us/windows/win32/api/winternl/nf-winternl-ntqueryinformationprocess) for this function.
+#[cfg(feature = "io-priority")]
+pub fn get_process_io_priority(process_handle: isize) -> Result<IoPriority, io::Error> {
+    let mut value = 0u32;
+
+    unsafe {
+        NtQueryInformationProcess(
+            HANDLE(process_handle),
+            ProcessIoPriority,
+            &mut value as *mut _ as *mut c_void,
+            mem::size_of_val(&value) as u32,
+            ptr::null_mut(),
+        )
+    }
+    .ok()?;
+
+    IoPriority::try_from(value).map_err(|value| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid I/O priority {}", value),
+        )
+    })
+}
+
+pub(crate) fn unicode_string_to_string(string: &UNICODE_STRING) -> String {
+    if string.Buffer.is_null() {
+        return String::new();
+    }
+
+    // `Length` is in bytes.
+    let wide = unsafe {
+        std::slice::from_raw_parts(
+            string.Buffer.0,
+            string.Length as usize / mem::size_of::<u16>(),
+        )
+    };
+
+    String::from_utf16_lossy(wide)
+}
+
+/// Return the names of the job objects in the object directory `namespace`.
+///
+/// Named objects of the current session are in `\Sessions\<session id>\BaseNamedObjects`
+/// (or in `\BaseNamedObjects` for session 0), and global objects (created with a `Global\` prefix)
+/// are in `\BaseNamedObjects`. The names do not include the prefix, so jobs in the directory
+/// of the current session can be opened with `Job::open(name)`, and global ones with
+/// `Job::open(&format!("Global\\{}", name))`.
+///
+/// See also [Microsoft Docs](https://learn.microsoft.com/en-us/windows/win32/devnotes/ntquerydirectoryobject) for this function.
+pub fn list_named_jobs(namespace: &str) -> Result<Vec<String>, io::Error> {
+    let mut wide: Vec<u16> = namespace.encode_utf16().collect();
+    let name = UNICODE_STRING {
+        Length: (wide.len() * mem::size_of::<u16>()) as u16,
+        MaximumLength: (wide.len() * mem::size_of::<u16>()) as u16,
+        Buffer: windows::core::PWSTR(wide.as_mut_ptr()),
+    };
+    let attributes = OBJECT_ATTRIBUTES {
+        Length: mem::size_of::<OBJECT_ATTRIBUTES>() as u32,
+        ObjectName: &name,
+        ..Default::default()
+    };
+
+    let mut directory = HANDLE::default();
+    unsafe { NtOpenDirectoryObject(&mut directory, DIRECTORY_QUERY, &attributes) }.ok()?;
+    let directory = OwnedHandle(directory);
+
+    // Use `u64`s, so the buffer is aligned for the entries.
+    let mut buf = vec![0u64; 8 * 1024];
+    let mut context = 0;
+    let mut restart = true;
+    let mut names = vec![];
+
+    loop {
+        let status = unsafe {
+            NtQueryDirectoryObject(
+                directory.0,
+                buf.as_mut_ptr() as *mut c_void,
+                mem::size_of_val(buf.as_slice()) as u32,
+                BOOLEAN(0),
+                BOOLEAN(restart as u8),
+                &mut context,
+                ptr::null_mut(),
+            )
+        };
+        restart = false;
+
+        if status == STATUS_NO_MORE_ENTRIES {
+            break;
+        }
+        status.ok()?;
+
+        // The entries are terminated by an empty entry, and their strings point into `buf`.
+        let entries = buf.as_ptr() as *const ObjectDirectoryInformation;
+
+        for index in 0.. {
+            let entry = unsafe { &*entries.add(index) };
+
+            if entry.name.Buffer.is_null() {
+                break;
+            }
+
+            if unicode_string_to_string(&entry.type_name) == "Job" {
+                names.push(unicode_string_to_string(&entry.name));
+            }
+        }
+
+        if status != STATUS_MORE_ENTRIES {
+            break;
+        }
+    }
+
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use rusty_fork::rusty_fork_test;
+
+    use super::*;
+
+    #[test]
+    fn current_thread_info() {
+        let thread = get_current_thread();
+
+        let (mask, group) = get_thread_group_affinity(thread).unwrap();
+        assert_ne!(mask, 0);
+        assert_eq!(
+            set_thread_group_affinity(thread, mask, group).unwrap(),
+            (mask, group)
+        );
+
+        let times = get_thread_times(thread).unwrap();
+        assert!(times.exit_time.is_none());
+        assert!(times.creation_time <= SystemTime::now());
+    }
+
+    #[test]
+    fn processor_counts() {
+        let groups = get_active_processor_group_count();
+        assert!(groups >= 1);
+        assert!(groups <= get_maximum_processor_group_count());
+
+        let per_group: u32 = (0..groups).map(get_group_active_processor_count).sum();
+        assert_eq!(per_group, get_active_processor_count());
+        assert!(get_group_active_processor_count(0) <= get_group_maximum_processor_count(0));
+        assert!(get_active_processor_count() <= get_maximum_processor_count());
+
+        assert_eq!(get_group_active_processor_count(groups), 0);
+    }
+
+    #[test]
+    fn memory_info() {
+        let info = get_system_memory_info().unwrap();
+        assert!(info.available_physical <= info.total_physical);
+
+        let process = get_current_process();
+        let (min, max, _, _) = get_process_working_set_size(process).unwrap();
+        assert!(min <= max);
+
+        empty_working_set(process).unwrap();
+    }
+
+    #[test]
+    fn named_jobs() {
+        assert!(list_named_jobs(r"\BaseNamedObjects").is_ok());
+        assert!(list_named_jobs(r"\NoSuchDirectory").is_err());
+    }
+
+    rusty_fork_test! {
+        #[test]
+        fn background_mode() {
+            enter_background_mode().unwrap();
+            enter_background_mode().unwrap();
+
+            leave_background_mode().unwrap();
+            leave_background_mode().unwrap();
+        }
+    }
+
+    #[cfg(feature = "io-priority")]
+    rusty_fork_test! {
+        #[test]
+        fn io_priority() {
+            let process = get_current_process();
+            assert_eq!(get_process_io_priority(process).unwrap(), IoPriority::Normal);
+
+            set_process_io_priority(process, IoPriority::Low).unwrap();
+            assert_eq!(get_process_io_priority(process).unwrap(), IoPriority::Low);
+        }
+    }
+}
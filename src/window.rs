@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+
+use windows::Win32::{
+    Foundation::{BOOL, HWND, LPARAM, WPARAM},
+    UI::WindowsAndMessaging::{
+        EnumWindows, GetWindow, GetWindowThreadProcessId, PostMessageW, GW_OWNER, WM_CLOSE,
+    },
+};
+
+use crate::{Job, JobError, Pid};
+
+struct CloseWindows<'a> {
+    pids: &'a HashSet<u32>,
+    closed: usize,
+}
+
+unsafe extern "system" fn close_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let close_windows = &mut *(lparam.0 as *mut CloseWindows);
+
+    let mut pid = 0;
+    GetWindowThreadProcessId(hwnd, Some(&mut pid));
+
+    // Only close top-level windows which are not owned by another window.
+    if close_windows.pids.contains(&pid) && GetWindow(hwnd, GW_OWNER).0 == 0 {
+        match PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0)) {
+            Ok(()) => close_windows.closed += 1,
+            Err(err) => {
+                log_debug!("Failed to close window {:?} of {}: {}", hwnd, pid, err);
+            }
+        }
+    }
+
+    true.into()
+}
+
+/// Post a `WM_CLOSE` message to the top-level windows of `pids`.
+/// Return the number of windows which were sent the message.
+pub(crate) fn close_windows(pids: &[u32]) -> usize {
+    let pids = pids.iter().copied().collect();
+    let mut close_windows = CloseWindows {
+        pids: &pids,
+        closed: 0,
+    };
+
+    if let Err(err) = unsafe {
+        EnumWindows(
+            Some(close_window),
+            LPARAM(&mut close_windows as *mut _ as isize),
+        )
+    } {
+        log_warn!("Failed to enumerate windows: {}", err);
+    }
+
+    close_windows.closed
+}
+
+impl Job {
+    /// Ask the GUI processes of the job to exit, by posting a `WM_CLOSE` message
+    /// to the top-level (unowned) windows of the job's processes.
+    /// This is the GUI analogue of `send_ctrl_break`.
+    ///
+    /// Return the number of windows which were sent the message.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self), fields(job = self.handle.0), ret, err)
+    )]
+    pub fn close_windows(&self) -> Result<usize, JobError> {
+        let pids: Vec<u32> = self
+            .query_process_id_list()?
+            .into_iter()
+            .map(Pid::as_u32)
+            .collect();
+
+        Ok(close_windows(&pids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Job;
+
+    #[test]
+    fn close_windows_of_empty_job() {
+        let job = Job::create().unwrap();
+
+        assert_eq!(job.close_windows().unwrap(), 0);
+    }
+}
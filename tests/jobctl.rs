@@ -0,0 +1,67 @@
+//! Runs the `jobctl` binary against real job objects.
+#![cfg(windows)]
+
+use std::process::{Command, Output};
+
+use win32job::{Job, JobCommand};
+
+fn jobctl(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_jobctl"))
+        .args(args)
+        .output()
+        .expect("failed to run jobctl")
+}
+
+fn job_name(test: &str) -> String {
+    format!("win32job-test-jobctl-{}-{}", test, std::process::id())
+}
+
+#[test]
+fn run() {
+    let name = job_name("run");
+
+    let output = jobctl(&[
+        "run",
+        &name,
+        "--kill-on-close",
+        "--cpu-rate",
+        "50",
+        "--",
+        "cmd",
+        "/C",
+        "exit 3",
+    ]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(3), "{}", stdout);
+    // The command ran inside the job from its start.
+    assert!(stdout.contains("total processes:      1"), "{}", stdout);
+    assert!(stdout.contains("active processes:     0"), "{}", stdout);
+}
+
+#[test]
+fn query() {
+    let name = job_name("query");
+    let job = Job::create_named(&name).unwrap();
+    let status = JobCommand::new(&job, "cmd")
+        .args(["/C", "exit 0"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = jobctl(&["query", &name]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("total processes:      1"), "{}", stdout);
+    assert!(stdout.contains("active processes:     0"), "{}", stdout);
+    assert!(!stdout.contains("process: "), "{}", stdout);
+}
+
+#[test]
+fn query_missing_job() {
+    let output = jobctl(&["query", &job_name("missing")]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("jobctl: "));
+}